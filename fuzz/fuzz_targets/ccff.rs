@@ -0,0 +1,16 @@
+//! The CCFF decoders must never panic, whether or not the file is
+//! well-formed.
+
+#![no_main]
+
+use std::io::Cursor;
+
+use calypso_filety::ccff::{hl::ContainerFile, ll::CcffHeader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Cursor::new(data);
+    if let Ok(header) = CcffHeader::read(&mut input) {
+        let _ = ContainerFile::decode(header).read_all(&mut input);
+    }
+});
@@ -0,0 +1,31 @@
+//! The lexer must never panic, and its tokens must cover every byte of the
+//! source.
+
+#![no_main]
+
+use std::sync::Arc;
+
+use calypso_base::ui::{termcolor::ColorChoice, Emitters};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::{diagnostic::SourceMgr, report::GlobalReportingCtxt};
+use calypso_parsing::lexer::Lexer;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let gcx = Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(ColorChoice::Never, ColorChoice::Never)),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    });
+
+    let mut hi = 0;
+    for token in Lexer::new(0, source, gcx) {
+        assert_eq!(token.span().lo(), hi);
+        assert_eq!(
+            token.value().1,
+            &source[token.span().lo()..token.span().hi()]
+        );
+        hi = token.span().hi();
+    }
+    assert_eq!(hi, source.len());
+});
@@ -0,0 +1,24 @@
+//! The Pratt parser must never panic, whether or not the source is well-formed.
+
+#![no_main]
+
+use std::sync::Arc;
+
+use calypso_base::ui::{termcolor::ColorChoice, Emitters};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::{diagnostic::SourceMgr, report::GlobalReportingCtxt};
+use calypso_parsing::parser::pratt;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let gcx = Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(ColorChoice::Never, ColorChoice::Never)),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    });
+
+    // Parse errors are also turned into diagnostics, which must not panic
+    // either.
+    let _ = pratt::parse_expr(0, source, &gcx);
+    let _ = pratt::parse_program(0, source, &gcx);
+});
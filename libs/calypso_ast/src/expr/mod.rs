@@ -1,12 +1,109 @@
 use std::fmt::{self, Display};
 
-use calypso_base::span::Spanned;
+use calypso_base::span::{Span, Spanned};
+use calypso_base::symbol::{PotentiallyInterned, Symbol};
+
+use crate::pattern::Pattern;
+use crate::stmt::Block;
+
+/// An expression, with its location in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr<'tok> {
+    pub kind: ExprKind<'tok>,
+    /// The span of the expression. This does not include any parentheses
+    /// directly around it.
+    pub span: Span,
+    /// The ID of the file the expression is in.
+    pub file_id: usize,
+}
+
+impl<'tok> Expr<'tok> {
+    #[must_use]
+    pub fn new(kind: ExprKind<'tok>, span: Span, file_id: usize) -> Self {
+        Self {
+            kind,
+            span,
+            file_id,
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum Expr<'tok> {
-    BinOp(Box<Expr<'tok>>, BinOpKind, Box<Expr<'tok>>),
+pub enum ExprKind<'tok> {
+    BinOp(Box<Expr<'tok>>, Spanned<BinOpKind>, Box<Expr<'tok>>),
     UnOp(Spanned<UnOpKind>, Box<Expr<'tok>>),
+    /// A literal. Its span is that of the containing [`Expr`].
     Primary(Primary<'tok>),
+    /// A variable, e.g. `x`.
+    Ident(Symbol),
+    /// A function call, e.g. `f(a, b)`.
+    Call(Box<Expr<'tok>>, Vec<Expr<'tok>>),
+    /// Access of a field or method, e.g. `a.b`. A method call, e.g.
+    /// `1.to(n)`, is a call of a field access.
+    Field(Box<Expr<'tok>>, Spanned<Symbol>),
+    /// Pattern matching, e.g. `case n, acc do 0, acc -> acc end`. The first
+    /// arm whose patterns match the scrutinees (and whose guard is true) is
+    /// evaluated.
+    Case {
+        scrutinees: Vec<Expr<'tok>>,
+        arms: Vec<CaseArm<'tok>>,
+    },
+    /// A string literal containing interpolations, e.g. `"a ${b} c"`.
+    Interpolated(Vec<StringPart<'tok>>),
+    /// A scope block, e.g. `do -> let x = 1 end`. Variables bound within it
+    /// are only visible until its `end`.
+    Do(Block<'tok>),
+    /// A conditional, e.g. `if n == 0 -> 1 else -> n`. Without an `else`
+    /// branch, its value is `:unit`.
+    If {
+        cond: Box<Expr<'tok>>,
+        then_branch: Box<Expr<'tok>>,
+        else_branch: Option<Box<Expr<'tok>>>,
+    },
+    /// A loop that runs its body as long as the condition is true, e.g.
+    /// `while n > 0 -> n = n - 1`.
+    While {
+        cond: Box<Expr<'tok>>,
+        body: Box<Expr<'tok>>,
+    },
+    /// A loop over the values of an iterator, e.g. `for i in 1.to(n) -> f(i)`.
+    For {
+        pattern: Pattern<'tok>,
+        iter: Box<Expr<'tok>>,
+        body: Box<Expr<'tok>>,
+    },
+    /// A loop that runs its body until it's exited with `break` (or `ret`).
+    Loop(Box<Expr<'tok>>),
+    /// Exit the innermost loop.
+    Break,
+    /// Skip to the next iteration of the innermost loop.
+    Continue,
+    /// Return from the function, with `:unit` if there's no value.
+    Ret(Option<Box<Expr<'tok>>>),
+    /// A placeholder for an expression that could not be parsed. The syntax
+    /// error has already been reported, so later passes should skip it
+    /// without reporting any more errors about it.
+    Error,
+}
+
+/// An arm of a `case` expression, e.g. `n, acc where n > 0 -> n * acc`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaseArm<'tok> {
+    /// The patterns matched against each of the scrutinees, in order.
+    pub patterns: Vec<Pattern<'tok>>,
+    /// The guard after `where`, if any.
+    pub guard: Option<Box<Expr<'tok>>>,
+    pub body: Box<Expr<'tok>>,
+    pub span: Span,
+}
+
+/// A part of an interpolated string literal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StringPart<'tok> {
+    /// Text that is not an interpolation, with escapes decoded.
+    Fragment(PotentiallyInterned<'tok>),
+    /// An interpolation (`${ ... }`).
+    Interpolation(Box<Expr<'tok>>),
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -73,10 +170,23 @@ impl Display for UnOpKind {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Primary<'tok> {
     Number(&'tok str, Radix, Option<Suffix>),
     Bool(bool),
+    /// The value of a string literal without interpolations (including raw
+    /// and multi-line string literals), without the quotes and with escapes
+    /// decoded.
+    String(PotentiallyInterned<'tok>),
+    /// The value of a character literal, with escapes decoded.
+    Char(char),
+    /// The value of a byte string literal (e.g. `b"\x7FELF"`), with escapes
+    /// decoded.
+    Bytes(Vec<u8>),
+    /// The value of a byte literal (e.g. `b'x'`), with escapes decoded.
+    Byte(u8),
+    /// An atom, e.g. `:foo` or `:"foo bar"`.
+    Atom(Symbol),
 }
 
 impl<'tok> Primary<'tok> {
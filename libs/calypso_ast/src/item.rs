@@ -0,0 +1,55 @@
+use calypso_base::span::{Span, Spanned};
+use calypso_base::symbol::Symbol;
+
+use crate::expr::Expr;
+use crate::ty::Ty;
+
+/// An item, e.g. a function declaration, with its location in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item<'tok> {
+    pub kind: ItemKind<'tok>,
+    pub span: Span,
+    /// The ID of the file the item is in.
+    pub file_id: usize,
+}
+
+impl<'tok> Item<'tok> {
+    #[must_use]
+    pub fn new(kind: ItemKind<'tok>, span: Span, file_id: usize) -> Self {
+        Self {
+            kind,
+            span,
+            file_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemKind<'tok> {
+    /// A function declaration, e.g. `pub fn f(n: uint): uint -> n + 1`.
+    Fn(FnDecl<'tok>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FnDecl<'tok> {
+    /// Whether the function is visible outside of its module (`pub`).
+    pub public: bool,
+    pub name: Spanned<Symbol>,
+    pub params: Vec<Param<'tok>>,
+    /// The return type, if it's annotated.
+    pub ret: Option<Ty>,
+    /// The body, which is a `do` block if the function is declared with
+    /// `-> do ... end`.
+    pub body: Box<Expr<'tok>>,
+}
+
+/// A parameter of a function, e.g. `acc: uint \\ 1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param<'tok> {
+    pub name: Spanned<Symbol>,
+    /// The type of the parameter, if it's annotated.
+    pub ty: Option<Ty>,
+    /// The default argument, used if the parameter is not given in a call.
+    pub default: Option<Box<Expr<'tok>>>,
+    pub span: Span,
+}
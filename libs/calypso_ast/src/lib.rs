@@ -1,8 +1,10 @@
 #![doc(html_root_url = "https://calypso-lang.github.io/rustdoc/calypso_ast/index.html")]
 #![warn(clippy::pedantic)]
 
-// todo(@ThePuzzlemaker: parse|frame):
-//   add span information to AST and parser
 pub mod expr;
+pub mod item;
+pub mod pattern;
 pub mod pretty;
+pub mod stmt;
 pub mod traverse;
+pub mod ty;
@@ -0,0 +1,49 @@
+use calypso_base::span::{Span, Spanned};
+use calypso_base::symbol::Symbol;
+
+use crate::expr::Primary;
+
+/// A pattern, e.g. on the left-hand side of a `let` binding or in an arm of
+/// a `case` expression, with its location in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern<'tok> {
+    pub kind: PatternKind<'tok>,
+    pub span: Span,
+    /// The ID of the file the pattern is in.
+    pub file_id: usize,
+}
+
+impl<'tok> Pattern<'tok> {
+    #[must_use]
+    pub fn new(kind: PatternKind<'tok>, span: Span, file_id: usize) -> Self {
+        Self {
+            kind,
+            span,
+            file_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternKind<'tok> {
+    /// Bind the value to a variable, e.g. `x` or `mut x`.
+    ///
+    /// A constructor without any fields (e.g. `None`) looks the same, so
+    /// it's parsed as a binding until names are resolved.
+    Binding { name: Symbol, mutable: bool },
+    /// Ignore the value (`_`).
+    Wildcard,
+    /// Match a value equal to a literal, e.g. `0` or `:ok`.
+    Literal(Primary<'tok>),
+    /// Match a variant of a type and its fields, e.g. `Some(value)`.
+    Constructor {
+        name: Spanned<Symbol>,
+        fields: Vec<Pattern<'tok>>,
+    },
+    /// Match any of the alternatives, e.g. `0 | 1`. Each alternative must
+    /// bind the same variables.
+    Or(Vec<Pattern<'tok>>),
+    /// A placeholder for a pattern that could not be parsed, like
+    /// [`ExprKind::Error`](crate::expr::ExprKind::Error).
+    Error,
+}
@@ -1,8 +1,14 @@
 use std::fmt::{self, Display, Write};
 
+use unicode_xid::UnicodeXID;
+
 use crate::{
-    expr::{Expr, Primary},
+    expr::{CaseArm, Expr, ExprKind, Primary, StringPart},
+    item::{Item, ItemKind},
+    pattern::{Pattern, PatternKind},
+    stmt::{Block, Stmt, StmtKind},
     traverse::Visitor,
+    ty::{Ty, TyKind},
 };
 use calypso_error::CalResult;
 
@@ -18,8 +24,8 @@ impl Display for PrettyPrinter {
 
 impl Visitor for PrettyPrinter {
     fn visit_primary(&mut self, x: &Primary) -> CalResult<()> {
-        match *x {
-            Primary::Number(num, radix, suffix) => write!(
+        match x {
+            &Primary::Number(num, radix, suffix) => write!(
                 self.0,
                 "{}{}{}",
                 radix,
@@ -27,26 +33,354 @@ impl Visitor for PrettyPrinter {
                 suffix.map(|s| format!("{}", s)).unwrap_or_default()
             )?,
             Primary::Bool(b) => write!(self.0, "{}", b)?,
+            Primary::String(s) => write!(self.0, "\"{}\"", escape(s.as_ref()))?,
+            // Double quotes don't need to be escaped in character literals,
+            // but single quotes do.
+            Primary::Char('"') => write!(self.0, "'\"'")?,
+            Primary::Char('\'') => write!(self.0, "'\\''")?,
+            Primary::Char(ch) => write!(self.0, "'{}'", escape(ch.encode_utf8(&mut [0; 4])))?,
+            Primary::Bytes(bytes) => write!(self.0, "b\"{}\"", escape_bytes(bytes, b'"'))?,
+            Primary::Byte(byte) => write!(self.0, "b'{}'", escape_bytes(&[*byte], b'\''))?,
+            Primary::Atom(atom) => {
+                if is_plain_atom(atom.as_str()) {
+                    write!(self.0, ":{}", atom)?;
+                } else {
+                    write!(self.0, ":\"{}\"", escape(atom.as_str()))?;
+                }
+            }
         }
         Ok(())
     }
 
     fn visit_expr(&mut self, x: &Expr) -> CalResult<()> {
-        match x {
-            Expr::BinOp(left, op, right) => {
-                write!(self.0, "({} ", op)?;
+        match &x.kind {
+            ExprKind::BinOp(left, op, right) => {
+                write!(self.0, "({} ", op.value())?;
                 self.visit_expr(left)?;
                 write!(self.0, " ")?;
                 self.visit_expr(right)?;
                 write!(self.0, ")")?;
             }
-            Expr::Primary(primary) => self.visit_primary(primary)?,
-            Expr::UnOp(op, expr) => {
+            ExprKind::Primary(primary) => self.visit_primary(primary)?,
+            ExprKind::Ident(ident) => write!(self.0, "{}", ident)?,
+            ExprKind::Call(callee, args) => {
+                write!(self.0, "(call ")?;
+                self.visit_expr(callee)?;
+                for arg in args {
+                    write!(self.0, " ")?;
+                    self.visit_expr(arg)?;
+                }
+                write!(self.0, ")")?;
+            }
+            ExprKind::Field(expr, field) => {
+                write!(self.0, "(. ")?;
+                self.visit_expr(expr)?;
+                write!(self.0, " {})", field.value())?;
+            }
+            ExprKind::Case { scrutinees, arms } => {
+                write!(self.0, "(case (")?;
+                for (idx, scrutinee) in scrutinees.iter().enumerate() {
+                    if idx > 0 {
+                        write!(self.0, " ")?;
+                    }
+                    self.visit_expr(scrutinee)?;
+                }
+                write!(self.0, ")")?;
+                for arm in arms {
+                    write!(self.0, " ")?;
+                    self.visit_case_arm(arm)?;
+                }
+                write!(self.0, ")")?;
+            }
+            ExprKind::Do(block) => self.list("do", &block.stmts)?,
+            ExprKind::Interpolated(parts) => {
+                write!(self.0, "\"")?;
+                for part in parts {
+                    match part {
+                        StringPart::Fragment(s) => write!(self.0, "{}", escape(s.as_ref()))?,
+                        StringPart::Interpolation(expr) => {
+                            write!(self.0, "${{")?;
+                            self.visit_expr(expr)?;
+                            write!(self.0, "}}")?;
+                        }
+                    }
+                }
+                write!(self.0, "\"")?;
+            }
+            ExprKind::UnOp(op, expr) => {
                 write!(self.0, "({} ", op.value())?;
                 self.visit_expr(expr)?;
                 write!(self.0, ")")?;
             }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch: None,
+            } => self.exprs("if", &[cond, then_branch])?,
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch: Some(else_branch),
+            } => self.exprs("if", &[cond, then_branch, else_branch])?,
+            ExprKind::While { cond, body } => self.exprs("while", &[cond, body])?,
+            ExprKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                write!(self.0, "(for ")?;
+                self.visit_pattern(pattern)?;
+                write!(self.0, " ")?;
+                self.visit_expr(iter)?;
+                write!(self.0, " ")?;
+                self.visit_expr(body)?;
+                write!(self.0, ")")?;
+            }
+            ExprKind::Loop(body) => self.exprs("loop", &[body])?,
+            ExprKind::Break => self.exprs("break", &[])?,
+            ExprKind::Continue => self.exprs("continue", &[])?,
+            ExprKind::Ret(None) => self.exprs("ret", &[])?,
+            ExprKind::Ret(Some(value)) => self.exprs("ret", &[value])?,
+            ExprKind::Error => write!(self.0, "<error>")?,
+        }
+        Ok(())
+    }
+
+    fn visit_case_arm(&mut self, x: &CaseArm) -> CalResult<()> {
+        write!(self.0, "(arm (")?;
+        for (idx, pattern) in x.patterns.iter().enumerate() {
+            if idx > 0 {
+                write!(self.0, " ")?;
+            }
+            self.visit_pattern(pattern)?;
+        }
+        write!(self.0, ") ")?;
+        if let Some(guard) = &x.guard {
+            write!(self.0, "(where ")?;
+            self.visit_expr(guard)?;
+            write!(self.0, ") ")?;
+        }
+        self.visit_expr(&x.body)?;
+        write!(self.0, ")")?;
+        Ok(())
+    }
+
+    fn visit_stmt(&mut self, x: &Stmt) -> CalResult<()> {
+        match &x.kind {
+            StmtKind::Let { pattern, value } => {
+                write!(self.0, "(let ")?;
+                self.visit_pattern(pattern)?;
+                if let Some(value) = value {
+                    write!(self.0, " ")?;
+                    self.visit_expr(value)?;
+                }
+                write!(self.0, ")")?;
+            }
+            StmtKind::Assign { target, value } => {
+                write!(self.0, "(= {} ", target.value())?;
+                self.visit_expr(value)?;
+                write!(self.0, ")")?;
+            }
+            StmtKind::AssignOp { target, op, value } => {
+                write!(self.0, "({}= {} ", op.value(), target.value())?;
+                self.visit_expr(value)?;
+                write!(self.0, ")")?;
+            }
+            StmtKind::Expr(expr) => self.visit_expr(expr)?,
+            StmtKind::Item(item) => self.visit_item(item)?,
+        }
+        Ok(())
+    }
+
+    fn visit_block(&mut self, x: &Block) -> CalResult<()> {
+        self.list("block", &x.stmts)
+    }
+
+    fn visit_pattern(&mut self, x: &Pattern) -> CalResult<()> {
+        match &x.kind {
+            PatternKind::Binding {
+                name,
+                mutable: false,
+            } => write!(self.0, "{}", name)?,
+            PatternKind::Binding {
+                name,
+                mutable: true,
+            } => write!(self.0, "(mut {})", name)?,
+            PatternKind::Wildcard => write!(self.0, "_")?,
+            PatternKind::Literal(primary) => self.visit_primary(primary)?,
+            PatternKind::Constructor { name, fields } => {
+                write!(self.0, "({}", name.value())?;
+                for field in fields {
+                    write!(self.0, " ")?;
+                    self.visit_pattern(field)?;
+                }
+                write!(self.0, ")")?;
+            }
+            PatternKind::Or(alternatives) => {
+                write!(self.0, "(|")?;
+                for alternative in alternatives {
+                    write!(self.0, " ")?;
+                    self.visit_pattern(alternative)?;
+                }
+                write!(self.0, ")")?;
+            }
+            PatternKind::Error => write!(self.0, "<error>")?,
+        }
+        Ok(())
+    }
+
+    fn visit_item(&mut self, x: &Item) -> CalResult<()> {
+        let ItemKind::Fn(decl) = &x.kind;
+        if decl.public {
+            write!(self.0, "(pub ")?;
+        }
+        write!(self.0, "(fn {} (", decl.name.value())?;
+        for (idx, param) in decl.params.iter().enumerate() {
+            if idx > 0 {
+                write!(self.0, " ")?;
+            }
+            if param.default.is_some() {
+                write!(self.0, "(\\\\ ")?;
+            }
+            match &param.ty {
+                Some(ty) => {
+                    write!(self.0, "(: {} ", param.name.value())?;
+                    self.visit_ty(ty)?;
+                    write!(self.0, ")")?;
+                }
+                None => write!(self.0, "{}", param.name.value())?,
+            }
+            if let Some(default) = &param.default {
+                write!(self.0, " ")?;
+                self.visit_expr(default)?;
+                write!(self.0, ")")?;
+            }
+        }
+        write!(self.0, ") ")?;
+        self.ret_ty(decl.ret.as_ref())?;
+        write!(self.0, " ")?;
+        self.visit_expr(&decl.body)?;
+        write!(self.0, ")")?;
+        if decl.public {
+            write!(self.0, ")")?;
         }
         Ok(())
     }
+
+    fn visit_ty(&mut self, x: &Ty) -> CalResult<()> {
+        match &x.kind {
+            TyKind::Named { name, args } if args.is_empty() => write!(self.0, "{}", name)?,
+            TyKind::Named { name, args } => {
+                write!(self.0, "({}", name)?;
+                for arg in args {
+                    write!(self.0, " ")?;
+                    self.visit_ty(arg)?;
+                }
+                write!(self.0, ")")?;
+            }
+            TyKind::Fn { params, ret } => {
+                write!(self.0, "(fn (")?;
+                for (idx, param) in params.iter().enumerate() {
+                    if idx > 0 {
+                        write!(self.0, " ")?;
+                    }
+                    self.visit_ty(param)?;
+                }
+                write!(self.0, ") ")?;
+                self.ret_ty(ret.as_deref())?;
+                write!(self.0, ")")?;
+            }
+            TyKind::Error => write!(self.0, "<error>")?,
+        }
+        Ok(())
+    }
+}
+
+impl PrettyPrinter {
+    /// Print a list of statements, e.g. `(do (let x 1) x)`.
+    fn list(&mut self, head: &str, stmts: &[Stmt]) -> CalResult<()> {
+        write!(self.0, "({}", head)?;
+        for stmt in stmts {
+            write!(self.0, " ")?;
+            self.visit_stmt(stmt)?;
+        }
+        write!(self.0, ")")?;
+        Ok(())
+    }
+
+    /// Print a list of expressions, e.g. `(while c x)`.
+    fn exprs(&mut self, head: &str, exprs: &[&Expr]) -> CalResult<()> {
+        write!(self.0, "({}", head)?;
+        for expr in exprs {
+            write!(self.0, " ")?;
+            self.visit_expr(expr)?;
+        }
+        write!(self.0, ")")?;
+        Ok(())
+    }
+
+    /// Print a return type, or `_` if it's not annotated.
+    fn ret_ty(&mut self, ret: Option<&Ty>) -> CalResult<()> {
+        if let Some(ty) = ret {
+            return self.visit_ty(ty);
+        }
+        write!(self.0, "_")?;
+        Ok(())
+    }
+}
+
+/// Escape a string so that it can be written within a string literal.
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    let mut chars = string.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' | '"' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            // Only `${` begins an interpolation.
+            '$' if chars.peek() == Some(&'{') => escaped.push_str("\\$"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            ch if ch.is_control() => escaped.extend(ch.escape_unicode()),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Escape the value of a byte string or byte literal, which is delimited by
+/// `quote`. Bytes that aren't printable ASCII characters are written as hex
+/// escapes.
+fn escape_bytes(bytes: &[u8], quote: u8) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'\\' => escaped.push_str("\\\\"),
+            byte if byte == quote => {
+                escaped.push('\\');
+                escaped.push(byte as char);
+            }
+            b'\n' => escaped.push_str("\\n"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\t' => escaped.push_str("\\t"),
+            b'\0' => escaped.push_str("\\0"),
+            b' '..=b'~' => escaped.push(byte as char),
+            byte => write!(escaped, "\\x{:02X}", byte).unwrap(),
+        }
+    }
+    escaped
+}
+
+/// Check if an atom can be written without quotes, i.e. if it's a valid
+/// identifier (optionally with `!`/`?` suffixes).
+fn is_plain_atom(atom: &str) -> bool {
+    let name = atom.strip_suffix('?').unwrap_or(atom);
+    let name = name.strip_suffix('!').unwrap_or(name);
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(ch) if ch.is_xid_start() || ch == '_')
+        && chars.all(UnicodeXID::is_xid_continue)
 }
@@ -0,0 +1,76 @@
+use calypso_base::span::{Span, Spanned};
+use calypso_base::symbol::Symbol;
+
+use crate::expr::{BinOpKind, Expr};
+use crate::item::Item;
+use crate::pattern::Pattern;
+
+/// A statement, with its location in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stmt<'tok> {
+    pub kind: StmtKind<'tok>,
+    pub span: Span,
+    /// The ID of the file the statement is in.
+    pub file_id: usize,
+}
+
+impl<'tok> Stmt<'tok> {
+    #[must_use]
+    pub fn new(kind: StmtKind<'tok>, span: Span, file_id: usize) -> Self {
+        Self {
+            kind,
+            span,
+            file_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StmtKind<'tok> {
+    /// A variable binding, e.g. `let mut x = 0`. Binding a variable that
+    /// already exists shadows it. The value may be left out (e.g. `let x`),
+    /// in which case the variable must be assigned before it's used.
+    Let {
+        pattern: Pattern<'tok>,
+        value: Option<Box<Expr<'tok>>>,
+    },
+    /// Reassignment of a (mutable) variable, e.g. `x = 7`.
+    Assign {
+        target: Spanned<Symbol>,
+        value: Box<Expr<'tok>>,
+    },
+    /// Reassignment of a variable with the result of a binary operation on
+    /// it, e.g. `res *= i`.
+    AssignOp {
+        target: Spanned<Symbol>,
+        op: Spanned<BinOpKind>,
+        value: Box<Expr<'tok>>,
+    },
+    /// An expression, whose value is discarded unless it's the last
+    /// statement of a block.
+    Expr(Box<Expr<'tok>>),
+    /// An item, e.g. a function declaration. Items in a block are only
+    /// visible within it.
+    Item(Item<'tok>),
+}
+
+/// A sequence of statements, which are separated by newlines, e.g. the body
+/// of a `do -> ... end` block or a whole file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block<'tok> {
+    pub stmts: Vec<Stmt<'tok>>,
+    pub span: Span,
+    /// The ID of the file the block is in.
+    pub file_id: usize,
+}
+
+impl<'tok> Block<'tok> {
+    #[must_use]
+    pub fn new(stmts: Vec<Stmt<'tok>>, span: Span, file_id: usize) -> Self {
+        Self {
+            stmts,
+            span,
+            file_id,
+        }
+    }
+}
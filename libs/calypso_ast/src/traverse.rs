@@ -1,4 +1,8 @@
-use crate::expr::{Expr, Primary};
+use crate::expr::{CaseArm, Expr, Primary};
+use crate::item::Item;
+use crate::pattern::Pattern;
+use crate::stmt::{Block, Stmt};
+use crate::ty::Ty;
 use calypso_error::CalResult;
 
 pub trait Visitor {
@@ -9,4 +13,28 @@ pub trait Visitor {
     fn visit_primary(&mut self, _primary: &Primary) -> CalResult<()> {
         Ok(())
     }
+
+    fn visit_case_arm(&mut self, _arm: &CaseArm) -> CalResult<()> {
+        Ok(())
+    }
+
+    fn visit_stmt(&mut self, _stmt: &Stmt) -> CalResult<()> {
+        Ok(())
+    }
+
+    fn visit_block(&mut self, _block: &Block) -> CalResult<()> {
+        Ok(())
+    }
+
+    fn visit_pattern(&mut self, _pattern: &Pattern) -> CalResult<()> {
+        Ok(())
+    }
+
+    fn visit_item(&mut self, _item: &Item) -> CalResult<()> {
+        Ok(())
+    }
+
+    fn visit_ty(&mut self, _ty: &Ty) -> CalResult<()> {
+        Ok(())
+    }
 }
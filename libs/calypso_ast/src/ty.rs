@@ -0,0 +1,39 @@
+use calypso_base::span::Span;
+use calypso_base::symbol::Symbol;
+
+/// A type annotation, e.g. `uint` or `Option(T)`, with its location in the
+/// source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ty {
+    pub kind: TyKind,
+    pub span: Span,
+    /// The ID of the file the type is in.
+    pub file_id: usize,
+}
+
+impl Ty {
+    #[must_use]
+    pub fn new(kind: TyKind, span: Span, file_id: usize) -> Self {
+        Self {
+            kind,
+            span,
+            file_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TyKind {
+    /// A type referred to by its name, with any type arguments, e.g. `uint`
+    /// or `Option(T)`.
+    Named { name: Symbol, args: Vec<Ty> },
+    /// A function type, e.g. `fn(T): U`. Functions without a return type
+    /// return `:unit`.
+    Fn {
+        params: Vec<Ty>,
+        ret: Option<Box<Ty>>,
+    },
+    /// A placeholder for a type that could not be parsed, like
+    /// [`ExprKind::Error`](crate::expr::ExprKind::Error).
+    Error,
+}
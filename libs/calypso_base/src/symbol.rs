@@ -54,7 +54,25 @@ impl Symbol {
     /// Check if a symbol is a keyword.
     #[must_use]
     pub fn is_keyword(self) -> bool {
-        self == kw::TRUE || self == kw::FALSE
+        self == kw::TRUE
+            || self == kw::FALSE
+            || self == kw::LET
+            || self == kw::MUT
+            || self == kw::DO
+            || self == kw::END
+            || self == kw::PUB
+            || self == kw::FN
+            || self == kw::CASE
+            || self == kw::WHERE
+            || self == kw::IF
+            || self == kw::ELSE
+            || self == kw::WHILE
+            || self == kw::FOR
+            || self == kw::IN
+            || self == kw::LOOP
+            || self == kw::BREAK
+            || self == kw::CONTINUE
+            || self == kw::RET
     }
 }
 
@@ -199,5 +217,23 @@ intern_static! {kw, "Keywords", Keyword => {
     Under; UNDERSCORE: "_"; "Underscore (`_`)",
 
     True; TRUE: "true"; "True (`true`)",
-    False; FALSE: "false"; "False (`false`)"
+    False; FALSE: "false"; "False (`false`)",
+
+    Let; LET: "let"; "Let (`let`)",
+    Mut; MUT: "mut"; "Mut (`mut`)",
+    Do; DO: "do"; "Do (`do`)",
+    End; END: "end"; "End (`end`)",
+    Pub; PUB: "pub"; "Pub (`pub`)",
+    Fn; FN: "fn"; "Fn (`fn`)",
+    Case; CASE: "case"; "Case (`case`)",
+    Where; WHERE: "where"; "Where (`where`)",
+    If; IF: "if"; "If (`if`)",
+    Else; ELSE: "else"; "Else (`else`)",
+    While; WHILE: "while"; "While (`while`)",
+    For; FOR: "for"; "For (`for`)",
+    In; IN: "in"; "In (`in`)",
+    Loop; LOOP: "loop"; "Loop (`loop`)",
+    Break; BREAK: "break"; "Break (`break`)",
+    Continue; CONTINUE: "continue"; "Continue (`continue`)",
+    Ret; RET: "ret"; "Ret (`ret`)"
 }}
@@ -17,7 +17,7 @@ pub mod builder;
 
 pub use builder::{Builder, EnsembleBuilder};
 
-pub use codespan_reporting::diagnostic::{LabelStyle, Severity};
+pub use codespan_reporting::diagnostic::{Label, LabelStyle, Severity};
 
 /// The structure used for managing source file names, IDs, and contents.
 pub type SourceMgr = SimpleFiles<String, String>;
@@ -47,6 +47,37 @@ impl Diagnostic {
 
         Ok(())
     }
+
+    /// Get the severity of the diagnostic.
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        self.0.severity
+    }
+
+    /// Get the error code of the diagnostic, if any.
+    #[must_use]
+    pub fn code(&self) -> Option<&str> {
+        self.0.code.as_deref()
+    }
+
+    /// Get the main message of the diagnostic.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    /// Get the labels of the diagnostic, which point to spans in source
+    /// files.
+    #[must_use]
+    pub fn labels(&self) -> &[Label<usize>] {
+        &self.0.labels
+    }
+
+    /// Get the notes of the diagnostic, which are shown below the labels.
+    #[must_use]
+    pub fn notes(&self) -> &[String] {
+        &self.0.notes
+    }
 }
 
 /// One or more diagnostics in a specific order, in order to form an "ensemble
@@ -80,6 +111,15 @@ impl EnsembleDiagnostic {
                 .try_for_each(|diag| diag.render(buf, sourcemgr, config)),
         }
     }
+
+    /// Get the diagnostics in this ensemble, in order.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Self::One(diag) => std::slice::from_ref(diag),
+            Self::Many(diags) => diags,
+        }
+    }
 }
 
 impl From<Diagnostic> for EnsembleDiagnostic {
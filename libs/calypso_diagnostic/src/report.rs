@@ -58,6 +58,12 @@ impl GlobalReportingCtxt {
         self.fatal.as_ref()
     }
 
+    /// Take the current fatal error, if any, so that another one can be
+    /// reported (e.g. for the next input of a REPL).
+    pub fn take_fatal(&mut self) -> Option<EnsembleDiagnostic> {
+        self.fatal.take()
+    }
+
     /// Get the list of synchronizable errors.
     #[must_use]
     pub fn errors(&self) -> &[EnsembleDiagnostic] {
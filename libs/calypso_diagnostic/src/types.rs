@@ -88,5 +88,31 @@ register_diagnostics! {
     E0032: "Expected a valid exponent of a float, found EOF.",
     E0033: "Cannot use an explicit base for a float.",
     E0034: "Identifiers (and atoms) must be shorter than 255 characters.",
-    E0035: "Expected a digit after number base specifier."
+    E0035: "Expected a digit after number base specifier.",
+    E0036: "Identifier suffixes must be in the order `!?`, found `?!`.",
+    E0037: "Ambiguous `!=` directly after an identifier.",
+    E0038: "Expected a closing curly bracket at end of string interpolation, found EOF.",
+    E0039: "Expected a Unicode scalar value in escape sequence, found the surrogate {cp}.",
+    E0040: "Expected a Unicode scalar value in escape sequence, found {cp}, which is greater than U+10FFFF.",
+    E0041: "Expected an ASCII character in hex escape sequence, found `\\x{val}`.",
+    E0042: "Invalid suffix `{suffix}` for a number literal.",
+    E0043: "Invalid digit `{ch}` for a base {radix} literal.",
+    E0044: "Number literal out of range for `{ty}`.",
+    E0045: "Cannot use the `u` suffix on a negative number literal.",
+    E0046: "Cannot use the integer suffix `{suffix}` on a float literal.",
+    E0047: "Identifier `{ident}` contains uncommon Unicode codepoints.",
+    E0048: "Identifier `{ident}` mixes characters from different scripts.",
+    E0049: "Identifier `{ident}` is confusable with `{other}`.",
+    E0050: "Expected `\"{fence}` at end of raw string literal, found EOF.",
+    E0051: "Expected `\"\"\"` at end of multi-line string literal, found EOF.",
+    E0052: "Expected a line break after the opening `\"\"\"` of a multi-line string literal.",
+    E0053: "Expected the closing `\"\"\"` of a multi-line string literal to be on a line of its own.",
+    E0054: "Insufficient indentation in multi-line string literal.",
+    E0055: "Expected an ASCII character in byte literal, found `{ch}`.",
+    E0056: "Unicode escapes are not allowed in byte literals.",
+    E0057: "Expected {expected}, found {found}.",
+    E0058: "Expected {expected}, found EOF.",
+    E0059: "Expected the end of the input, found {found}.",
+    E0060: "Encountered an invalid token.",
+    E0061: "Invalid float literal `{lit}`.",
 }
@@ -1,64 +1,67 @@
-// use std::io::prelude::*;
-// use std::rc::Rc;
-// use std::{cell::RefCell, sync::Arc};
+use std::io::{self, prelude::*};
+use std::sync::Arc;
 
-// use calypso_ast::pretty::PrettyPrinter;
-// use calypso_ast::traverse::Visitor;
-// use calypso_base::sourcemgr::SourceMgr;
-// use calypso_base::{
-//     session::BaseSession,
-//     ui::{atty::Stream, parse_color_pref},
-// };
-// use calypso_diagnostic::{prelude::*, report::GlobalReportingCtxt};
-// use calypso_parsing::parser::tokens::process_iter;
-// use calypso_parsing::session::ParseSess;
-// use calypso_parsing::{lexer::Lexer, parser::grammar::ExprParser};
+use calypso_ast::{pretty::PrettyPrinter, traverse::Visitor};
+use calypso_base::ui::{atty::Stream, parse_color_pref, Emitters};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::reporting::files::Files;
+use calypso_diagnostic::{diagnostic::SourceMgr, report::GlobalReportingCtxt};
+use calypso_parsing::parser::parse_expr;
 
-// use lalrpop_util::ParseError;
+/// Parse each line of standard input as an expression, and pretty-print it.
+fn main() {
+    let gcx = Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(
+            parse_color_pref("auto", Stream::Stdout),
+            parse_color_pref("auto", Stream::Stderr),
+        )),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    });
 
-// fn main() {
-//     // let stdin = std::io::stdin();
-//     // loop {
-//     let mut contents = String::from("awegawe");
-//     // stdin.lock().read_line(&mut contents).expect("io");
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.expect("failed to read from stdin");
+        let file_id = gcx.sourcemgr.write().add("<stdin>".to_string(), line);
+        let sourcemgr = gcx.sourcemgr.read();
+        let source = sourcemgr.source(file_id).unwrap();
 
-//     let mut smgr = SourceMgr::new();
-//     let source_id = smgr.intern_add("<anon>", contents);
-//     smgr.finalize();
-//     let sess = Arc::new(BaseSession::new(
-//         parse_color_pref("auto", Stream::Stdout),
-//         parse_color_pref("auto", Stream::Stderr),
-//         smgr,
-//     ));
+        // Diagnostics from earlier lines have already been printed.
+        let (n_errors, n_nonfatals) = {
+            let reports = gcx.grcx.read();
+            (reports.errors().len(), reports.nonfatals().len())
+        };
+        let result = parse_expr(file_id, source, &gcx);
 
-//     let grcx = Arc::new(GlobalReportingCtxt::new());
-//     let mut lexer =
-//         Lexer::new(source_id, Arc::new(ParseSess::new(sess, Arc::clone(&grcx)))).unwrap();
-//     println!("{:?}", lexer.scan().unwrap());
-//     drop(lexer);
-//     // let iter = lexer.into_iter();
-//     // let iter = process_iter(iter, true);
-//     // let parser = ExprParser::new();
-//     // let res = parser.parse(source_id, iter);
-//     // if let Ok(parsed) = res {
-//     //     let mut printer = PrettyPrinter::default();
-//     //     printer.visit_expr(&parsed).unwrap();
-//     //     println!("{}", printer);
-//     // } else if let Err(err) = res {
-//     //     if matches!(err, ParseError::UnrecognizedEOF { .. }) {
-//     //         break;
-//     //     } else {
-//     //         println!("{:#?}", err);
-//     //     }
-//     // }
-//     // grcx.borrow()
-//     //     .errors()
-//     //     .iter()
-//     //     .take(1)
-//     //     .for_each(|e| println!("{}", e));
-//     // }
-// }
+        // Syntax errors that the parser recovered from are reported whether
+        // or not it could parse the rest of the line.
+        let reports = gcx.grcx.read();
+        for diag in reports.errors()[n_errors..]
+            .iter()
+            .chain(reports.nonfatals()[n_nonfatals..].iter())
+        {
+            let mut emit = gcx.emit.write();
+            let mut buf = emit.err.buffer();
+            diag.render(&mut buf, &sourcemgr, None).unwrap();
+            emit.err.emit(&buf).unwrap();
+        }
+        drop(reports);
 
-fn main() {
-    todo!()
+        match result {
+            Ok(expr) => {
+                let mut printer = PrettyPrinter::default();
+                printer.visit_expr(&expr).unwrap();
+                println!("{}", printer);
+            }
+            Err(err) => {
+                let mut buf = gcx.emit.read().err.buffer();
+                let fatal = gcx.grcx.write().take_fatal();
+                match fatal {
+                    Some(fatal) => fatal.render(&mut buf, &sourcemgr, None).unwrap(),
+                    None => println!("{}", err),
+                }
+                gcx.emit.write().err.emit(&buf).unwrap();
+            }
+        }
+    }
 }
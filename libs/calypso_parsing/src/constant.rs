@@ -0,0 +1,518 @@
+//! Evaluation of number literals into typed constants.
+//!
+//! The lexer and parser keep numbers as unparsed strings (with underscores,
+//! and without their radix prefix). This module converts these into the
+//! `uint`, `sint` or `float` values they represent.
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use calypso_ast::expr::{Radix, Suffix};
+use calypso_base::span::Span;
+use calypso_base::symbol::kw::Keyword;
+use calypso_common::gcx::GlobalCtxt;
+use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
+use calypso_diagnostic::prelude::*;
+
+use crate::lexer::{Token, TokenType};
+use crate::parser::tokens::{continues_expr, trim_number, Tok};
+
+/// A typed numeric constant.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Constant {
+    /// `uint`
+    Uint(u64),
+    /// `sint`
+    Sint(i64),
+    /// `float`
+    Float(f64),
+}
+
+impl Constant {
+    /// Get the type of this constant.
+    #[must_use]
+    pub fn ty(self) -> NumberType {
+        match self {
+            Self::Uint(_) => NumberType::Uint,
+            Self::Sint(_) => NumberType::Sint,
+            Self::Float(_) => NumberType::Float,
+        }
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uint(value) => write!(f, "{}u", value),
+            Self::Sint(value) => write!(f, "{}s", value),
+            Self::Float(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// The type of a numeric constant.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NumberType {
+    Uint,
+    Sint,
+    Float,
+}
+
+impl Display for NumberType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uint => write!(f, "uint"),
+            Self::Sint => write!(f, "sint"),
+            Self::Float => write!(f, "float"),
+        }
+    }
+}
+
+/// An error found while evaluating a number literal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    /// The literal does not fit in its type.
+    Overflow(NumberType),
+    /// The literal has an invalid suffix, which begins at the given byte
+    /// index in the literal.
+    InvalidSuffix(usize),
+    /// The literal has a digit that is invalid for its radix, at the given
+    /// byte index in the literal.
+    InvalidDigit(usize),
+    /// A negative literal with the `u` suffix.
+    NegativeUint,
+    /// A float literal with an integer suffix (`u` or `s`), which begins at
+    /// the given byte index in the literal.
+    IntSuffixOnFloat(usize),
+    /// A float literal that could not be parsed, e.g. one with an empty
+    /// exponent (`1e`).
+    MalformedFloat,
+}
+
+impl EvalError {
+    /// Is this error in the literal itself, rather than in its value? Such
+    /// errors don't depend on whether the literal is negated, so they're
+    /// reported by the lexer.
+    #[must_use]
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidSuffix(_)
+                | Self::InvalidDigit(_)
+                | Self::IntSuffixOnFloat(_)
+                | Self::MalformedFloat
+        )
+    }
+}
+
+/// Evaluate a number literal.
+///
+/// `number` is the literal without its radix prefix (as in
+/// [`calypso_ast::expr::Primary::Number`]), and `negative` is whether the
+/// literal is directly negated (e.g. `-5`). Integer literals without a suffix
+/// are `uint`s, unless they are negative, in which case they are `sint`s.
+///
+/// # Errors
+///
+/// This function errors if the literal is invalid, or does not fit in its
+/// type. See [`EvalError`] for more details.
+pub fn evaluate(
+    number: &str,
+    radix: Radix,
+    suffix: Option<Suffix>,
+    negative: bool,
+) -> Result<Constant, EvalError> {
+    match suffix {
+        Some(Suffix::TrueFloat) => evaluate_float(number, negative),
+        Some(Suffix::Invalid) => Err(find_invalid_suffix(number, radix)),
+        Some(Suffix::Float) => {
+            let value = digits(number, radix).fold(0.0, |acc, digit| {
+                acc * f64::from(radix.radix()) + f64::from(digit)
+            });
+            Ok(Constant::Float(if negative { -value } else { value }))
+        }
+        Some(Suffix::Uint) if negative => Err(EvalError::NegativeUint),
+        Some(Suffix::Uint) | None if !negative => evaluate_uint(number, radix)
+            .map(Constant::Uint)
+            .ok_or(EvalError::Overflow(NumberType::Uint)),
+        _ => {
+            let magnitude = evaluate_uint(number, radix);
+            let value = match magnitude {
+                // This can't be represented as a positive `i64`.
+                Some(magnitude) if negative && magnitude == i64::MIN.unsigned_abs() => {
+                    Some(i64::MIN)
+                }
+                Some(magnitude) => {
+                    i64::try_from(magnitude)
+                        .ok()
+                        .map(|value| if negative { -value } else { value })
+                }
+                None => None,
+            };
+            value
+                .map(Constant::Sint)
+                .ok_or(EvalError::Overflow(NumberType::Sint))
+        }
+    }
+}
+
+fn digits(number: &str, radix: Radix) -> impl Iterator<Item = u32> + '_ {
+    number
+        .chars()
+        .filter_map(move |ch| ch.to_digit(radix.radix()))
+}
+
+fn evaluate_uint(number: &str, radix: Radix) -> Option<u64> {
+    digits(number, radix).try_fold(0_u64, |acc, digit| {
+        acc.checked_mul(u64::from(radix.radix()))?
+            .checked_add(u64::from(digit))
+    })
+}
+
+fn evaluate_float(number: &str, negative: bool) -> Result<Constant, EvalError> {
+    // The lexer keeps any suffix within the lexeme of a float. An empty
+    // exponent (which the lexer reports) is not a suffix.
+    let is_e = |c: char| c == 'e' || c == 'E';
+    let is_exponent = |idx: usize, ch: char| {
+        is_e(ch)
+            && !number[..idx].contains(is_e)
+            && (number.len() == idx + 1
+                || number[idx + 1..]
+                    .starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-'))
+    };
+    let suffix_idx = number
+        .char_indices()
+        .find(|&(idx, ch)| ch.is_ascii_alphabetic() && !is_exponent(idx, ch))
+        .map_or(number.len(), |(idx, _)| idx);
+    match &number[suffix_idx..] {
+        "" | "f" => {}
+        "u" | "s" => return Err(EvalError::IntSuffixOnFloat(suffix_idx)),
+        _ => return Err(EvalError::InvalidSuffix(suffix_idx)),
+    }
+
+    let number = number[..suffix_idx].replace('_', "");
+    // Rust's float parsing doesn't accept a trailing `.`, and Calypso's floats
+    // can't have one anyway (which is reported by the lexer).
+    let value = number
+        .trim_end_matches('.')
+        .parse::<f64>()
+        .map_err(|_| EvalError::MalformedFloat)?;
+    if value.is_infinite() {
+        return Err(EvalError::Overflow(NumberType::Float));
+    }
+    Ok(Constant::Float(if negative { -value } else { value }))
+}
+
+fn find_invalid_suffix(number: &str, radix: Radix) -> EvalError {
+    let (idx, ch) = number
+        .char_indices()
+        .find(|&(_, ch)| ch != '_' && !ch.is_digit(radix.radix()))
+        .unwrap_or((number.len(), ' '));
+    if ch.is_ascii_digit() {
+        EvalError::InvalidDigit(idx)
+    } else {
+        EvalError::InvalidSuffix(idx)
+    }
+}
+
+/// Evaluates number literals and reports any errors found while evaluating.
+pub struct ConstEvaluator {
+    file_id: usize,
+    gcx: Arc<GlobalCtxt>,
+}
+
+impl ConstEvaluator {
+    #[must_use]
+    pub fn new(file_id: usize, gcx: Arc<GlobalCtxt>) -> Self {
+        Self { file_id, gcx }
+    }
+
+    /// Evaluate a number literal token ([`TokenType::Int`] or
+    /// [`TokenType::Float`]). `negation` is the span of the `-` directly
+    /// before this literal, if any.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the token is not a number literal token.
+    #[must_use]
+    pub fn eval_token(&self, token: &Token<'_>, negation: Option<Span>) -> Option<Constant> {
+        let (ty, lexeme) = *token.value();
+        let (radix, suffix) = match ty {
+            TokenType::Int { radix, suffix } => (radix, suffix),
+            TokenType::Float => (Radix::None, Some(Suffix::TrueFloat)),
+            _ => panic!("expected a number literal token, found {:?}", ty),
+        };
+        self.eval(
+            token.span(),
+            trim_number(lexeme, radix, suffix),
+            radix,
+            suffix,
+            negation,
+        )
+    }
+
+    /// Evaluate a number literal, as in
+    /// [`calypso_ast::expr::Primary::Number`]. `span` is the span of the
+    /// literal, and `negation` is the span of the `-` directly before this
+    /// literal, if any.
+    ///
+    /// If the literal is invalid, an error is reported and `None` is
+    /// returned.
+    #[must_use]
+    pub fn eval(
+        &self,
+        span: Span,
+        number: &str,
+        radix: Radix,
+        suffix: Option<Suffix>,
+        negation: Option<Span>,
+    ) -> Option<Constant> {
+        match evaluate(number, radix, suffix, negation.is_some()) {
+            Ok(constant) => Some(constant),
+            Err(error) => {
+                self.report(span, number, radix, negation, error);
+                None
+            }
+        }
+    }
+
+    /// Report the errors in the digits or suffix of a number literal token
+    /// that was just lexed (see [`EvalError::is_malformed`]).
+    ///
+    /// Malformed floats are not reported, as the lexer has already reported
+    /// why they're malformed.
+    pub(crate) fn check_literal(&self, token: &Token<'_>) {
+        let (ty, lexeme) = *token.value();
+        let (radix, suffix) = match ty {
+            TokenType::Int { radix, suffix } => (radix, suffix),
+            _ => (Radix::None, Some(Suffix::TrueFloat)),
+        };
+        let number = trim_number(lexeme, radix, suffix);
+        if let Err(error) = evaluate(number, radix, suffix, false) {
+            if error.is_malformed() && error != EvalError::MalformedFloat {
+                self.report(token.span(), number, radix, None, error);
+            }
+        }
+    }
+
+    /// Report the errors in the values of the number literals in a file's
+    /// processed tokens, i.e. the errors that depend on whether a literal is
+    /// negated. The other errors have already been reported by the lexer.
+    pub(crate) fn check_values(&self, tokens: &[(usize, Tok<'_>, usize)]) {
+        for (idx, &(lo, tok, hi)) in tokens.iter().enumerate() {
+            if let Tok::Number(number, radix, suffix) = tok {
+                let negation = match idx.checked_sub(1).map(|idx| tokens[idx]) {
+                    Some((lo, Tok::Unprocessed(TokenType::Minus), hi))
+                        if idx < 2 || starts_operand(tokens[idx - 2].1) =>
+                    {
+                        Some(Span::new(lo, hi))
+                    }
+                    _ => None,
+                };
+                match evaluate(number, radix, suffix, negation.is_some()) {
+                    Err(error) if !error.is_malformed() => {
+                        self.report(Span::new(lo, hi), number, radix, negation, error);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Report an error found while evaluating a number literal.
+    fn report(
+        &self,
+        span: Span,
+        number: &str,
+        radix: Radix,
+        negation: Option<Span>,
+        error: EvalError,
+    ) {
+        // The span of the part of the literal starting at `idx`.
+        let rest = |idx: usize| Span::new(span.hi() - (number.len() - idx), span.hi());
+        let literal = negation.map_or(span, |negation| Span::new(negation.lo(), span.hi()));
+
+        let diag = match error {
+            EvalError::Overflow(ty) => EnsembleBuilder::new().error(|b| {
+                let (min, max) = match ty {
+                    NumberType::Uint => ("0".to_string(), u64::MAX.to_string()),
+                    NumberType::Sint => (i64::MIN.to_string(), i64::MAX.to_string()),
+                    NumberType::Float => (f64::MIN.to_string(), f64::MAX.to_string()),
+                };
+                b.code("E0044")
+                    .short(err!(E0044, ty = ty))
+                    .label(LabelStyle::Primary, None, self.file_id, literal)
+                    .note(format!(
+                        "the range of `{}` is `{}` to `{}`, inclusive.",
+                        ty, min, max
+                    ))
+            }),
+            EvalError::InvalidSuffix(idx) => EnsembleBuilder::new().error(|b| {
+                b.code("E0042")
+                    .short(err!(E0042, suffix = &number[idx..]))
+                    .label(LabelStyle::Primary, None, self.file_id, rest(idx))
+                    .note("the valid suffixes are `u` (uint), `s` (sint) and `f` (float).")
+            }),
+            EvalError::InvalidDigit(idx) => EnsembleBuilder::new().error(|b| {
+                let ch = number[idx..].chars().next().unwrap_or_default();
+                b.code("E0043")
+                    .short(err!(E0043, ch = ch, radix = radix.radix()))
+                    .label(
+                        LabelStyle::Primary,
+                        None,
+                        self.file_id,
+                        Span::new(rest(idx).lo(), rest(idx).lo() + ch.len_utf8()),
+                    )
+            }),
+            EvalError::NegativeUint => EnsembleBuilder::new().error(|b| {
+                b.code("E0045")
+                    .short(err!(E0045))
+                    .label(LabelStyle::Primary, None, self.file_id, literal)
+                    .note(format!(
+                        "help: perhaps you meant to use a `sint` literal: `-{}{}s`?",
+                        radix, number
+                    ))
+            }),
+            EvalError::IntSuffixOnFloat(idx) => EnsembleBuilder::new().error(|b| {
+                let suffix = &number[idx..];
+                b.code("E0046")
+                    .short(err!(E0046, suffix = suffix))
+                    .label(LabelStyle::Primary, None, self.file_id, rest(idx))
+                    .note("help: remove this suffix, or use the `f` suffix instead.")
+            }),
+            EvalError::MalformedFloat => EnsembleBuilder::new().error(|b| {
+                b.code("E0061").short(err!(E0061, lit = number)).label(
+                    LabelStyle::Primary,
+                    None,
+                    self.file_id,
+                    span,
+                )
+            }),
+        };
+        self.gcx.grcx.write().report_syncd(diag.build());
+    }
+}
+
+/// Can an operand (and so a prefix `-`) start after this token, i.e. is it
+/// one that can't end an expression?
+fn starts_operand(tok: Tok<'_>) -> bool {
+    match tok {
+        Tok::Unprocessed(ty) => continues_expr(ty),
+        Tok::Keyword(kw) => !matches!(
+            kw,
+            Keyword::True | Keyword::False | Keyword::End | Keyword::Break | Keyword::Continue
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsuffixed() {
+        assert_eq!(
+            evaluate("1_000", Radix::None, None, false),
+            Ok(Constant::Uint(1000))
+        );
+        assert_eq!(
+            evaluate("691", Radix::None, None, true),
+            Ok(Constant::Sint(-691))
+        );
+        assert_eq!(
+            evaluate("ff", Radix::Hexadecimal, None, false),
+            Ok(Constant::Uint(255))
+        );
+        assert_eq!(
+            evaluate("1010", Radix::Binary, None, false),
+            Ok(Constant::Uint(10))
+        );
+    }
+
+    #[test]
+    fn suffixed() {
+        assert_eq!(
+            evaluate("691", Radix::None, Some(Suffix::Sint), false),
+            Ok(Constant::Sint(691))
+        );
+        assert_eq!(
+            evaluate("17", Radix::Octal, Some(Suffix::Uint), false),
+            Ok(Constant::Uint(15))
+        );
+        assert_eq!(
+            evaluate("5", Radix::None, Some(Suffix::Float), true),
+            Ok(Constant::Float(-5.0))
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(
+            evaluate("7.5", Radix::None, Some(Suffix::TrueFloat), false),
+            Ok(Constant::Float(7.5))
+        );
+        assert_eq!(
+            evaluate("1_0e-1f", Radix::None, Some(Suffix::TrueFloat), true),
+            Ok(Constant::Float(-1.0))
+        );
+        assert_eq!(
+            evaluate("1.5u", Radix::None, Some(Suffix::TrueFloat), false),
+            Err(EvalError::IntSuffixOnFloat(3))
+        );
+        assert_eq!(
+            evaluate("1e5foo", Radix::None, Some(Suffix::TrueFloat), false),
+            Err(EvalError::InvalidSuffix(3))
+        );
+        assert_eq!(
+            evaluate("1e", Radix::None, Some(Suffix::TrueFloat), false),
+            Err(EvalError::MalformedFloat)
+        );
+        assert_eq!(
+            evaluate("1e400", Radix::None, Some(Suffix::TrueFloat), false),
+            Err(EvalError::Overflow(NumberType::Float))
+        );
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(
+            evaluate("18446744073709551615", Radix::None, None, false),
+            Ok(Constant::Uint(u64::MAX))
+        );
+        assert_eq!(
+            evaluate("18446744073709551616", Radix::None, None, false),
+            Err(EvalError::Overflow(NumberType::Uint))
+        );
+        assert_eq!(
+            evaluate("9223372036854775808", Radix::None, Some(Suffix::Sint), true),
+            Ok(Constant::Sint(i64::MIN))
+        );
+        assert_eq!(
+            evaluate(
+                "9223372036854775808",
+                Radix::None,
+                Some(Suffix::Sint),
+                false
+            ),
+            Err(EvalError::Overflow(NumberType::Sint))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            evaluate("5", Radix::None, Some(Suffix::Uint), true),
+            Err(EvalError::NegativeUint)
+        );
+        assert_eq!(
+            evaluate("12abc", Radix::None, Some(Suffix::Invalid), false),
+            Err(EvalError::InvalidSuffix(2))
+        );
+        assert_eq!(
+            evaluate("102", Radix::Binary, Some(Suffix::Invalid), false),
+            Err(EvalError::InvalidDigit(2))
+        );
+    }
+}
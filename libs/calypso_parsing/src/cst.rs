@@ -0,0 +1,30 @@
+//! A lossless concrete syntax tree (CST) for Calypso.
+//!
+//! Unlike the AST produced by [`crate::parser`], the CST keeps every token
+//! from the lexer, including whitespace, newlines and comments, so the source
+//! can always be rebuilt from it exactly. This is built on
+//! [`rowan`](https://docs.rs/rowan)'s green/red trees: the green tree is an
+//! immutable, position-independent tree that can be shared and cached, and
+//! the red tree ([`SyntaxNode`]) is a view of it with parent pointers and
+//! absolute positions.
+//!
+//! The CST parser understands the same language as [`crate::parser`], with
+//! the same operator precedence and the same significant newlines. Tokens
+//! that it can't parse end up in [`SyntaxKind::Error`] nodes.
+//!
+//! The typed AST view in [`ast`] sits on top of the CST, for consumers that
+//! don't care about trivia.
+
+pub use kind::{CalypsoLang, SyntaxKind};
+pub use parser::{parse, Parse};
+
+pub mod ast;
+mod kind;
+mod parser;
+
+#[cfg(test)]
+mod tests;
+
+pub type SyntaxNode = rowan::SyntaxNode<CalypsoLang>;
+pub type SyntaxToken = rowan::SyntaxToken<CalypsoLang>;
+pub type SyntaxElement = rowan::SyntaxElement<CalypsoLang>;
@@ -0,0 +1,745 @@
+//! A typed view of the CST.
+//!
+//! Each AST node is a thin wrapper around a [`SyntaxNode`] of the right
+//! [`SyntaxKind`]. Accessors return `Option`s, as the CST may be incomplete if
+//! there were errors while parsing.
+
+use calypso_ast::expr::{BinOpKind, UnOpKind};
+
+use super::{SyntaxKind, SyntaxNode, SyntaxToken};
+
+/// A typed view of a [`SyntaxNode`].
+pub trait AstNode: Sized {
+    /// Convert a [`SyntaxNode`] into this AST node, if it is of the right
+    /// kind.
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    /// Get the underlying [`SyntaxNode`].
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! ast_node {
+    ($($(#[$attr:meta])* $name:ident => $kind:ident,)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+            pub struct $name(SyntaxNode);
+
+            impl AstNode for $name {
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    if node.kind() == SyntaxKind::$kind {
+                        Some(Self(node))
+                    } else {
+                        None
+                    }
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+ast_node! {
+    /// The root of a CST.
+    Root => Root,
+    /// A binary operation, e.g. `a + b`.
+    BinExpr => BinExpr,
+    /// A unary operation, e.g. `-a`.
+    UnaryExpr => UnaryExpr,
+    /// A parenthesized expression, e.g. `(a)`.
+    ParenExpr => ParenExpr,
+    /// A literal, e.g. `1` or `"a"`.
+    Literal => Literal,
+    /// A string literal containing interpolations, e.g. `"a ${b} c"`.
+    InterpString => InterpString,
+    /// An interpolation within a string literal, e.g. `${b}`.
+    Interpolation => Interpolation,
+    /// A reference to a name, e.g. `a`.
+    NameRef => NameRef,
+    /// A function call, e.g. `f(a, b)`.
+    CallExpr => CallExpr,
+    /// The arguments of a function call, e.g. `(a, b)`.
+    ArgList => ArgList,
+    /// A field access, e.g. `a.b`.
+    FieldExpr => FieldExpr,
+    /// A block of statements, e.g. `do a end`.
+    BlockExpr => BlockExpr,
+    /// An `if` expression, e.g. `if a -> b else -> c`.
+    IfExpr => IfExpr,
+    /// A `while` loop, e.g. `while a -> b`.
+    WhileExpr => WhileExpr,
+    /// A `for` loop, e.g. `for x in xs -> f(x)`.
+    ForExpr => ForExpr,
+    /// A `loop`, e.g. `loop -> f()`.
+    LoopExpr => LoopExpr,
+    /// `break`.
+    BreakExpr => BreakExpr,
+    /// `continue`.
+    ContinueExpr => ContinueExpr,
+    /// A return, e.g. `ret a`.
+    RetExpr => RetExpr,
+    /// A `case` expression, e.g. `case a do 0 -> b end`.
+    CaseExpr => CaseExpr,
+    /// An arm of a `case` expression, e.g. `0 -> b`.
+    CaseArm => CaseArm,
+    /// The guard of an arm of a `case` expression, e.g. `where n > 0`.
+    CaseGuard => CaseGuard,
+    /// A `let` statement, e.g. `let a = 1`.
+    LetStmt => LetStmt,
+    /// An assignment, e.g. `a = 1` or `a += 1`.
+    AssignStmt => AssignStmt,
+    /// A function declaration, e.g. `fn f(a) -> a`.
+    FnItem => FnItem,
+    /// The parameters of a function declaration, e.g. `(a: int, b)`.
+    ParamList => ParamList,
+    /// A parameter of a function declaration, e.g. `a: int \\ 1`.
+    Param => Param,
+    /// A named type, e.g. `int` or `List(int)`.
+    NamedTy => NamedTy,
+    /// A function type, e.g. `fn(int): int`.
+    FnTy => FnTy,
+    /// The arguments of a named type or the parameters of a function type,
+    /// e.g. `(int, bool)`.
+    TyList => TyList,
+    /// A pattern that binds a name, e.g. `a` or `mut a`.
+    BindingPattern => BindingPattern,
+    /// `_`.
+    WildcardPattern => WildcardPattern,
+    /// A literal pattern, e.g. `1`.
+    LiteralPattern => LiteralPattern,
+    /// A constructor pattern, e.g. `Pair(a, b)`.
+    ConstructorPattern => ConstructorPattern,
+    /// A pattern with alternatives, e.g. `0 | 1`.
+    OrPattern => OrPattern,
+}
+
+/// Define an enum of AST nodes, each of which has a [`SyntaxKind`] with the
+/// same name.
+macro_rules! ast_enum {
+    ($(#[$attr:meta])* $name:ident { $($variant:ident,)* }) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant($variant),)*
+        }
+
+        impl AstNode for $name {
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                match node.kind() {
+                    $(SyntaxKind::$variant => Some(Self::$variant($variant(node))),)*
+                    _ => None,
+                }
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                match self {
+                    $(Self::$variant(node) => node.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+ast_enum! {
+    /// An expression.
+    Expr {
+        BinExpr,
+        UnaryExpr,
+        ParenExpr,
+        Literal,
+        InterpString,
+        NameRef,
+        CallExpr,
+        FieldExpr,
+        BlockExpr,
+        IfExpr,
+        WhileExpr,
+        ForExpr,
+        LoopExpr,
+        BreakExpr,
+        ContinueExpr,
+        RetExpr,
+        CaseExpr,
+    }
+}
+
+ast_enum! {
+    /// A pattern.
+    Pattern {
+        BindingPattern,
+        WildcardPattern,
+        LiteralPattern,
+        ConstructorPattern,
+        OrPattern,
+    }
+}
+
+ast_enum! {
+    /// A type.
+    Ty {
+        NamedTy,
+        FnTy,
+    }
+}
+
+/// A statement.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Stmt {
+    Let(LetStmt),
+    Assign(AssignStmt),
+    Fn(FnItem),
+    Expr(Expr),
+}
+
+impl AstNode for Stmt {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::LetStmt => Some(Self::Let(LetStmt(node))),
+            SyntaxKind::AssignStmt => Some(Self::Assign(AssignStmt(node))),
+            SyntaxKind::FnItem => Some(Self::Fn(FnItem(node))),
+            _ => Expr::cast(node).map(Self::Expr),
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Let(stmt) => stmt.syntax(),
+            Self::Assign(stmt) => stmt.syntax(),
+            Self::Fn(stmt) => stmt.syntax(),
+            Self::Expr(stmt) => stmt.syntax(),
+        }
+    }
+}
+
+/// Get the child expressions of a node.
+fn exprs(node: &SyntaxNode) -> impl Iterator<Item = Expr> {
+    node.children().filter_map(Expr::cast)
+}
+
+/// Get the child statements of a node. Expressions are statements, too.
+fn stmts(node: &SyntaxNode) -> impl Iterator<Item = Stmt> {
+    node.children().filter_map(Stmt::cast)
+}
+
+/// Get the child tokens of a node that aren't trivia.
+fn tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(rowan::NodeOrToken::into_token)
+        .filter(|token| !token.kind().is_trivia())
+}
+
+/// Get the first child token of a node that isn't trivia.
+fn first_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    tokens(node).next()
+}
+
+/// Get the first child token of a node of the given kind.
+fn token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    tokens(node).find(|token| token.kind() == kind)
+}
+
+/// Check if a node has a child `keyword` token.
+fn has_keyword(node: &SyntaxNode, keyword: &str) -> bool {
+    tokens(node).any(|token| token.kind() == SyntaxKind::Keyword && token.text() == keyword)
+}
+
+impl Root {
+    /// Get the statements of the file.
+    pub fn stmts(&self) -> impl Iterator<Item = Stmt> {
+        stmts(&self.0)
+    }
+
+    /// Get the inner doc comments (`//!` and `/*! ... */`) at the top of the
+    /// file, which document the module itself. Only trivia (e.g. other
+    /// comments or a shebang) may come before them.
+    pub fn inner_docs(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .take_while(
+                |element| matches!(element.as_token(), Some(token) if token.kind().is_trivia()),
+            )
+            .filter_map(rowan::NodeOrToken::into_token)
+            .filter(is_inner_doc)
+    }
+
+    /// Get the text of the module's documentation, from the inner doc
+    /// comments at the top of the file, without the comment markers. Each
+    /// comment is on its own line.
+    #[must_use]
+    pub fn docs(&self) -> Option<String> {
+        let lines = self
+            .inner_docs()
+            .map(|token| {
+                let text = token.text();
+                let text = match token.kind() {
+                    SyntaxKind::LineComment => &text[3..],
+                    _ => text[3..].strip_suffix("*/").unwrap_or(&text[3..]),
+                };
+                // Allow a single space after the comment marker.
+                text.strip_prefix(' ')
+                    .unwrap_or(text)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+/// Is this token an inner doc comment (`//!` or `/*! ... */`)?
+fn is_inner_doc(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        SyntaxKind::LineComment => token.text().starts_with("//!"),
+        SyntaxKind::BlockComment => token.text().starts_with("/*!"),
+        _ => false,
+    }
+}
+
+impl BinExpr {
+    #[must_use]
+    pub fn lhs(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    #[must_use]
+    pub fn rhs(&self) -> Option<Expr> {
+        exprs(&self.0).nth(1)
+    }
+
+    /// Get the operator token.
+    #[must_use]
+    pub fn op(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+
+    #[must_use]
+    pub fn op_kind(&self) -> Option<BinOpKind> {
+        let kind = match self.op()?.kind() {
+            SyntaxKind::PipePipe => BinOpKind::LogicalOr,
+            SyntaxKind::AndAnd => BinOpKind::LogicalAnd,
+            SyntaxKind::Pipe => BinOpKind::BitOr,
+            SyntaxKind::Caret => BinOpKind::BitXor,
+            SyntaxKind::And => BinOpKind::BitAnd,
+            SyntaxKind::EqEq => BinOpKind::Equal,
+            SyntaxKind::BangEq => BinOpKind::NotEqual,
+            SyntaxKind::Lt => BinOpKind::Lt,
+            SyntaxKind::Gt => BinOpKind::Gt,
+            SyntaxKind::LtEq => BinOpKind::LtEq,
+            SyntaxKind::GtEq => BinOpKind::GtEq,
+            SyntaxKind::GtGt => BinOpKind::BitShiftRight,
+            SyntaxKind::LtLt => BinOpKind::BitShiftLeft,
+            SyntaxKind::Plus => BinOpKind::Add,
+            SyntaxKind::Minus => BinOpKind::Subtract,
+            SyntaxKind::Star => BinOpKind::Multiply,
+            SyntaxKind::Slash => BinOpKind::Divide,
+            SyntaxKind::Percent => BinOpKind::Modulo,
+            SyntaxKind::StarStar => BinOpKind::Exponent,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl UnaryExpr {
+    /// Get the operator token.
+    #[must_use]
+    pub fn op(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+
+    #[must_use]
+    pub fn op_kind(&self) -> Option<UnOpKind> {
+        match self.op()?.kind() {
+            SyntaxKind::Minus => Some(UnOpKind::Negative),
+            SyntaxKind::Bang => Some(UnOpKind::UnaryNot),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn operand(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl ParenExpr {
+    #[must_use]
+    pub fn expr(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl Literal {
+    /// Get the literal's token. Its kind is one of [`SyntaxKind::Int`],
+    /// [`SyntaxKind::Float`], [`SyntaxKind::String`],
+    /// [`SyntaxKind::RawString`], [`SyntaxKind::MultilineString`],
+    /// [`SyntaxKind::Char`], [`SyntaxKind::ByteString`],
+    /// [`SyntaxKind::Byte`], [`SyntaxKind::Atom`] or [`SyntaxKind::Keyword`]
+    /// (`true` or `false`).
+    #[must_use]
+    pub fn token(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+}
+
+/// A part of an interpolated string literal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InterpPart {
+    /// Text that is not an interpolation, with escapes left as-is.
+    Fragment(SyntaxToken),
+    /// An interpolation (`${ ... }`).
+    Interpolation(Interpolation),
+}
+
+impl InterpString {
+    /// Get the parts of the string literal, in order.
+    pub fn parts(&self) -> impl Iterator<Item = InterpPart> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|element| match element {
+                rowan::NodeOrToken::Token(token) if token.kind() == SyntaxKind::StringFragment => {
+                    Some(InterpPart::Fragment(token))
+                }
+                rowan::NodeOrToken::Node(node) => {
+                    Interpolation::cast(node).map(InterpPart::Interpolation)
+                }
+                rowan::NodeOrToken::Token(_) => None,
+            })
+    }
+}
+
+impl Interpolation {
+    #[must_use]
+    pub fn expr(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl NameRef {
+    /// Get the identifier token.
+    #[must_use]
+    pub fn ident(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+}
+
+impl CallExpr {
+    #[must_use]
+    pub fn callee(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    #[must_use]
+    pub fn arg_list(&self) -> Option<ArgList> {
+        self.0.children().find_map(ArgList::cast)
+    }
+}
+
+impl ArgList {
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        exprs(&self.0)
+    }
+}
+
+impl FieldExpr {
+    #[must_use]
+    pub fn expr(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    /// Get the field name's token.
+    #[must_use]
+    pub fn field(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+}
+
+impl BlockExpr {
+    pub fn stmts(&self) -> impl Iterator<Item = Stmt> {
+        stmts(&self.0)
+    }
+}
+
+impl IfExpr {
+    #[must_use]
+    pub fn cond(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    /// Get the `then` branch: a [`BlockExpr`], or a single statement after
+    /// `->`.
+    #[must_use]
+    pub fn then_branch(&self) -> Option<Stmt> {
+        stmts(&self.0).nth(1)
+    }
+
+    /// Get the `else` branch, if any: a [`BlockExpr`], another [`IfExpr`],
+    /// or a single statement after `->`.
+    #[must_use]
+    pub fn else_branch(&self) -> Option<Stmt> {
+        stmts(&self.0).nth(2)
+    }
+}
+
+impl WhileExpr {
+    #[must_use]
+    pub fn cond(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    /// Get the body: a [`BlockExpr`], or a single statement after `->`.
+    #[must_use]
+    pub fn body(&self) -> Option<Stmt> {
+        stmts(&self.0).nth(1)
+    }
+}
+
+impl ForExpr {
+    #[must_use]
+    pub fn pattern(&self) -> Option<Pattern> {
+        self.0.children().find_map(Pattern::cast)
+    }
+
+    #[must_use]
+    pub fn iterable(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    /// Get the body: a [`BlockExpr`], or a single statement after `->`.
+    #[must_use]
+    pub fn body(&self) -> Option<Stmt> {
+        stmts(&self.0).nth(1)
+    }
+}
+
+impl LoopExpr {
+    /// Get the body: a [`BlockExpr`], or a single statement after `->`.
+    #[must_use]
+    pub fn body(&self) -> Option<Stmt> {
+        stmts(&self.0).next()
+    }
+}
+
+impl RetExpr {
+    #[must_use]
+    pub fn value(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl CaseExpr {
+    pub fn scrutinees(&self) -> impl Iterator<Item = Expr> {
+        exprs(&self.0)
+    }
+
+    pub fn arms(&self) -> impl Iterator<Item = CaseArm> {
+        self.0.children().filter_map(CaseArm::cast)
+    }
+}
+
+impl CaseArm {
+    pub fn patterns(&self) -> impl Iterator<Item = Pattern> {
+        self.0.children().filter_map(Pattern::cast)
+    }
+
+    #[must_use]
+    pub fn guard(&self) -> Option<CaseGuard> {
+        self.0.children().find_map(CaseGuard::cast)
+    }
+
+    #[must_use]
+    pub fn body(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl CaseGuard {
+    #[must_use]
+    pub fn expr(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl LetStmt {
+    #[must_use]
+    pub fn pattern(&self) -> Option<Pattern> {
+        self.0.children().find_map(Pattern::cast)
+    }
+
+    #[must_use]
+    pub fn value(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl AssignStmt {
+    /// Get the token of the name that's assigned to.
+    #[must_use]
+    pub fn target(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    /// Get the operator token, e.g. `=` or `+=`.
+    #[must_use]
+    pub fn op(&self) -> Option<SyntaxToken> {
+        tokens(&self.0).nth(1)
+    }
+
+    /// Get the operator of a compound assignment, or `None` for a plain
+    /// assignment.
+    #[must_use]
+    pub fn op_kind(&self) -> Option<BinOpKind> {
+        let kind = match self.op()?.kind() {
+            SyntaxKind::PlusEq => BinOpKind::Add,
+            SyntaxKind::MinusEq => BinOpKind::Subtract,
+            SyntaxKind::StarEq => BinOpKind::Multiply,
+            SyntaxKind::SlashEq => BinOpKind::Divide,
+            SyntaxKind::PercentEq => BinOpKind::Modulo,
+            SyntaxKind::StarStarEq => BinOpKind::Exponent,
+            SyntaxKind::PipeEq => BinOpKind::BitOr,
+            SyntaxKind::CaretEq => BinOpKind::BitXor,
+            SyntaxKind::AndEq => BinOpKind::BitAnd,
+            SyntaxKind::LtLtEq => BinOpKind::BitShiftLeft,
+            SyntaxKind::GtGtEq => BinOpKind::BitShiftRight,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    #[must_use]
+    pub fn value(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl FnItem {
+    /// Is the function public (`pub`)?
+    #[must_use]
+    pub fn is_public(&self) -> bool {
+        has_keyword(&self.0, "pub")
+    }
+
+    /// Get the function name's token.
+    #[must_use]
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    #[must_use]
+    pub fn param_list(&self) -> Option<ParamList> {
+        self.0.children().find_map(ParamList::cast)
+    }
+
+    #[must_use]
+    pub fn ret_ty(&self) -> Option<Ty> {
+        self.0.children().find_map(Ty::cast)
+    }
+
+    /// Get the body: an expression after `->`, or a [`BlockExpr`].
+    #[must_use]
+    pub fn body(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl ParamList {
+    pub fn params(&self) -> impl Iterator<Item = Param> {
+        self.0.children().filter_map(Param::cast)
+    }
+}
+
+impl Param {
+    /// Get the parameter name's token.
+    #[must_use]
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    #[must_use]
+    pub fn ty(&self) -> Option<Ty> {
+        self.0.children().find_map(Ty::cast)
+    }
+
+    /// Get the default value (after `\\`), if any.
+    #[must_use]
+    pub fn default(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl NamedTy {
+    /// Get the type name's token.
+    #[must_use]
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    #[must_use]
+    pub fn args(&self) -> Option<TyList> {
+        self.0.children().find_map(TyList::cast)
+    }
+}
+
+impl FnTy {
+    #[must_use]
+    pub fn params(&self) -> Option<TyList> {
+        self.0.children().find_map(TyList::cast)
+    }
+
+    #[must_use]
+    pub fn ret(&self) -> Option<Ty> {
+        self.0.children().find_map(Ty::cast)
+    }
+}
+
+impl TyList {
+    pub fn tys(&self) -> impl Iterator<Item = Ty> {
+        self.0.children().filter_map(Ty::cast)
+    }
+}
+
+impl BindingPattern {
+    /// Is the binding mutable (`mut`)?
+    #[must_use]
+    pub fn is_mut(&self) -> bool {
+        has_keyword(&self.0, "mut")
+    }
+
+    /// Get the bound name's token.
+    #[must_use]
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+}
+
+impl LiteralPattern {
+    #[must_use]
+    pub fn literal(&self) -> Option<Literal> {
+        self.0.children().find_map(Literal::cast)
+    }
+}
+
+impl ConstructorPattern {
+    /// Get the constructor name's token.
+    #[must_use]
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = Pattern> {
+        self.0.children().filter_map(Pattern::cast)
+    }
+}
+
+impl OrPattern {
+    pub fn alternatives(&self) -> impl Iterator<Item = Pattern> {
+        self.0.children().filter_map(Pattern::cast)
+    }
+}
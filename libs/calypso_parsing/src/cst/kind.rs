@@ -0,0 +1,354 @@
+use crate::lexer::TokenType;
+
+macro_rules! syntax_kinds {
+    ($($(#[$attr:meta])* $name:ident,)*) => {
+        /// Kinds of nodes and tokens in the CST.
+        ///
+        /// Token kinds correspond to [`TokenType`]s, without their payloads
+        /// (which can be recovered from the token's text).
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(u16)]
+        pub enum SyntaxKind {
+            $($(#[$attr])* $name,)*
+        }
+
+        impl SyntaxKind {
+            const ALL: &'static [SyntaxKind] = &[$(SyntaxKind::$name,)*];
+        }
+    };
+}
+
+syntax_kinds! {
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `**`
+    StarStar,
+    /// `/`
+    Slash,
+    /// `%`
+    Percent,
+    /// `|`
+    Pipe,
+    /// `||`
+    PipePipe,
+    /// `&`
+    And,
+    /// `&&`
+    AndAnd,
+    /// `!`
+    Bang,
+    /// `^`
+    Caret,
+    /// `>>`
+    GtGt,
+    /// `<<`
+    LtLt,
+    /// `==`
+    EqEq,
+    /// `>=`
+    GtEq,
+    /// `<=`
+    LtEq,
+    /// `!=`
+    BangEq,
+    /// `<`
+    Lt,
+    /// `>`
+    Gt,
+    /// `:`
+    Colon,
+    /// `=`
+    Eq,
+    /// `+=`
+    PlusEq,
+    /// `-=`
+    MinusEq,
+    /// `*=`
+    StarEq,
+    /// `**=`
+    StarStarEq,
+    /// `/=`
+    SlashEq,
+    /// `%=`
+    PercentEq,
+    /// `^=`
+    CaretEq,
+    /// `|=`
+    PipeEq,
+    /// `&=`
+    AndEq,
+    /// `<<=`
+    LtLtEq,
+    /// `>>=`
+    GtGtEq,
+    /// `->`
+    Arrow,
+    /// `|>`
+    PipeGt,
+    /// `,`
+    Comma,
+    /// `;`
+    Semi,
+    /// `.`
+    Dot,
+    /// `..`
+    DotDot,
+    /// `..=`
+    DotDotEq,
+    /// `#`
+    Hash,
+    /// `#!`
+    HashBang,
+    /// `\\`
+    BackslashBackslash,
+    /// `_`
+    Under,
+    /// `(`
+    LParen,
+    /// `)`
+    RParen,
+    /// `{`
+    LBrace,
+    /// `}`
+    RBrace,
+    /// `[`
+    LBracket,
+    /// `]`
+    RBracket,
+    /// Unexpected characters
+    Unexpected,
+    /// Identifier
+    Ident,
+    /// Whitespace, not including line breaks
+    Ws,
+    /// A line break
+    Newline,
+    /// Line comment
+    LineComment,
+    /// Block comment
+    BlockComment,
+    /// A shebang on the first line of a file
+    Shebang,
+    /// Keyword
+    Keyword,
+    /// Atom
+    Atom,
+    /// String literal
+    String,
+    /// Raw string literal
+    RawString,
+    /// Multi-line string literal
+    MultilineString,
+    /// The opening `"` of a string literal containing interpolations
+    StringStart,
+    /// A part of an interpolated string literal that is not an interpolation
+    StringFragment,
+    /// The closing `"` of a string literal containing interpolations
+    StringEnd,
+    /// `${`, within a string literal
+    InterpStart,
+    /// The `}` ending an interpolation
+    InterpEnd,
+    /// Character literal
+    Char,
+    /// Byte string literal
+    ByteString,
+    /// Byte literal
+    Byte,
+    /// Integer literal
+    Int,
+    /// Float literal
+    Float,
+
+    /// The root of a CST
+    Root,
+    /// A binary operation, e.g. `a + b`
+    BinExpr,
+    /// A unary operation, e.g. `-a`
+    UnaryExpr,
+    /// A parenthesized expression, e.g. `(a)`
+    ParenExpr,
+    /// A literal, e.g. `1` or `"a"`
+    Literal,
+    /// A string literal containing interpolations, e.g. `"a ${b} c"`
+    InterpString,
+    /// An interpolation within a string literal, e.g. `${b}`
+    Interpolation,
+    /// A reference to a name, e.g. `a`
+    NameRef,
+    /// A function call, e.g. `f(a, b)`
+    CallExpr,
+    /// The arguments of a function call, e.g. `(a, b)`
+    ArgList,
+    /// A field access, e.g. `a.b`
+    FieldExpr,
+    /// A block of statements, e.g. `do a end`
+    BlockExpr,
+    /// An `if` expression, e.g. `if a -> b else -> c`
+    IfExpr,
+    /// A `while` loop, e.g. `while a -> b`
+    WhileExpr,
+    /// A `for` loop, e.g. `for x in xs -> f(x)`
+    ForExpr,
+    /// A `loop`, e.g. `loop -> f()`
+    LoopExpr,
+    /// `break`
+    BreakExpr,
+    /// `continue`
+    ContinueExpr,
+    /// A return, e.g. `ret a`
+    RetExpr,
+    /// A `case` expression, e.g. `case a do 0 -> b end`
+    CaseExpr,
+    /// An arm of a `case` expression, e.g. `0 -> b`
+    CaseArm,
+    /// The guard of an arm of a `case` expression, e.g. `where n > 0`
+    CaseGuard,
+    /// A `let` statement, e.g. `let a = 1`
+    LetStmt,
+    /// An assignment, e.g. `a = 1` or `a += 1`
+    AssignStmt,
+    /// A function declaration, e.g. `fn f(a) -> a`
+    FnItem,
+    /// The parameters of a function declaration, e.g. `(a: int, b)`
+    ParamList,
+    /// A parameter of a function declaration, e.g. `a: int \\ 1`
+    Param,
+    /// A named type, e.g. `int` or `List(int)`
+    NamedTy,
+    /// A function type, e.g. `fn(int): int`
+    FnTy,
+    /// The arguments of a named type or the parameters of a function type,
+    /// e.g. `(int, bool)`
+    TyList,
+    /// A pattern that binds a name, e.g. `a` or `mut a`
+    BindingPattern,
+    /// `_`
+    WildcardPattern,
+    /// A literal pattern, e.g. `1`
+    LiteralPattern,
+    /// A constructor pattern, e.g. `Pair(a, b)`
+    ConstructorPattern,
+    /// A pattern with alternatives, e.g. `0 | 1`
+    OrPattern,
+    /// Tokens that could not be parsed
+    Error,
+}
+
+impl SyntaxKind {
+    /// Is this kind of token trivia (i.e. whitespace, newlines, comments or
+    /// a shebang)?
+    #[must_use]
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            Self::Ws | Self::Newline | Self::LineComment | Self::BlockComment | Self::Shebang
+        )
+    }
+}
+
+impl From<TokenType> for SyntaxKind {
+    fn from(ty: TokenType) -> Self {
+        match ty {
+            TokenType::Plus => Self::Plus,
+            TokenType::Minus => Self::Minus,
+            TokenType::Star => Self::Star,
+            TokenType::StarStar => Self::StarStar,
+            TokenType::Slash => Self::Slash,
+            TokenType::Percent => Self::Percent,
+            TokenType::Pipe => Self::Pipe,
+            TokenType::PipePipe => Self::PipePipe,
+            TokenType::And => Self::And,
+            TokenType::AndAnd => Self::AndAnd,
+            TokenType::Bang => Self::Bang,
+            TokenType::Caret => Self::Caret,
+            TokenType::GtGt => Self::GtGt,
+            TokenType::LtLt => Self::LtLt,
+            TokenType::EqEq => Self::EqEq,
+            TokenType::GtEq => Self::GtEq,
+            TokenType::LtEq => Self::LtEq,
+            TokenType::BangEq => Self::BangEq,
+            TokenType::Lt => Self::Lt,
+            TokenType::Gt => Self::Gt,
+            TokenType::Colon => Self::Colon,
+            TokenType::Eq => Self::Eq,
+            TokenType::PlusEq => Self::PlusEq,
+            TokenType::MinusEq => Self::MinusEq,
+            TokenType::StarEq => Self::StarEq,
+            TokenType::StarStarEq => Self::StarStarEq,
+            TokenType::SlashEq => Self::SlashEq,
+            TokenType::PercentEq => Self::PercentEq,
+            TokenType::CaretEq => Self::CaretEq,
+            TokenType::PipeEq => Self::PipeEq,
+            TokenType::AndEq => Self::AndEq,
+            TokenType::LtLtEq => Self::LtLtEq,
+            TokenType::GtGtEq => Self::GtGtEq,
+            TokenType::Arrow => Self::Arrow,
+            TokenType::PipeGt => Self::PipeGt,
+            TokenType::Comma => Self::Comma,
+            TokenType::Semi => Self::Semi,
+            TokenType::Dot => Self::Dot,
+            TokenType::DotDot => Self::DotDot,
+            TokenType::DotDotEq => Self::DotDotEq,
+            TokenType::Hash => Self::Hash,
+            TokenType::HashBang => Self::HashBang,
+            TokenType::BackslashBackslash => Self::BackslashBackslash,
+            TokenType::Under => Self::Under,
+            TokenType::LParen => Self::LParen,
+            TokenType::RParen => Self::RParen,
+            TokenType::LBrace => Self::LBrace,
+            TokenType::RBrace => Self::RBrace,
+            TokenType::LBracket => Self::LBracket,
+            TokenType::RBracket => Self::RBracket,
+            // EOF tokens are empty, so they never end up in the CST.
+            TokenType::Eof | TokenType::Unexpected => Self::Unexpected,
+            TokenType::Ident(_) => Self::Ident,
+            TokenType::Ws => Self::Ws,
+            TokenType::Newline => Self::Newline,
+            TokenType::LineComment { .. } => Self::LineComment,
+            TokenType::BlockComment { .. } => Self::BlockComment,
+            TokenType::Shebang => Self::Shebang,
+            TokenType::Keyword(_) => Self::Keyword,
+            TokenType::Atom(_) => Self::Atom,
+            TokenType::String(_) => Self::String,
+            TokenType::RawString { .. } => Self::RawString,
+            TokenType::MultilineString(_) => Self::MultilineString,
+            TokenType::StringStart => Self::StringStart,
+            TokenType::StringFragment(_) => Self::StringFragment,
+            TokenType::StringEnd => Self::StringEnd,
+            TokenType::InterpStart => Self::InterpStart,
+            TokenType::InterpEnd => Self::InterpEnd,
+            TokenType::Char(_) => Self::Char,
+            TokenType::ByteString => Self::ByteString,
+            TokenType::Byte(_) => Self::Byte,
+            TokenType::Int { .. } => Self::Int,
+            TokenType::Float => Self::Float,
+        }
+    }
+}
+
+impl From<SyntaxKind> for rowan::SyntaxKind {
+    fn from(kind: SyntaxKind) -> Self {
+        Self(kind as u16)
+    }
+}
+
+/// The Calypso language, for use with [`rowan`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CalypsoLang {}
+
+impl rowan::Language for CalypsoLang {
+    type Kind = SyntaxKind;
+
+    fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+        SyntaxKind::ALL[usize::from(raw.0)]
+    }
+
+    fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+        kind.into()
+    }
+}
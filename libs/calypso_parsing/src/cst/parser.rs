@@ -0,0 +1,941 @@
+use std::collections::HashSet;
+
+use rowan::{GreenNode, GreenNodeBuilder};
+
+use calypso_base::span::{Span, Spanned};
+use calypso_base::symbol::kw::Keyword;
+
+use super::ast::{AstNode, Root};
+use super::{SyntaxKind, SyntaxNode};
+use crate::lexer::{Token, TokenType};
+use crate::parser::pratt::{Assoc, BINARY_OPERATORS};
+use crate::parser::tokens::{process_iter, Tok};
+
+/// The result of parsing a CST.
+#[derive(Clone, Debug)]
+pub struct Parse {
+    green: GreenNode,
+    errors: Vec<Spanned<String>>,
+}
+
+impl Parse {
+    /// Get the root of the CST.
+    #[must_use]
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    /// Get the typed AST view of the root of the CST.
+    ///
+    /// # Panics
+    ///
+    /// This function should not panic.
+    #[must_use]
+    pub fn root(&self) -> Root {
+        Root::cast(self.syntax()).unwrap()
+    }
+
+    /// Get the errors that were found while parsing. These are also
+    /// represented in the CST as [`SyntaxKind::Error`] nodes, or missing
+    /// nodes.
+    #[must_use]
+    pub fn errors(&self) -> &[Spanned<String>] {
+        &self.errors
+    }
+}
+
+/// Parse a CST from the tokens of a source file, including whitespace,
+/// newlines and comments.
+///
+/// This never fails: tokens that can't be parsed are kept within
+/// [`SyntaxKind::Error`] nodes, so the CST always contains all of the tokens.
+#[must_use]
+pub fn parse(tokens: &[Token<'_>]) -> Parse {
+    // Only the newlines that could end a statement are significant, as in
+    // the other parsers. The rest are trivia.
+    let newlines = process_iter(tokens.iter().copied(), true)
+        .filter(|&(_, tok, _)| tok == Tok::Unprocessed(TokenType::Newline))
+        .map(|(lo, _, _)| lo)
+        .collect();
+    let mut parser = Parser {
+        tokens,
+        newlines,
+        pos: 0,
+        builder: GreenNodeBuilder::new(),
+        closers: Vec::new(),
+        errors: Vec::new(),
+    };
+    parser.root();
+    Parse {
+        green: parser.builder.finish(),
+        errors: parser.errors,
+    }
+}
+
+/// The binding power of unary operators, which bind more tightly than any
+/// binary operator.
+const UNARY_BP: u8 = u8::MAX;
+
+/// Get the left and right binding powers of a binary operator, from its
+/// precedence and associativity in [`BINARY_OPERATORS`]. A higher binding
+/// power on the right than on the left makes an operator left-associative,
+/// and vice versa.
+fn infix_bp(kind: SyntaxKind) -> Option<(u8, u8)> {
+    let &(_, _, prec, assoc) = BINARY_OPERATORS
+        .iter()
+        .find(|&&(ty, ..)| SyntaxKind::from(ty) == kind)?;
+    let bp = prec * 2;
+    match assoc {
+        Assoc::Left => Some((bp, bp + 1)),
+        Assoc::Right => Some((bp + 1, bp)),
+    }
+}
+
+/// Check if `kind` is the operator of a compound assignment, e.g. `*=`.
+fn is_assign_op(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::PlusEq
+            | SyntaxKind::MinusEq
+            | SyntaxKind::StarEq
+            | SyntaxKind::StarStarEq
+            | SyntaxKind::SlashEq
+            | SyntaxKind::PercentEq
+            | SyntaxKind::CaretEq
+            | SyntaxKind::PipeEq
+            | SyntaxKind::AndEq
+            | SyntaxKind::LtLtEq
+            | SyntaxKind::GtGtEq
+    )
+}
+
+/// Check if `kind` is a literal token (other than `true` and `false`).
+fn is_literal(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Int
+            | SyntaxKind::Float
+            | SyntaxKind::String
+            | SyntaxKind::RawString
+            | SyntaxKind::MultilineString
+            | SyntaxKind::Char
+            | SyntaxKind::ByteString
+            | SyntaxKind::Byte
+            | SyntaxKind::Atom
+    )
+}
+
+/// A token that ends a construct.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Closer {
+    Token(SyntaxKind),
+    Keyword(Keyword),
+}
+
+// The tokens that end each kind of construct. Recovery from a syntax error
+// stops at one of those of the constructs that the parser is in.
+const PAREN: &[Closer] = &[Closer::Token(SyntaxKind::RParen)];
+const INTERP: &[Closer] = &[Closer::Token(SyntaxKind::InterpEnd)];
+const PROGRAM: &[Closer] = &[Closer::Token(SyntaxKind::Newline)];
+const BLOCK: &[Closer] = &[
+    Closer::Token(SyntaxKind::Newline),
+    Closer::Keyword(Keyword::End),
+];
+const THEN_BLOCK: &[Closer] = &[
+    Closer::Token(SyntaxKind::Newline),
+    Closer::Keyword(Keyword::Else),
+    Closer::Keyword(Keyword::End),
+];
+const CASE_ARMS: &[Closer] = &[
+    Closer::Token(SyntaxKind::Newline),
+    Closer::Token(SyntaxKind::Comma),
+    Closer::Keyword(Keyword::End),
+];
+
+struct Parser<'a, 'lex> {
+    tokens: &'a [Token<'lex>],
+    /// The locations of the newlines that could end a statement.
+    newlines: HashSet<usize>,
+    pos: usize,
+    builder: GreenNodeBuilder<'static>,
+    /// The tokens that end each of the constructs (e.g. parentheses and
+    /// blocks) that we're in, innermost last.
+    closers: Vec<&'static [Closer]>,
+    errors: Vec<Spanned<String>>,
+}
+
+impl<'a, 'lex> Parser<'a, 'lex> {
+    fn root(&mut self) {
+        self.builder.start_node(SyntaxKind::Root.into());
+        self.stmts(PROGRAM);
+        self.skip_trivia();
+        self.builder.finish_node();
+    }
+
+    /// Parse statements separated by newlines, up to a token that ends the
+    /// block (one of `closers`) or an enclosing construct.
+    fn stmts(&mut self, closers: &'static [Closer]) {
+        self.closers.push(closers);
+        loop {
+            match self.peek() {
+                None => break,
+                Some(kind) if kind != SyntaxKind::Newline && self.at_closer() => break,
+                Some(_) => {}
+            }
+            self.stmt();
+            if self.peek() != Some(SyntaxKind::Newline) {
+                self.recover("expected the end of the statement");
+            }
+            if self.peek() == Some(SyntaxKind::Newline) {
+                self.bump();
+            }
+        }
+        self.closers.pop();
+    }
+
+    fn stmt(&mut self) {
+        match self.peek_keyword() {
+            Some(Keyword::Let) => {
+                self.start_node(SyntaxKind::LetStmt);
+                self.bump();
+                self.pattern();
+                if self.peek() == Some(SyntaxKind::Eq) {
+                    self.bump();
+                    self.expr();
+                }
+                self.builder.finish_node();
+            }
+            Some(Keyword::Pub) | Some(Keyword::Fn) => self.fn_item(),
+            _ if self.at_assignment() => {
+                self.start_node(SyntaxKind::AssignStmt);
+                self.bump();
+                self.bump();
+                self.expr();
+                self.builder.finish_node();
+            }
+            _ => self.expr(),
+        }
+    }
+
+    fn fn_item(&mut self) {
+        self.start_node(SyntaxKind::FnItem);
+        self.eat_keyword(Keyword::Pub);
+        self.expect_keyword(Keyword::Fn, "expected `fn`");
+        self.expect(SyntaxKind::Ident, "expected a function name");
+        if self.peek() == Some(SyntaxKind::LParen) {
+            self.param_list();
+        } else {
+            self.report("expected `(`");
+        }
+        if self.peek() == Some(SyntaxKind::Colon) {
+            self.bump();
+            self.ty();
+        }
+
+        // The body is either an expression, or a block of statements after
+        // `-> do` or a `->` that ends a line.
+        if self.at_arrow_newline() {
+            self.block_expr();
+        } else {
+            self.expect(SyntaxKind::Arrow, "expected `->`");
+            if self.at_keyword(Keyword::Do) && self.peek_nth(1) != Some(SyntaxKind::Arrow) {
+                self.block_expr();
+            } else {
+                self.expr();
+            }
+        }
+        self.builder.finish_node();
+    }
+
+    fn param_list(&mut self) {
+        self.start_node(SyntaxKind::ParamList);
+        self.list(|parser| {
+            parser.start_node(SyntaxKind::Param);
+            parser.expect(SyntaxKind::Ident, "expected a parameter name");
+            if parser.peek() == Some(SyntaxKind::Colon) {
+                parser.bump();
+                parser.ty();
+            }
+            if parser.peek() == Some(SyntaxKind::BackslashBackslash) {
+                parser.bump();
+                parser.expr();
+            }
+            parser.builder.finish_node();
+        });
+        self.builder.finish_node();
+    }
+
+    fn ty(&mut self) {
+        match self.peek() {
+            Some(SyntaxKind::Ident) => {
+                self.start_node(SyntaxKind::NamedTy);
+                self.bump();
+                if self.peek() == Some(SyntaxKind::LParen) {
+                    self.ty_list();
+                }
+                self.builder.finish_node();
+            }
+            Some(SyntaxKind::Keyword) if self.at_keyword(Keyword::Fn) => {
+                self.start_node(SyntaxKind::FnTy);
+                self.bump();
+                if self.peek() == Some(SyntaxKind::LParen) {
+                    self.ty_list();
+                } else {
+                    self.report("expected `(`");
+                }
+                if self.peek() == Some(SyntaxKind::Colon) {
+                    self.bump();
+                    self.ty();
+                }
+                self.builder.finish_node();
+            }
+            _ => self.error("expected a type"),
+        }
+    }
+
+    fn ty_list(&mut self) {
+        self.start_node(SyntaxKind::TyList);
+        self.list(Self::ty);
+        self.builder.finish_node();
+    }
+
+    /// Parse a pattern, which may have alternatives separated by `|`.
+    fn pattern(&mut self) {
+        self.skip_trivia();
+        let checkpoint = self.builder.checkpoint();
+        self.single_pattern();
+        if self.peek() == Some(SyntaxKind::Pipe) {
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::OrPattern.into());
+            while self.peek() == Some(SyntaxKind::Pipe) {
+                self.bump();
+                self.single_pattern();
+            }
+            self.builder.finish_node();
+        }
+    }
+
+    /// Parse a pattern without any alternatives.
+    fn single_pattern(&mut self) {
+        match self.peek() {
+            Some(SyntaxKind::Keyword) if self.at_keyword(Keyword::Mut) => {
+                self.start_node(SyntaxKind::BindingPattern);
+                self.bump();
+                self.expect(SyntaxKind::Ident, "expected a name");
+                self.builder.finish_node();
+            }
+            Some(SyntaxKind::Ident) if self.peek_nth(1) == Some(SyntaxKind::LParen) => {
+                self.start_node(SyntaxKind::ConstructorPattern);
+                self.bump();
+                self.list(Self::pattern);
+                self.builder.finish_node();
+            }
+            Some(SyntaxKind::Ident) => {
+                self.start_node(SyntaxKind::BindingPattern);
+                self.bump();
+                self.builder.finish_node();
+            }
+            Some(SyntaxKind::Under) => {
+                self.start_node(SyntaxKind::WildcardPattern);
+                self.bump();
+                self.builder.finish_node();
+            }
+            Some(kind)
+                if is_literal(kind)
+                    || self.at_keyword(Keyword::True)
+                    || self.at_keyword(Keyword::False) =>
+            {
+                self.start_node(SyntaxKind::LiteralPattern);
+                self.literal();
+                self.builder.finish_node();
+            }
+            _ => self.error("expected a pattern"),
+        }
+    }
+
+    /// Parse an expression, which may be a control flow expression.
+    fn expr(&mut self) {
+        match self.peek_keyword() {
+            Some(Keyword::If) => self.if_expr(),
+            Some(Keyword::While) => {
+                self.start_node(SyntaxKind::WhileExpr);
+                self.bump();
+                self.binary(0);
+                self.branch();
+                self.builder.finish_node();
+            }
+            Some(Keyword::For) => {
+                self.start_node(SyntaxKind::ForExpr);
+                self.bump();
+                self.pattern();
+                self.expect_keyword(Keyword::In, "expected `in`");
+                self.binary(0);
+                self.branch();
+                self.builder.finish_node();
+            }
+            Some(Keyword::Loop) => {
+                self.start_node(SyntaxKind::LoopExpr);
+                self.bump();
+                self.branch();
+                self.builder.finish_node();
+            }
+            Some(Keyword::Break) => {
+                self.start_node(SyntaxKind::BreakExpr);
+                self.bump();
+                self.builder.finish_node();
+            }
+            Some(Keyword::Continue) => {
+                self.start_node(SyntaxKind::ContinueExpr);
+                self.bump();
+                self.builder.finish_node();
+            }
+            Some(Keyword::Ret) => {
+                self.start_node(SyntaxKind::RetExpr);
+                self.bump();
+                if self.at_expr() {
+                    self.expr();
+                }
+                self.builder.finish_node();
+            }
+            _ => self.binary(0),
+        }
+    }
+
+    /// Parse an `if` expression: either on one line (`if a -> b else -> c`)
+    /// or with blocks (`if a do ... else ... end`, or `if a ->` with the
+    /// blocks on the following lines).
+    fn if_expr(&mut self) {
+        self.start_node(SyntaxKind::IfExpr);
+        self.bump();
+        self.binary(0);
+        if self.at_keyword(Keyword::Do) {
+            self.start_node(SyntaxKind::BlockExpr);
+            self.bump();
+            self.stmts(THEN_BLOCK);
+            if self.at_keyword(Keyword::Else) {
+                self.builder.finish_node();
+                self.bump();
+                self.start_node(SyntaxKind::BlockExpr);
+                self.stmts(BLOCK);
+                self.expect_keyword(Keyword::End, "expected `end`");
+            } else {
+                self.expect_keyword(Keyword::End, "expected `else` or `end`");
+            }
+            self.builder.finish_node();
+        } else if self.at_arrow_newline() {
+            self.arrow_if();
+        } else if self.peek() == Some(SyntaxKind::Arrow) {
+            self.bump();
+            // An `else` on one line belongs to the innermost `if`, so the
+            // `then` branch can't be another control flow expression.
+            if self.at_operand() {
+                self.binary(0);
+                if self.eat_keyword(Keyword::Else) {
+                    self.else_branch();
+                } else {
+                    self.eat_keyword(Keyword::End);
+                }
+            } else {
+                self.stmt();
+                self.eat_keyword(Keyword::End);
+            }
+        } else {
+            self.report("expected `->` or `do`");
+        }
+        self.builder.finish_node();
+    }
+
+    /// Parse the rest of an `if` expression with blocks after `->`, from the
+    /// `->`. The whole `else if` chain is closed by a single `end`, which
+    /// belongs to the innermost block.
+    fn arrow_if(&mut self) {
+        self.start_node(SyntaxKind::BlockExpr);
+        self.bump();
+        self.bump();
+        self.stmts(THEN_BLOCK);
+        if !self.at_keyword(Keyword::Else) {
+            self.expect_keyword(Keyword::End, "expected `else` or `end`");
+            self.builder.finish_node();
+            return;
+        }
+        self.builder.finish_node();
+        self.bump();
+
+        if self.at_keyword(Keyword::If) {
+            self.start_node(SyntaxKind::IfExpr);
+            self.bump();
+            self.binary(0);
+            if self.at_arrow_newline() {
+                self.arrow_if();
+            } else {
+                self.report("expected `->` and a newline");
+            }
+            self.builder.finish_node();
+        } else if self.at_arrow_newline() {
+            self.block_expr();
+        } else {
+            self.report("expected `->` or `if`");
+        }
+    }
+
+    /// Parse the branch after the `else` of an `if` expression on one line,
+    /// which may be another `if` expression.
+    fn else_branch(&mut self) {
+        if self.at_keyword(Keyword::If) {
+            self.if_expr();
+        } else {
+            self.branch();
+        }
+    }
+
+    /// Parse the body of a control flow expression: a single statement after
+    /// `->` (which may be closed by an `end` on the same line), or a block
+    /// after `do` or a `->` that ends a line.
+    fn branch(&mut self) {
+        if self.at_arrow_newline() || self.at_keyword(Keyword::Do) {
+            self.block_expr();
+        } else if self.peek() == Some(SyntaxKind::Arrow) {
+            self.bump();
+            self.stmt();
+            self.eat_keyword(Keyword::End);
+        } else {
+            self.report("expected `->` or `do`");
+        }
+    }
+
+    /// Parse a block from its opening `do` or `->` to its `end`.
+    fn block_expr(&mut self) {
+        self.start_node(SyntaxKind::BlockExpr);
+        self.bump();
+        self.block_body();
+        self.builder.finish_node();
+    }
+
+    /// Parse the statements of a block (which may start on the next line)
+    /// and its `end`.
+    fn block_body(&mut self) {
+        if self.peek() == Some(SyntaxKind::Newline) {
+            self.bump();
+        }
+        self.stmts(BLOCK);
+        self.expect_keyword(Keyword::End, "expected `end`");
+    }
+
+    fn binary(&mut self, min_bp: u8) {
+        self.skip_trivia();
+        let checkpoint = self.builder.checkpoint();
+
+        match self.peek() {
+            Some(SyntaxKind::Minus) | Some(SyntaxKind::Bang) => self.unary(),
+            _ => self.postfix(),
+        }
+
+        while let Some((l_bp, r_bp)) = self.peek().and_then(infix_bp) {
+            if l_bp < min_bp {
+                break;
+            }
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::BinExpr.into());
+            self.bump();
+            self.binary(r_bp);
+            self.builder.finish_node();
+        }
+    }
+
+    fn unary(&mut self) {
+        self.start_node(SyntaxKind::UnaryExpr);
+        self.bump();
+        self.binary(UNARY_BP);
+        self.builder.finish_node();
+    }
+
+    /// Parse an atom followed by any calls and field accesses.
+    fn postfix(&mut self) {
+        let checkpoint = self.builder.checkpoint();
+        self.atom();
+        loop {
+            match self.peek() {
+                Some(SyntaxKind::LParen) => {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::CallExpr.into());
+                    self.start_node(SyntaxKind::ArgList);
+                    self.list(Self::expr);
+                    self.builder.finish_node();
+                    self.builder.finish_node();
+                }
+                Some(SyntaxKind::Dot) => {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::FieldExpr.into());
+                    self.bump();
+                    self.expect(SyntaxKind::Ident, "expected a field name");
+                    self.builder.finish_node();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Parse a comma-separated list (with an optional trailing comma) of
+    /// `item`s in parentheses, from the `(`.
+    fn list(&mut self, mut item: impl FnMut(&mut Self)) {
+        self.bump();
+        self.closers.push(PAREN);
+        while !matches!(self.peek(), Some(SyntaxKind::RParen) | None) {
+            item(self);
+            if self.peek() == Some(SyntaxKind::Comma) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        self.closers.pop();
+        self.expect(SyntaxKind::RParen, "expected `)`");
+    }
+
+    fn atom(&mut self) {
+        match self.peek() {
+            Some(kind) if is_literal(kind) => self.literal(),
+            Some(SyntaxKind::Keyword) => match self.peek_keyword() {
+                Some(Keyword::True) | Some(Keyword::False) => self.literal(),
+                Some(Keyword::Do) => {
+                    self.start_node(SyntaxKind::BlockExpr);
+                    self.bump();
+                    self.expect(SyntaxKind::Arrow, "expected `->`");
+                    self.block_body();
+                    self.builder.finish_node();
+                }
+                Some(Keyword::Case) => self.case_expr(),
+                _ => self.error("expected an expression"),
+            },
+            Some(SyntaxKind::Ident) => {
+                self.start_node(SyntaxKind::NameRef);
+                self.bump();
+                self.builder.finish_node();
+            }
+            Some(SyntaxKind::LParen) => {
+                self.start_node(SyntaxKind::ParenExpr);
+                self.bump();
+                self.closers.push(PAREN);
+                self.expr();
+                self.closers.pop();
+                self.expect(SyntaxKind::RParen, "expected `)`");
+                self.builder.finish_node();
+            }
+            Some(SyntaxKind::StringStart) => self.interp_string(),
+            _ => self.error("expected an expression"),
+        }
+    }
+
+    fn literal(&mut self) {
+        self.start_node(SyntaxKind::Literal);
+        self.bump();
+        self.builder.finish_node();
+    }
+
+    fn case_expr(&mut self) {
+        self.start_node(SyntaxKind::CaseExpr);
+        self.bump();
+        self.binary(0);
+        while self.peek() == Some(SyntaxKind::Comma) {
+            self.bump();
+            self.binary(0);
+        }
+        self.expect_keyword(Keyword::Do, "expected `do`");
+
+        // The arms are separated by newlines or commas.
+        self.closers.push(CASE_ARMS);
+        loop {
+            match self.peek() {
+                None => break,
+                Some(SyntaxKind::Newline) | Some(SyntaxKind::Comma) => {}
+                Some(_) if self.at_closer() => break,
+                Some(_) => {}
+            }
+            self.case_arm();
+            if !matches!(
+                self.peek(),
+                Some(SyntaxKind::Newline) | Some(SyntaxKind::Comma)
+            ) {
+                self.recover("expected the end of the arm");
+            }
+            if matches!(
+                self.peek(),
+                Some(SyntaxKind::Newline) | Some(SyntaxKind::Comma)
+            ) {
+                self.bump();
+            }
+        }
+        self.closers.pop();
+
+        self.expect_keyword(Keyword::End, "expected `end`");
+        self.builder.finish_node();
+    }
+
+    /// Parse an arm of a `case` expression, e.g. `n, acc where n > 0 -> n *
+    /// acc`.
+    fn case_arm(&mut self) {
+        self.start_node(SyntaxKind::CaseArm);
+        self.pattern();
+        while self.peek() == Some(SyntaxKind::Comma) {
+            self.bump();
+            self.pattern();
+        }
+        if self.at_keyword(Keyword::Where) {
+            self.start_node(SyntaxKind::CaseGuard);
+            self.bump();
+            self.expr();
+            self.builder.finish_node();
+        }
+        self.expect(SyntaxKind::Arrow, "expected `->`");
+        if self.peek() == Some(SyntaxKind::Newline) {
+            self.bump();
+        }
+        self.expr();
+        self.builder.finish_node();
+    }
+
+    fn interp_string(&mut self) {
+        self.start_node(SyntaxKind::InterpString);
+        self.bump();
+        loop {
+            match self.peek() {
+                Some(SyntaxKind::StringFragment) => self.bump(),
+                Some(SyntaxKind::InterpStart) => {
+                    self.start_node(SyntaxKind::Interpolation);
+                    self.bump();
+                    self.closers.push(INTERP);
+                    self.expr();
+                    self.closers.pop();
+                    self.expect(SyntaxKind::InterpEnd, "expected `}`");
+                    self.builder.finish_node();
+                }
+                Some(SyntaxKind::StringEnd) => {
+                    self.bump();
+                    break;
+                }
+                Some(_) if !self.at_closer() => {
+                    self.error("expected the rest of a string literal");
+                }
+                // The lexer has already reported unterminated strings.
+                _ => break,
+            }
+        }
+        self.builder.finish_node();
+    }
+
+    /// Check if the next statement is an assignment, e.g. `a = 1`.
+    fn at_assignment(&self) -> bool {
+        self.peek() == Some(SyntaxKind::Ident)
+            && matches!(
+                self.peek_nth(1),
+                Some(kind) if kind == SyntaxKind::Eq || is_assign_op(kind)
+            )
+    }
+
+    /// Check if the next statement is an expression that's made up of
+    /// operands and operators, i.e. not a control flow expression, a
+    /// binding, an assignment or an item.
+    fn at_operand(&self) -> bool {
+        let control = matches!(
+            self.peek_keyword(),
+            Some(Keyword::Let)
+                | Some(Keyword::Pub)
+                | Some(Keyword::Fn)
+                | Some(Keyword::If)
+                | Some(Keyword::While)
+                | Some(Keyword::For)
+                | Some(Keyword::Loop)
+                | Some(Keyword::Break)
+                | Some(Keyword::Continue)
+                | Some(Keyword::Ret)
+        );
+        !control && !self.at_assignment()
+    }
+
+    /// Check if the next token can begin an expression.
+    fn at_expr(&self) -> bool {
+        match self.peek() {
+            Some(SyntaxKind::Keyword) => matches!(
+                self.peek_keyword(),
+                Some(Keyword::True)
+                    | Some(Keyword::False)
+                    | Some(Keyword::Do)
+                    | Some(Keyword::Case)
+                    | Some(Keyword::If)
+                    | Some(Keyword::While)
+                    | Some(Keyword::For)
+                    | Some(Keyword::Loop)
+                    | Some(Keyword::Break)
+                    | Some(Keyword::Continue)
+                    | Some(Keyword::Ret)
+            ),
+            Some(kind) => {
+                is_literal(kind)
+                    || matches!(
+                        kind,
+                        SyntaxKind::Ident
+                            | SyntaxKind::LParen
+                            | SyntaxKind::Minus
+                            | SyntaxKind::Bang
+                            | SyntaxKind::StringStart
+                    )
+            }
+            None => false,
+        }
+    }
+
+    /// Check if the next tokens are a `->` that ends a line.
+    fn at_arrow_newline(&self) -> bool {
+        self.peek() == Some(SyntaxKind::Arrow) && self.peek_nth(1) == Some(SyntaxKind::Newline)
+    }
+
+    /// Check if the next token ends one of the constructs that we're in, up
+    /// to the innermost parenthesis or string interpolation (as e.g. a
+    /// newline can't end a block within them).
+    fn at_closer(&self) -> bool {
+        let kind = self.peek();
+        let keyword = self.peek_keyword();
+        for &closers in self.closers.iter().rev() {
+            let closes = closers.iter().any(|&closer| match closer {
+                Closer::Token(closer) => Some(closer) == kind,
+                Closer::Keyword(closer) => Some(closer) == keyword,
+            });
+            if closes {
+                return true;
+            }
+            if closers == PAREN || closers == INTERP {
+                break;
+            }
+        }
+        false
+    }
+
+    /// Bump the current token if it is of the expected kind, otherwise
+    /// report an error without consuming anything.
+    fn expect(&mut self, kind: SyntaxKind, message: &str) {
+        if self.peek() == Some(kind) {
+            self.bump();
+        } else {
+            self.report(message);
+        }
+    }
+
+    /// Bump the current token if it is the expected keyword, otherwise
+    /// report an error without consuming anything.
+    fn expect_keyword(&mut self, keyword: Keyword, message: &str) {
+        if !self.eat_keyword(keyword) {
+            self.report(message);
+        }
+    }
+
+    /// Bump the current token if it is `keyword`. Returns whether it was.
+    fn eat_keyword(&mut self, keyword: Keyword) -> bool {
+        let eaten = self.at_keyword(keyword);
+        if eaten {
+            self.bump();
+        }
+        eaten
+    }
+
+    /// Report an error at the current token, without consuming anything.
+    fn report(&mut self, message: &str) {
+        self.errors
+            .push(Spanned::new(self.current_span(), message.to_string()));
+    }
+
+    /// Report an error, wrapping the current token (if any) in an error
+    /// node. A token that ends an enclosing construct is left for it.
+    fn error(&mut self, message: &str) {
+        self.report(message);
+        if self.peek().is_some() && !self.at_closer() {
+            self.start_node(SyntaxKind::Error);
+            self.bump();
+            self.builder.finish_node();
+        }
+    }
+
+    /// Report an error for each token up to the next one that ends an
+    /// enclosing construct, wrapping them in error nodes.
+    fn recover(&mut self, message: &str) {
+        while self.peek().is_some() && !self.at_closer() {
+            self.error(message);
+        }
+    }
+
+    /// Start a node. Any trivia before the next token is added to the
+    /// current node first, so that nodes never start with trivia.
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.skip_trivia();
+        self.builder.start_node(kind.into());
+    }
+
+    /// Get the kind of the next token that isn't trivia.
+    fn peek(&self) -> Option<SyntaxKind> {
+        self.peek_nth(0)
+    }
+
+    /// Get the kind of the `n`th next token that isn't trivia.
+    fn peek_nth(&self, n: usize) -> Option<SyntaxKind> {
+        self.tokens_ahead()
+            .nth(n)
+            .map(|token| SyntaxKind::from(token.value().0))
+    }
+
+    /// Get the next token if it is a keyword.
+    fn peek_keyword(&self) -> Option<Keyword> {
+        match self.tokens_ahead().next()?.value().0 {
+            TokenType::Keyword(symbol) => Some(Keyword::from(symbol)),
+            _ => None,
+        }
+    }
+
+    fn at_keyword(&self, keyword: Keyword) -> bool {
+        self.peek_keyword() == Some(keyword)
+    }
+
+    /// Get the tokens from the current one on that aren't trivia.
+    fn tokens_ahead(&self) -> impl Iterator<Item = &'a Token<'lex>> + '_ {
+        let tokens = self.tokens;
+        (self.pos..tokens.len())
+            .filter(move |&index| !self.is_trivia(index))
+            .map(move |index| &tokens[index])
+    }
+
+    /// Check if the token at `index` is trivia. Newlines are, unless they
+    /// could end a statement.
+    fn is_trivia(&self, index: usize) -> bool {
+        let token = &self.tokens[index];
+        let kind = SyntaxKind::from(token.value().0);
+        kind.is_trivia()
+            && !(kind == SyntaxKind::Newline && self.newlines.contains(&token.span().lo()))
+    }
+
+    /// Add any trivia before the next token to the current node.
+    fn skip_trivia(&mut self) {
+        while self.pos < self.tokens.len() && self.is_trivia(self.pos) {
+            self.bump_raw();
+        }
+    }
+
+    /// Add the next token that isn't trivia (and any trivia before it) to
+    /// the current node.
+    fn bump(&mut self) {
+        self.skip_trivia();
+        self.bump_raw();
+    }
+
+    fn bump_raw(&mut self) {
+        let (ty, lexeme) = self.tokens[self.pos].value_owned();
+        self.builder.token(SyntaxKind::from(ty).into(), lexeme);
+        self.pos += 1;
+    }
+
+    fn current_span(&self) -> Span {
+        match self.tokens_ahead().next() {
+            Some(token) => token.span(),
+            None => self.tokens.last().map_or_else(Span::default, |token| {
+                Span::new(token.span().hi(), token.span().hi())
+            }),
+        }
+    }
+}
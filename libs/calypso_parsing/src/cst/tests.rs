@@ -0,0 +1,391 @@
+use std::sync::Arc;
+
+use calypso_ast::{pretty::PrettyPrinter, traverse::Visitor};
+use calypso_base::ui::{termcolor::ColorChoice, Emitters};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::{diagnostic::SourceMgr, report::GlobalReportingCtxt};
+
+use super::ast::{Expr, InterpPart, Pattern, Stmt, Ty};
+use super::{parse, Parse, SyntaxKind};
+use crate::lexer::Lexer;
+use crate::parser::{parse_expr, parse_program};
+
+fn new_gcx() -> Arc<GlobalCtxt> {
+    Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(ColorChoice::Never, ColorChoice::Never)),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    })
+}
+
+fn parse_cst(source: &str) -> Parse {
+    let tokens = Lexer::new(0, source, new_gcx())
+        .into_iter()
+        .collect::<Vec<_>>();
+    parse(&tokens)
+}
+
+/// Print the typed view of an expression in the same format as
+/// [`PrettyPrinter`].
+fn sexpr(expr: &Expr) -> String {
+    match expr {
+        Expr::BinExpr(expr) => format!(
+            "({} {} {})",
+            expr.op_kind().unwrap(),
+            sexpr(&expr.lhs().unwrap()),
+            sexpr(&expr.rhs().unwrap())
+        ),
+        Expr::UnaryExpr(expr) => format!(
+            "({} {})",
+            expr.op().unwrap().text(),
+            sexpr(&expr.operand().unwrap())
+        ),
+        Expr::ParenExpr(expr) => sexpr(&expr.expr().unwrap()),
+        Expr::Literal(expr) => expr.token().unwrap().text().to_string(),
+        Expr::InterpString(expr) => {
+            let parts = expr
+                .parts()
+                .map(|part| match part {
+                    InterpPart::Fragment(token) => token.text().to_string(),
+                    InterpPart::Interpolation(interp) => {
+                        format!("${{{}}}", sexpr(&interp.expr().unwrap()))
+                    }
+                })
+                .collect::<String>();
+            format!("\"{}\"", parts)
+        }
+        Expr::NameRef(expr) => expr.ident().unwrap().text().to_string(),
+        Expr::CallExpr(expr) => {
+            let mut call = format!("(call {}", sexpr(&expr.callee().unwrap()));
+            for arg in expr.arg_list().unwrap().args() {
+                call.push(' ');
+                call.push_str(&sexpr(&arg));
+            }
+            call.push(')');
+            call
+        }
+        Expr::FieldExpr(expr) => format!(
+            "(. {} {})",
+            sexpr(&expr.expr().unwrap()),
+            expr.field().unwrap().text()
+        ),
+        Expr::BlockExpr(expr) => list("do", expr.stmts().map(|stmt| stmt_sexpr(&stmt))),
+        Expr::IfExpr(expr) => list(
+            "if",
+            std::iter::once(sexpr(&expr.cond().unwrap()))
+                .chain(expr.then_branch().map(|branch| branch_sexpr(&branch)))
+                .chain(expr.else_branch().map(|branch| branch_sexpr(&branch))),
+        ),
+        Expr::WhileExpr(expr) => format!(
+            "(while {} {})",
+            sexpr(&expr.cond().unwrap()),
+            branch_sexpr(&expr.body().unwrap())
+        ),
+        Expr::ForExpr(expr) => format!(
+            "(for {} {} {})",
+            pattern_sexpr(&expr.pattern().unwrap()),
+            sexpr(&expr.iterable().unwrap()),
+            branch_sexpr(&expr.body().unwrap())
+        ),
+        Expr::LoopExpr(expr) => format!("(loop {})", branch_sexpr(&expr.body().unwrap())),
+        Expr::BreakExpr(_) => "(break)".to_string(),
+        Expr::ContinueExpr(_) => "(continue)".to_string(),
+        Expr::RetExpr(expr) => list("ret", expr.value().map(|value| sexpr(&value))),
+        Expr::CaseExpr(expr) => {
+            let scrutinees = expr.scrutinees().map(|scrutinee| sexpr(&scrutinee));
+            let arms = expr.arms().map(|arm| {
+                let patterns = list("", arm.patterns().map(|pattern| pattern_sexpr(&pattern)));
+                let guard = arm
+                    .guard()
+                    .map(|guard| format!("(where {})", sexpr(&guard.expr().unwrap())));
+                list(
+                    "arm",
+                    std::iter::once(patterns)
+                        .chain(guard)
+                        .chain(std::iter::once(sexpr(&arm.body().unwrap()))),
+                )
+            });
+            list("case", std::iter::once(list("", scrutinees)).chain(arms))
+        }
+    }
+}
+
+/// Print a list in parentheses, e.g. `(do a b)`. The `head` is left out if
+/// it's empty.
+fn list(head: &str, items: impl IntoIterator<Item = String>) -> String {
+    let items = std::iter::once(head.to_string())
+        .filter(|head| !head.is_empty())
+        .chain(items)
+        .collect::<Vec<_>>();
+    format!("({})", items.join(" "))
+}
+
+/// Print the typed view of a statement in the same format as
+/// [`PrettyPrinter`].
+fn stmt_sexpr(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::Let(stmt) => list(
+            "let",
+            std::iter::once(pattern_sexpr(&stmt.pattern().unwrap()))
+                .chain(stmt.value().map(|value| sexpr(&value))),
+        ),
+        Stmt::Assign(stmt) => format!(
+            "({} {} {})",
+            stmt.op().unwrap().text(),
+            stmt.target().unwrap().text(),
+            sexpr(&stmt.value().unwrap())
+        ),
+        Stmt::Fn(item) => {
+            let params = item.param_list().unwrap().params().map(|param| {
+                let name = param.name().unwrap().text().to_string();
+                let param_sexpr = match param.ty() {
+                    Some(ty) => format!("(: {} {})", name, ty_sexpr(&ty)),
+                    None => name,
+                };
+                match param.default() {
+                    Some(default) => format!("(\\\\ {} {})", param_sexpr, sexpr(&default)),
+                    None => param_sexpr,
+                }
+            });
+            let decl = format!(
+                "(fn {} {} {} {})",
+                item.name().unwrap().text(),
+                list("", params),
+                item.ret_ty()
+                    .map_or_else(|| "_".to_string(), |ty| ty_sexpr(&ty)),
+                sexpr(&item.body().unwrap())
+            );
+            if item.is_public() {
+                format!("(pub {})", decl)
+            } else {
+                decl
+            }
+        }
+        Stmt::Expr(expr) => sexpr(expr),
+    }
+}
+
+/// Print the body of a control flow expression, where a statement that
+/// isn't an expression becomes a block of its own.
+fn branch_sexpr(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::Expr(expr) => sexpr(expr),
+        stmt => format!("(do {})", stmt_sexpr(stmt)),
+    }
+}
+
+fn pattern_sexpr(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::BindingPattern(pattern) if pattern.is_mut() => {
+            format!("(mut {})", pattern.name().unwrap().text())
+        }
+        Pattern::BindingPattern(pattern) => pattern.name().unwrap().text().to_string(),
+        Pattern::WildcardPattern(_) => "_".to_string(),
+        Pattern::LiteralPattern(pattern) => sexpr(&Expr::Literal(pattern.literal().unwrap())),
+        Pattern::ConstructorPattern(pattern) => list(
+            pattern.name().unwrap().text(),
+            pattern.fields().map(|field| pattern_sexpr(&field)),
+        ),
+        Pattern::OrPattern(pattern) => list(
+            "|",
+            pattern
+                .alternatives()
+                .map(|alternative| pattern_sexpr(&alternative)),
+        ),
+    }
+}
+
+fn ty_sexpr(ty: &Ty) -> String {
+    match ty {
+        Ty::NamedTy(ty) => match ty.args() {
+            Some(args) => list(
+                ty.name().unwrap().text(),
+                args.tys().map(|arg| ty_sexpr(&arg)),
+            ),
+            None => ty.name().unwrap().text().to_string(),
+        },
+        Ty::FnTy(ty) => format!(
+            "(fn {} {})",
+            list("", ty.params().unwrap().tys().map(|param| ty_sexpr(&param))),
+            ty.ret()
+                .map_or_else(|| "_".to_string(), |ret| ty_sexpr(&ret))
+        ),
+    }
+}
+
+/// Get the expression that makes up a whole file.
+fn root_expr(parse: &Parse) -> Expr {
+    match parse.root().stmts().next() {
+        Some(Stmt::Expr(expr)) => expr,
+        stmt => panic!("expected an expression, found {:?}", stmt),
+    }
+}
+
+/// Parse an expression with LALRPOP and pretty-print it.
+fn parse_lalrpop(source: &str) -> String {
+    let expr = parse_expr(0, source, &new_gcx()).unwrap();
+    let mut printer = PrettyPrinter::default();
+    printer.visit_expr(&expr).unwrap();
+    printer.to_string()
+}
+
+/// Parse a whole file with LALRPOP and pretty-print it.
+fn parse_lalrpop_program(source: &str) -> String {
+    let block = parse_program(0, source, &new_gcx()).unwrap();
+    let mut printer = PrettyPrinter::default();
+    printer.visit_block(&block).unwrap();
+    printer.to_string()
+}
+
+#[test]
+fn lossless() {
+    for source in &[
+        "",
+        "  // just a comment\n",
+        "1 + /* two */ 2 // three\r\n",
+        "\n-(a /* /* nested */ */ ** 2)\n\n",
+        "\"a ${ 1 +\n 2 } b\"",
+        "r#\"a\"# == \"\"\"\n  b\n  \"\"\"",
+        // Errors are kept in the tree, too.
+        "1 + + 2 )",
+        "(1 + ",
+        "\"a ${1",
+        "a.(b, c",
+        "let x = 1 // one\n\n  /* two */ x += 2\r\n",
+        "fn f(a: int, b \\\\ 1) ->\n  // body\n  a + b\nend\n",
+        "if a do\n  b\nelse\n  c\nend",
+        "case x do\n  0 -> 1, _ -> 2 // default\nend",
+        "let = 1\nfn (\nwhile x y\nend )",
+    ] {
+        let parse = parse_cst(source);
+        assert_eq!(parse.syntax().to_string(), *source);
+    }
+}
+
+#[test]
+fn same_as_lalrpop() {
+    for source in &[
+        "1 + 2 * 3 ** 4",
+        "-(1 - 2) == !true",
+        "1 - 2 - 3",
+        "2 ** 3 ** 4",
+        "-2 ** -2",
+        "1 || 2 && 3 | 4 ^ 5 & 6 == 7 < 8 >> 9 + 10 * 11 ** 12",
+        "1 << 2 >= 3 != 4",
+        ":foo != 0xffu",
+        "\"a ${1 + 2} b ${\"c${true}\"}\"",
+        "1.5e3 /* comment */ % // comment\n 2",
+        "\"a\\tb\" == \"c\"",
+        "b\"\\x7F\" | b'a'",
+        "a.b(c, 1 + 2,).d",
+        "-f()(x) ** (a).b",
+        "\"${a.b}\".c",
+    ] {
+        let parse = parse_cst(source);
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        assert_eq!(
+            sexpr(&root_expr(&parse)),
+            parse_lalrpop(source),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn programs_same_as_lalrpop() {
+    for source in &[
+        "let x = 1\nlet mut y\ny = x\ny **= 2 + x",
+        "let Pair(a, _) | Pair(_, a) = p\nlet 0 | :zero = n",
+        "fn id(x) -> x\npub fn add(a: int, b: int \\\\ 1): int -> a + b",
+        "fn apply(f: fn(int, int): Result(int, str), x) -> do\n  f(x, x)\nend",
+        "fn pt1() ->\n  let x = 1\n  println(x)\nend",
+        "if n == 0 -> ret 1\nret",
+        "let x = if a -> 1 else if b -> 2 else -> 3",
+        "if a ->\n  b\nelse if c ->\n  d\nelse ->\n  e\nend",
+        "if c do\n  a\nelse\n  b\n  c\nend",
+        "if a do b else c end",
+        "while x > 0 -> x = x - 1\nfor Pair(k, v) in xs -> res *= v",
+        "loop ->\n  if foo == bar -> break end\n  continue\nend",
+        "loop do if a -> b else -> c end end",
+        "x = do -> 1\nend\ny = do ->\n  2\nend",
+        "case a, b do\n  0, _ -> 1\n  n, m where n > m ->\n    n\n  _, _ -> case b do 1 -> 2, _ -> 3 end\nend",
+        "f(if a -> b, while c -> d)\n\"${if a -> b}\"",
+        "x\n  .y\n  .z()",
+    ] {
+        let parse = parse_cst(source);
+        assert!(parse.errors().is_empty(), "{}: {:?}", source, parse.errors());
+        assert_eq!(
+            list("block", parse.root().stmts().map(|stmt| stmt_sexpr(&stmt))),
+            parse_lalrpop_program(source),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn trivia_placement() {
+    let parse = parse_cst("// a\n1 + 2 // b");
+    let root = parse.syntax();
+    let expr = root.first_child().unwrap();
+    assert_eq!(expr.kind(), SyntaxKind::BinExpr);
+    // Leading and trailing trivia belong to the root, not the expression.
+    assert_eq!(expr.text().to_string(), "1 + 2");
+    assert_eq!(
+        root.first_token().map(|token| token.kind()),
+        Some(SyntaxKind::LineComment)
+    );
+    assert_eq!(
+        root.last_token().map(|token| token.kind()),
+        Some(SyntaxKind::LineComment)
+    );
+}
+
+#[test]
+fn errors() {
+    let parse = parse_cst("1 + + 2 )");
+    assert_eq!(parse.errors().len(), 3);
+    assert!(parse
+        .syntax()
+        .descendants()
+        .any(|node| node.kind() == SyntaxKind::Error));
+
+    let parse = parse_cst("(1 + ");
+    assert_eq!(parse.errors().len(), 2);
+
+    let parse = parse_cst("a.(b, c");
+    assert_eq!(parse.errors().len(), 2);
+
+    // Statements that could not be parsed are skipped up to the next line.
+    let parse = parse_cst("while x y\nz");
+    assert_eq!(parse.errors().len(), 2);
+    assert_eq!(
+        parse.root().stmts().nth(1).map(|stmt| stmt_sexpr(&stmt)),
+        Some("z".to_string())
+    );
+
+    // Blocks are closed at their `end`, even after a syntax error.
+    let parse = parse_cst("do ->\n  1 + )\nend\nx");
+    assert_eq!(parse.errors().len(), 1);
+    assert_eq!(parse.root().stmts().count(), 2);
+}
+
+#[test]
+fn module_docs() {
+    let parse = parse_cst("#!/usr/bin/env calypso\n// not docs\n//! Line one.\n/*! Line two. */\n//! Line three.\n1 //! not docs either");
+    let root = parse.root();
+    assert!(parse.errors().is_empty());
+    assert_eq!(root.inner_docs().count(), 3);
+    assert_eq!(
+        root.docs().as_deref(),
+        Some("Line one.\nLine two.\nLine three.")
+    );
+    assert_eq!(
+        parse.syntax().first_token().unwrap().kind(),
+        SyntaxKind::Shebang
+    );
+
+    // Outer doc comments don't document the module.
+    assert_eq!(parse_cst("/// Not module docs.\n1").root().docs(), None);
+}
@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::sync::Arc;
@@ -5,21 +6,26 @@ use std::sync::Arc;
 use calypso_base::{
     span::{Span, Spanned},
     streams::{Stream, StringStream},
+    symbol::Symbol,
 };
 use calypso_common::gcx::GlobalCtxt;
-use calypso_diagnostic::prelude::*;
 
 pub use types::*;
 
 pub mod types;
 
-mod helpers;
+pub(crate) mod helpers;
 mod ident_kw;
+mod interp;
 mod lit;
 mod num;
+pub mod relex;
 mod scan;
 mod ws;
 
+#[cfg(test)]
+mod tests;
+
 pub type Token<'lex> = Spanned<(TokenType, Lexeme<'lex>)>;
 pub type Lexeme<'lex> = &'lex str;
 
@@ -27,9 +33,38 @@ pub struct Lexer<'lex> {
     stream: StringStream<'lex>,
     file_id: usize,
     start: Span,
+    modes: Vec<Mode>,
+    /// Identifiers (and atoms) that have already been checked for
+    /// confusables.
+    seen_idents: HashSet<Symbol>,
+    /// The first identifier (or atom) with each confusable skeleton, and its
+    /// span.
+    skeletons: HashMap<String, (Symbol, Span)>,
+    /// The spans of `!=`s directly after an identifier (e.g. `abc!=`), and
+    /// that identifier, by the identifier with a `!` suffix that they could
+    /// be mistaken for (e.g. `abc!`). They are only reported once that
+    /// identifier is seen.
+    ambiguous_not_eqs: HashMap<Symbol, Vec<(Span, &'lex str)>>,
+    /// The type of the last token, which decides whether a `:` begins an
+    /// atom.
+    prev: Option<TokenType>,
     gcx: Arc<GlobalCtxt>,
 }
 
+/// The lexer's state within interpolated strings.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    /// Within the text of an interpolated string.
+    String,
+    /// Within an interpolation (`${ ... }`).
+    Interp {
+        /// The span of the `${`
+        start: Span,
+        /// The number of currently unclosed `{`s within the interpolation
+        depth: usize,
+    },
+}
+
 impl<'lex> Deref for Lexer<'lex> {
     type Target = StringStream<'lex>;
 
@@ -51,6 +86,11 @@ impl<'lex> Lexer<'lex> {
             file_id,
             gcx,
             start: Span::default(),
+            modes: Vec::new(),
+            seen_idents: HashSet::new(),
+            skeletons: HashMap::new(),
+            ambiguous_not_eqs: HashMap::new(),
+            prev: None,
             stream: StringStream::new(source),
         }
     }
@@ -87,41 +127,34 @@ impl<'lex> Lexer<'lex> {
 
 impl<'lex> IntoIterator for Lexer<'lex> {
     type IntoIter = Iter<'lex>;
-    type Item = CalResult<Token<'lex>>;
+    type Item = Token<'lex>;
 
     fn into_iter(self) -> Self::IntoIter {
         Iter {
             lexer: self,
             encountered_eof: false,
-            encountered_error: false,
         }
     }
 }
 
 pub struct Iter<'lex> {
     lexer: Lexer<'lex>,
-    encountered_error: bool,
     encountered_eof: bool,
 }
 
 impl<'lex> Iterator for Iter<'lex> {
-    type Item = CalResult<Token<'lex>>;
+    type Item = Token<'lex>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.encountered_error || self.encountered_eof {
+        if self.encountered_eof {
+            return None;
+        }
+        let token = self.lexer.scan();
+        if token.value().0 == TokenType::Eof {
+            self.encountered_eof = true;
             None
         } else {
-            let res = self.lexer.scan();
-            if res.is_err() {
-                self.encountered_error = true;
-            }
-            if let Ok(res) = res {
-                if res.value().0 == TokenType::Eof {
-                    self.encountered_eof = true;
-                    return None;
-                }
-            }
-            Some(res)
+            Some(token)
         }
     }
 }
@@ -1,3 +1,5 @@
+use unicode_xid::UnicodeXID;
+
 use calypso_ast::expr::Radix;
 use calypso_base::span::Spanned;
 
@@ -27,18 +29,20 @@ pub(super) fn is_whitespace(elem: &Spanned<char>) -> bool {
 }
 
 #[inline]
-pub(super) fn is_whitespace_ch(ch: char) -> bool {
+pub(crate) fn is_whitespace_ch(ch: char) -> bool {
     WHITESPACE.contains(&ch)
 }
 
 #[inline]
 pub(super) fn is_ident_start(elem: &Spanned<char>) -> bool {
-    elem.value().is_ascii_alphabetic() || elem.value() == &'_'
+    let ch = elem.value_owned();
+    ch == '_' || ch.is_xid_start()
 }
 
 #[inline]
 pub(super) fn is_ident_continue(elem: &Spanned<char>) -> bool {
-    is_ident_start(elem) || elem.value().is_ascii_digit()
+    // XID_Continue includes `_` and the ASCII digits.
+    elem.value().is_xid_continue()
 }
 
 #[inline]
@@ -1,11 +1,17 @@
-use super::helpers::{is_ident_continue, is_ident_end};
-use super::{Lexer, Token, TokenType};
+use std::iter;
+
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+use unicode_security::{skeleton, GeneralSecurityProfile, MixedScript};
 
+use calypso_base::span::Span;
 use calypso_base::streams::Stream;
-use calypso_base::symbol::Symbol;
+use calypso_base::symbol::{kw::Keyword, Symbol};
 use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
 use calypso_diagnostic::prelude::*;
 
+use super::helpers::{is_ident_continue, is_ident_end, is_ident_start};
+use super::{Lexer, Token, TokenType};
+
 impl<'lex> Lexer<'lex> {
     pub(super) fn handle_identifier(&mut self) -> Token<'lex> {
         // `_` is not an ident on its own, but all other [A-Za-z]{1} idents are.
@@ -13,18 +19,40 @@ impl<'lex> Lexer<'lex> {
             return self.new_token(TokenType::Under);
         }
 
-        // Gorge while the character is a valid identifier character (and not an ident_end character).
-        self.gorge_while(|sp, _| is_ident_continue(sp) && !is_ident_end(sp));
-
-        // // Allow `abc!`, `abc?`, and `abc!?` but not `abc?!`
-        // if self.peek_eq(&'!') == Some(true) {
-        //     self.next();
-        // }
-        // if self.peek_eq(&'?') == Some(true) {
-        //     self.next();
-        // }
+        self.handle_ident_rest();
 
         let span = self.new_span();
+        let ident = self.intern_ident(span);
+        self.new_token(if ident.is_keyword() {
+            TokenType::Keyword(ident)
+        } else {
+            TokenType::Ident(ident)
+        })
+    }
+
+    /// Check if the `:` that was just consumed begins an atom. A `:` directly
+    /// after an operand (e.g. `a:b`) is never an atom, so that type
+    /// annotations and key-value pairs don't require whitespace, and neither
+    /// is one followed by whitespace (e.g. `n : uint`).
+    pub(super) fn is_atom_start(&self) -> bool {
+        !matches!(self.prev, Some(ty) if ends_operand(ty))
+            && (self.peek_cond(is_ident_start) == Some(true) || self.peek_eq(&'"') == Some(true))
+    }
+
+    pub(super) fn handle_atom(&mut self) -> Token<'lex> {
+        // Handle the first character of the atom's name
+        self.next();
+        self.handle_ident_rest();
+
+        // Don't include the `:` in the atom's name
+        let span = self.new_span().add_lo(1);
+        let atom = self.intern_ident(span);
+        self.new_token(TokenType::Atom(atom))
+    }
+
+    /// Check that the identifier (or atom) at the given span is not too long,
+    /// and return the sliced identifier.
+    pub(super) fn check_ident_len(&self, span: Span) -> &'lex str {
         let sliced = self.slice(span);
 
         if sliced.len() > 255 {
@@ -42,11 +70,219 @@ impl<'lex> Lexer<'lex> {
             );
         }
 
-        let ident = Symbol::intern(sliced);
-        self.new_token(if ident.is_keyword() {
-            TokenType::Keyword(ident)
+        sliced
+    }
+
+    /// Normalize the identifier (or atom) at the given span to NFC and
+    /// intern it. The first occurrence of each identifier is checked for
+    /// security issues.
+    fn intern_ident(&mut self, span: Span) -> Symbol {
+        let sliced = self.check_ident_len(span);
+        let ident = if is_nfc(sliced) {
+            Symbol::intern(sliced)
         } else {
-            TokenType::Ident(ident)
-        })
+            Symbol::intern(&sliced.nfc().collect::<String>())
+        };
+
+        if self.seen_idents.insert(ident) {
+            self.check_ident_security(ident, span);
+            for (span, ident) in self.ambiguous_not_eqs.remove(&ident).unwrap_or_default() {
+                self.report_ambiguous_not_eq(span, ident);
+            }
+        }
+        ident
+    }
+
+    /// Mark an identifier (or atom) as already checked for security issues,
+    /// as if it had been lexed at the given span.
+    pub(super) fn mark_ident_seen(&mut self, ident: Symbol, span: Span) {
+        if self.seen_idents.insert(ident) {
+            self.skeletons
+                .entry(skeleton(ident.as_str()).collect())
+                .or_insert((ident, span));
+        }
+    }
+
+    /// Warn about identifiers that contain codepoints which are uncommon or
+    /// restricted in identifiers, that mix scripts, or that are confusable
+    /// with another identifier in the same file, following [UTS #39].
+    ///
+    /// [UTS #39]: https://www.unicode.org/reports/tr39/
+    fn check_ident_security(&mut self, ident: Symbol, span: Span) {
+        let name = ident.as_str();
+
+        if !name.is_ascii() {
+            let uncommon = name
+                .chars()
+                .filter(|&ch| !ch.identifier_allowed())
+                .collect::<Vec<_>>();
+            if !uncommon.is_empty() {
+                self.gcx.grcx.write().report_non_fatal(
+                    EnsembleBuilder::new()
+                        .warning(|b| {
+                            uncommon.iter().fold(
+                                b.code("E0047").short(err!(E0047, ident = name)).label(
+                                    LabelStyle::Primary,
+                                    None,
+                                    self.file_id,
+                                    span,
+                                ),
+                                |b, ch| {
+                                    b.note(format!(
+                                        "note: `{}` (U+{:04X}) is not recommended for use in identifiers",
+                                        ch, *ch as u32
+                                    ))
+                                },
+                            )
+                        })
+                        .build(),
+                );
+            } else if !name.is_single_script() {
+                self.gcx.grcx.write().report_non_fatal(
+                    EnsembleBuilder::new()
+                        .warning(|b| {
+                            b.code("E0048")
+                                .short(err!(E0048, ident = name))
+                                .label(LabelStyle::Primary, None, self.file_id, span)
+                                .note("note: identifiers that mix scripts can look like other identifiers")
+                        })
+                        .build(),
+                );
+            }
+        }
+
+        let skeleton = skeleton(name).collect::<String>();
+        if let Some(&(other, other_span)) = self.skeletons.get(&skeleton) {
+            // Confusables within ASCII (e.g. `l` and `I`) are common enough
+            // that they aren't worth warning about.
+            if !name.is_ascii() || !other.as_str().is_ascii() {
+                self.gcx.grcx.write().report_non_fatal(
+                    EnsembleBuilder::new()
+                        .warning(|b| {
+                            b.code("E0049")
+                                .short(err!(E0049, ident = name, other = other))
+                                .label(LabelStyle::Primary, None, self.file_id, span)
+                                .label(
+                                    LabelStyle::Secondary,
+                                    Some(&format!("`{}` is used here", other)),
+                                    self.file_id,
+                                    other_span,
+                                )
+                        })
+                        .build(),
+                );
+            }
+        } else {
+            self.skeletons.insert(skeleton, (ident, span));
+        }
+    }
+
+    fn report_ambiguous_not_eq(&self, span: Span, ident: &str) {
+        self.gcx.grcx.write().report_non_fatal(
+            EnsembleBuilder::new()
+                .warning(|b| {
+                    b.code("E0037")
+                        .short(err!(E0037))
+                        .label(
+                            LabelStyle::Primary,
+                            Some("this is treated as the not-equals operator"),
+                            self.file_id,
+                            span,
+                        )
+                        .note(format!(
+                            "help: if you meant to compare `{0}`, add a space: `{0} !=`",
+                            ident
+                        ))
+                        .note(format!(
+                            "help: if you meant to use `{0}!`, add a space: `{0}! =`",
+                            ident
+                        ))
+                })
+                .build(),
+        );
+    }
+
+    fn handle_ident_rest(&mut self) {
+        // Gorge while the character is a valid identifier character (and not an ident_end character).
+        self.gorge_while(|sp, _| is_ident_continue(sp) && !is_ident_end(sp));
+
+        self.handle_ident_end();
+    }
+
+    fn handle_ident_end(&mut self) {
+        // Allow `abc!`, `abc?`, and `abc!?` but not `abc?!`
+        if self.peek_eq(&'!') == Some(true) {
+            if self.peek2_eq(&'=') == Some(true) {
+                // `abc!=` is lexed as `abc !=`, but warn about it if there is
+                // also an identifier `abc!` in this file.
+                let span = self.peek().unwrap().span().to(self.peek2().unwrap().span());
+                let ident = self.slice(self.new_span());
+                let suffixed = Symbol::intern(
+                    &ident
+                        .trim_start_matches(':')
+                        .nfc()
+                        .chain(iter::once('!'))
+                        .collect::<String>(),
+                );
+                if self.seen_idents.contains(&suffixed) {
+                    self.report_ambiguous_not_eq(span, ident);
+                } else {
+                    self.ambiguous_not_eqs
+                        .entry(suffixed)
+                        .or_default()
+                        .push((span, ident));
+                }
+                return;
+            }
+            self.next();
+        }
+        if self.next_if_eq(&'?').is_some()
+            && self.peek_eq(&'!') == Some(true)
+            && self.peek2_eq(&'=') != Some(true)
+        {
+            let span = self.prev().unwrap().span().to(self.peek().unwrap().span());
+            self.next();
+            let sliced = self.slice(self.new_span());
+            self.gcx.grcx.write().report_syncd(
+                EnsembleBuilder::new()
+                    .error(|b| {
+                        b.code("E0036")
+                            .short(err!(E0036))
+                            .label(LabelStyle::Primary, None, self.file_id, span)
+                            .note(format!(
+                                "help: perhaps you meant `{}!?`?",
+                                &sliced[..sliced.len() - 2]
+                            ))
+                    })
+                    .build(),
+            );
+        }
+    }
+}
+
+/// Can this token end an operand (e.g. an identifier, literal or closing
+/// bracket)?
+pub(super) fn ends_operand(ty: TokenType) -> bool {
+    match ty {
+        TokenType::Ident(_)
+        | TokenType::Atom(_)
+        | TokenType::Under
+        | TokenType::RParen
+        | TokenType::RBracket
+        | TokenType::RBrace
+        | TokenType::String(_)
+        | TokenType::RawString { .. }
+        | TokenType::MultilineString(_)
+        | TokenType::StringEnd
+        | TokenType::Char(_)
+        | TokenType::ByteString
+        | TokenType::Byte(_)
+        | TokenType::Int { .. }
+        | TokenType::Float => true,
+        TokenType::Keyword(kw) => matches!(
+            Keyword::from(kw),
+            Keyword::True | Keyword::False | Keyword::End | Keyword::Break | Keyword::Continue
+        ),
+        _ => false,
     }
 }
@@ -0,0 +1,121 @@
+use super::{Lexer, Mode, Token, TokenType};
+
+use calypso_base::streams::Stream;
+use calypso_base::symbol::Symbol;
+use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
+use calypso_diagnostic::prelude::*;
+
+use crate::literal;
+
+impl<'lex> Lexer<'lex> {
+    /// Check if the string literal whose opening `"` was just consumed
+    /// contains an interpolation (`${`) before its closing `"`.
+    pub(super) fn is_interpolated_string(&self) -> bool {
+        let mut chars = self[self.current().lo()..].chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => return false,
+                '$' if chars.clone().next() == Some('{') => return true,
+                _ => (),
+            }
+        }
+        false
+    }
+
+    pub(super) fn handle_interpolated_string(&mut self) -> Token<'lex> {
+        self.modes.push(Mode::String);
+        self.new_token(TokenType::StringStart)
+    }
+
+    /// Scan the next token within the text of an interpolated string.
+    pub(super) fn handle_string_text(&mut self) -> Token<'lex> {
+        self.current_to_start();
+        if self.next_if_eq(&'"').is_some() {
+            self.modes.pop();
+            return self.new_token(TokenType::StringEnd);
+        }
+        if self.is_at_interp_start() {
+            self.next();
+            self.next();
+            self.modes.push(Mode::Interp {
+                start: self.new_span(),
+                depth: 0,
+            });
+            return self.new_token(TokenType::InterpStart);
+        }
+
+        while !self.is_at_end() && self.peek_eq(&'"') != Some(true) && !self.is_at_interp_start() {
+            if !self.handle_escape_character() {
+                self.next();
+            }
+        }
+        let span = self.new_span();
+        let value = self.literal_value(literal::decode(self.slice(span), span.lo()));
+        self.new_token(TokenType::StringFragment(Symbol::intern(&value)))
+    }
+
+    fn is_at_interp_start(&self) -> bool {
+        self.peek_eq(&'$') == Some(true) && self.peek2_eq(&'{') == Some(true)
+    }
+
+    pub(super) fn handle_lbrace(&mut self) -> TokenType {
+        if let Some(Mode::Interp { depth, .. }) = self.modes.last_mut() {
+            *depth += 1;
+        }
+        TokenType::LBrace
+    }
+
+    pub(super) fn handle_rbrace(&mut self) -> TokenType {
+        match self.modes.last_mut() {
+            Some(Mode::Interp { depth: 0, .. }) => {
+                self.modes.pop();
+                TokenType::InterpEnd
+            }
+            Some(Mode::Interp { depth, .. }) => {
+                *depth -= 1;
+                TokenType::RBrace
+            }
+            _ => TokenType::RBrace,
+        }
+    }
+
+    /// Report the innermost unterminated interpolated string or interpolation,
+    /// if any, once the end of the input has been reached.
+    pub(super) fn handle_unterminated_modes(&mut self) {
+        match self.modes.last().copied() {
+            Some(Mode::String) => {
+                self.gcx.grcx.write().report_syncd(
+                    EnsembleBuilder::new()
+                        .error(|b| {
+                            b.code("E0024").short(err!(E0024)).label(
+                                LabelStyle::Primary,
+                                None,
+                                self.file_id,
+                                self.new_span(),
+                            )
+                        })
+                        .build(),
+                );
+            }
+            Some(Mode::Interp { start, .. }) => {
+                self.gcx.grcx.write().report_syncd(
+                    EnsembleBuilder::new()
+                        .error(|b| {
+                            b.code("E0038").short(err!(E0038)).label(
+                                LabelStyle::Primary,
+                                Some("this interpolation needs to be terminated"),
+                                self.file_id,
+                                start,
+                            )
+                        })
+                        .build(),
+                );
+            }
+            None => (),
+        }
+        self.modes.clear();
+    }
+}
@@ -1,25 +1,37 @@
+use std::sync::Arc;
+
 use super::helpers::{is_valid_for_char_literal, is_whitespace, is_whitespace_ch};
 use super::{Lexer, Token, TokenType};
 
 use calypso_base::span::Span;
 use calypso_base::streams::Stream;
+use calypso_base::symbol::Symbol;
 use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
 use calypso_diagnostic::prelude::*;
 
-// todo(@ThePuzzlemaker: parse):
-//   looks like a lot of these could be converted to sync rather than panic
+use crate::literal::{self, Decoded, LiteralDecoder};
+
 impl<'lex> Lexer<'lex> {
-    pub(super) fn handle_escape_character(&mut self) -> CalResult<bool> {
+    /// Report any errors in the values of the escape sequences of the
+    /// literal that was just scanned (e.g. surrogates in Unicode escapes),
+    /// and return its value. Malformed escape sequences are reported while
+    /// scanning.
+    pub(super) fn literal_value<T>(&self, decoded: Decoded<T>) -> T {
+        LiteralDecoder::new(self.file_id, Arc::clone(&self.gcx)).report(&decoded);
+        decoded.value
+    }
+
+    pub(super) fn handle_escape_character(&mut self) -> bool {
         let saved_start = self.start;
         self.current_to_start();
         if self.next_if_eq(&'\\').is_some() {
             match self.peek().map(|v| v.value_owned()) {
                 Some('n') | Some('r') | Some('t') | Some('\\') | Some('0') | Some('\'')
-                | Some('"') | Some('\r') | Some('\n') => {
+                | Some('"') | Some('$') | Some('\r') | Some('\n') => {
                     self.next();
                 }
-                Some('x') => self.handle_hex_escape()?,
-                Some('u') => self.handle_unicode_escape()?,
+                Some('x') => self.handle_hex_escape(),
+                Some('u') => self.handle_unicode_escape(),
                 Some(ch) => {
                     if is_whitespace_ch(ch) {
                         self.gcx.grcx.write().report_syncd(
@@ -52,7 +64,7 @@ impl<'lex> Lexer<'lex> {
                     }
                 }
                 None => {
-                    self.gcx.grcx.write().report_fatal(
+                    self.gcx.grcx.write().report_syncd(
                         EnsembleBuilder::new()
                             .error(|b| {
                                 b.code("E0007").short(err!(E0007)).label(
@@ -64,19 +76,18 @@ impl<'lex> Lexer<'lex> {
                             })
                             .build(),
                     );
-                    return Err(DiagnosticError::Diagnostic.into());
                 }
             }
             self.set_start(saved_start);
-            return Ok(true);
+            return true;
         }
 
         self.set_start(saved_start);
         // We don't care *what* sequence was found, just if there was one.
-        Ok(false)
+        false
     }
 
-    pub(super) fn handle_hex_escape(&mut self) -> CalResult<()> {
+    pub(super) fn handle_hex_escape(&mut self) {
         // Handle the `x` in `\x41`
         self.next();
         self.current_to_start();
@@ -88,7 +99,7 @@ impl<'lex> Lexer<'lex> {
                 || sp.unwrap().value_owned() == '\"'
             {
                 if i == 1 {
-                    self.gcx.grcx.write().report_fatal(
+                    self.gcx.grcx.write().report_syncd(
                         EnsembleBuilder::new()
                             .error(|b| {
                                 b.code("E0004").short(err!(E0004)).label(
@@ -100,9 +111,8 @@ impl<'lex> Lexer<'lex> {
                             })
                             .build(),
                     );
-                    return Err(DiagnosticError::Diagnostic.into());
-                } else if i == 2 {
-                    self.gcx.grcx.write().report_fatal(
+                } else {
+                    self.gcx.grcx.write().report_syncd(
                         EnsembleBuilder::new()
                             .error(|b| {
                                 b.code("E0009")
@@ -115,8 +125,10 @@ impl<'lex> Lexer<'lex> {
                             })
                             .build(),
                     );
-                    return Err(DiagnosticError::Diagnostic.into());
                 }
+                // Leave the terminating character alone, so the literal can
+                // still be closed properly.
+                return;
             }
             let sp = *sp.unwrap();
             let ch = sp.value_owned();
@@ -140,17 +152,15 @@ impl<'lex> Lexer<'lex> {
                 self.next();
             }
         }
-        Ok(())
     }
 
-    #[allow(clippy::too_many_lines)]
-    pub(super) fn handle_unicode_escape(&mut self) -> CalResult<()> {
+    pub(super) fn handle_unicode_escape(&mut self) {
         // Handle the `u` in `\u{1234}`
         self.next();
         self.current_to_start();
         match self.peek().copied() {
             Some(sp) if is_whitespace(&sp) => {
-                self.gcx.grcx.write().report_fatal(
+                self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
                         .error(|b| {
                             b.code("E0012").short(err!(E0012)).label(
@@ -162,10 +172,10 @@ impl<'lex> Lexer<'lex> {
                         })
                         .build(),
                 );
-                return Err(DiagnosticError::Diagnostic.into());
+                return;
             }
             None => {
-                self.gcx.grcx.write().report_fatal(
+                self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
                         .error(|b| {
                             b.code("E0011").short(err!(E0011)).label(
@@ -177,30 +187,35 @@ impl<'lex> Lexer<'lex> {
                         })
                         .build(),
                 );
-                return Err(DiagnosticError::Diagnostic.into());
+                return;
             }
             Some(sp) if sp != '{' => {
-                self.next();
-                self.gcx.grcx.write().report_fatal(
+                // Don't consume this character, as it may be the end of the
+                // literal.
+                self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
                         .error(|b| {
                             b.code("E0010")
                                 .short(err!(E0010, ch = sp.value_owned()))
-                                .label(LabelStyle::Primary, None, self.file_id, self.new_span())
+                                .label(LabelStyle::Primary, None, self.file_id, sp.span())
                         })
                         .build(),
                 );
-                return Err(DiagnosticError::Diagnostic.into());
+                return;
             }
             Some(..) => (),
         }
         self.next();
 
-        let count = self.handle_unicode_escape_internal()?;
+        if let Some(count) = self.handle_unicode_escape_internal() {
+            self.handle_unicode_escape_end(count);
+        }
+    }
 
+    fn handle_unicode_escape_end(&mut self, count: usize) {
         if self.is_at_end() {
             self.current_to_start();
-            self.gcx.grcx.write().report_fatal(
+            self.gcx.grcx.write().report_syncd(
                 EnsembleBuilder::new()
                     .error(|b| {
                         b.code("E0015").short(err!(E0015)).label(
@@ -212,7 +227,7 @@ impl<'lex> Lexer<'lex> {
                     })
                     .build(),
             );
-            return Err(DiagnosticError::Diagnostic.into());
+            return;
         }
 
         let sp = *self.peek().unwrap();
@@ -255,12 +270,14 @@ impl<'lex> Lexer<'lex> {
                     .build(),
             );
         }
-        // Handle closing `}`
-        self.next();
-        Ok(())
+        // Handle closing `}`, if present. Anything else is left as-is, as it
+        // may be the end of the literal.
+        self.next_if_eq(&'}');
     }
 
-    fn handle_unicode_escape_internal(&mut self) -> CalResult<usize> {
+    /// Returns the amount of hex digits in the codepoint, or `None` if the
+    /// codepoint was malformed (in which case an error has been reported).
+    fn handle_unicode_escape_internal(&mut self) -> Option<usize> {
         let mut count = 0;
         while self.peek_eq(&'}') != Some(true) && !self.is_at_end() {
             self.current_to_start();
@@ -269,7 +286,7 @@ impl<'lex> Lexer<'lex> {
             if count == 6 {
                 break;
             } else if is_whitespace(&sp) {
-                self.gcx.grcx.write().report_fatal(
+                self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
                         .error(|b| {
                             b.code("E0018").short(err!(E0018)).label(
@@ -281,9 +298,9 @@ impl<'lex> Lexer<'lex> {
                         })
                         .build(),
                 );
-                return Err(DiagnosticError::Diagnostic.into());
+                return None;
             } else if !ch.is_ascii_hexdigit() {
-                self.gcx.grcx.write().report_fatal(
+                self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
                         .error(|b| {
                             b.code("E0014").short(err!(E0014, ch = ch)).label(
@@ -295,15 +312,28 @@ impl<'lex> Lexer<'lex> {
                         })
                         .build(),
                 );
-                return Err(DiagnosticError::Diagnostic.into());
+                return None;
             }
             self.next();
             count += 1;
         }
-        Ok(count)
+        Some(count)
     }
 
-    pub(super) fn handle_char_literal(&mut self) -> CalResult<Token<'lex>> {
+    pub(super) fn handle_char_literal(&mut self) -> Token<'lex> {
+        if !self.scan_char_literal() {
+            return self.new_token(TokenType::Unexpected);
+        }
+        let contents = self.new_span().add_lo(1).sub_hi(1);
+        let value = self.literal_value(literal::decode_char(self.slice(contents), contents.lo()));
+        self.new_token(TokenType::Char(value))
+    }
+
+    /// Scan the rest of a character literal whose opening `'` was just
+    /// consumed. If it's invalid (after reporting an error), `false` is
+    /// returned, and everything that was scanned should become a single
+    /// erroneous token.
+    fn scan_char_literal(&mut self) -> bool {
         let saved_start = self.start;
         let mut chs_found = 0;
         let mut expected_quote_here = Span::new_dummy();
@@ -311,7 +341,7 @@ impl<'lex> Lexer<'lex> {
             if chs_found == 1 {
                 expected_quote_here = self.current();
             }
-            if self.handle_escape_character()? {
+            if self.handle_escape_character() {
                 chs_found += 1;
             } else if is_valid_for_char_literal(self.peek().unwrap()) {
                 self.next();
@@ -341,7 +371,7 @@ impl<'lex> Lexer<'lex> {
 
         if chs_found > 1 {
             self.set_start(expected_quote_here);
-            self.gcx.grcx.write().report_fatal(
+            self.gcx.grcx.write().report_syncd(
                 EnsembleBuilder::new()
                     .error(|b| {
                         b.code("E0021").short(err!(E0021)).label(
@@ -353,7 +383,11 @@ impl<'lex> Lexer<'lex> {
                     })
                     .build(),
             );
-            return Err(DiagnosticError::Diagnostic.into());
+            // Treat everything up to (and including) the closing quote, if
+            // there is one, as a single erroneous token.
+            self.next_if_eq(&'\'');
+            self.set_start(saved_start);
+            return false;
         } else if chs_found == 0 && !self.is_at_end() {
             self.gcx.grcx.write().report_syncd(
                 EnsembleBuilder::new()
@@ -369,7 +403,7 @@ impl<'lex> Lexer<'lex> {
 
         if self.is_at_end() {
             self.current_to_start();
-            self.gcx.grcx.write().report_fatal(
+            self.gcx.grcx.write().report_syncd(
                 EnsembleBuilder::new()
                     .error(|b| {
                         b.code("E0023").short(err!(E0023)).label(
@@ -381,24 +415,53 @@ impl<'lex> Lexer<'lex> {
                     })
                     .build(),
             );
-            return Err(DiagnosticError::Diagnostic.into());
+            self.set_start(saved_start);
+            return false;
         }
         self.next();
 
         self.set_start(saved_start);
-        Ok(self.new_token(TokenType::Char))
+        true
+    }
+
+    pub(super) fn handle_atom_string(&mut self) -> Token<'lex> {
+        // Handle the opening `"`
+        self.next();
+        if !self.scan_string_literal() {
+            return self.new_token(TokenType::Unexpected);
+        }
+
+        // Don't include the `:` or the quotes in the atom's name
+        let contents = self.new_span().add_lo(2).sub_hi(1);
+        self.check_ident_len(contents);
+        let name = self.literal_value(literal::decode(self.slice(contents), contents.lo()));
+        self.new_token(TokenType::Atom(Symbol::intern(&name)))
+    }
+
+    pub(super) fn handle_string_literal(&mut self) -> Token<'lex> {
+        if !self.scan_string_literal() {
+            return self.new_token(TokenType::Unexpected);
+        }
+        let contents = self.new_span().add_lo(1).sub_hi(1);
+        let value = self.literal_value(literal::decode(self.slice(contents), contents.lo()));
+        self.new_token(TokenType::String(Symbol::intern(&value)))
     }
 
-    pub(super) fn handle_string_literal(&mut self) -> CalResult<Token<'lex>> {
+    /// Scan the rest of a string literal whose opening `"` was just
+    /// consumed. If it's unterminated (after reporting an error), `false` is
+    /// returned, and everything that was scanned should become a single
+    /// erroneous token.
+    fn scan_string_literal(&mut self) -> bool {
+        let saved_start = self.start;
         while self.peek_eq(&'"') != Some(true) && !self.is_at_end() {
-            if !self.handle_escape_character()? {
+            if !self.handle_escape_character() {
                 self.next();
             }
         }
 
         if self.peek_eq(&'"') != Some(true) {
             self.current_to_start();
-            self.gcx.grcx.write().report_fatal(
+            self.gcx.grcx.write().report_syncd(
                 EnsembleBuilder::new()
                     .error(|b| {
                         b.code("E0024").short(err!(E0024)).label(
@@ -410,11 +473,272 @@ impl<'lex> Lexer<'lex> {
                     })
                     .build(),
             );
-            return Err(DiagnosticError::Diagnostic.into());
+            self.set_start(saved_start);
+            return false;
         }
 
         self.next();
+        true
+    }
+
+    pub(super) fn handle_byte_literal(&mut self) -> Token<'lex> {
+        // Handle the opening quote
+        let is_string = self.next().unwrap() == '"';
+        let valid = if is_string {
+            self.scan_string_literal()
+        } else {
+            self.scan_char_literal()
+        };
+        if !valid {
+            return self.new_token(TokenType::Unexpected);
+        }
+
+        // Don't include the `b` or the quotes
+        let span = self.new_span();
+        let lo = span.lo() + 2;
+        let contents = &self[lo..span.hi() - 1];
+        let mut chars = contents.char_indices();
+        while let Some((idx, ch)) = chars.next() {
+            let span = Span::new(lo + idx, lo + idx + ch.len_utf8());
+            if ch == '\\' {
+                // Any other invalid escapes have already been reported.
+                if let Some((_, 'u')) = chars.next() {
+                    self.gcx.grcx.write().report_syncd(
+                        EnsembleBuilder::new()
+                            .error(|b| {
+                                b.code("E0056")
+                                    .short(err!(E0056))
+                                    .label(LabelStyle::Primary, None, self.file_id, span.add_hi(1))
+                                    .note("help: use a hex escape (e.g. `\\xFF`) for each byte instead")
+                            })
+                            .build(),
+                    );
+                }
+            } else if !ch.is_ascii() {
+                let escaped = ch
+                    .encode_utf8(&mut [0; 4])
+                    .bytes()
+                    .map(|byte| format!("\\x{:02X}", byte))
+                    .collect::<Vec<_>>()
+                    .concat();
+                self.gcx.grcx.write().report_syncd(
+                    EnsembleBuilder::new()
+                        .error(|b| {
+                            b.code("E0055")
+                                .short(err!(E0055, ch = ch))
+                                .label(LabelStyle::Primary, None, self.file_id, span)
+                                .note(format!(
+                                    "help: if you meant its UTF-8 encoding, use hex escapes: `{}`",
+                                    escaped
+                                ))
+                        })
+                        .build(),
+                );
+            }
+        }
+
+        if is_string {
+            return self.new_token(TokenType::ByteString);
+        }
+        // Any errors in the value (e.g. if there's no character at all) have
+        // already been reported.
+        let value = literal::decode_bytes(contents, lo).value;
+        self.new_token(TokenType::Byte(value.first().copied().unwrap_or(b'?')))
+    }
+
+    /// Check if the `r` that was just consumed begins a raw string literal,
+    /// i.e. if it's followed by any number of `#`s and a `"`.
+    pub(super) fn is_raw_string_start(&self) -> bool {
+        self[self.current().lo()..]
+            .trim_start_matches('#')
+            .starts_with('"')
+    }
+
+    pub(super) fn handle_raw_string(&mut self) -> Token<'lex> {
+        let saved_start = self.start;
+        let hashes = self.gorge_while_eq(&'#');
+        // Handle the opening `"`
+        self.next();
+        let opening = self.new_span();
+
+        // The most `#`s found after a `"` that didn't close the literal.
+        let mut closest = None;
+        while !self.is_at_end() {
+            if self.next().unwrap() != '"' {
+                continue;
+            }
+            let mut found = 0;
+            while found < hashes && self.next_if_eq(&'#').is_some() {
+                found += 1;
+            }
+            if found == hashes {
+                return self.new_token(TokenType::RawString { hashes });
+            }
+            // `None` is less than any `Some`.
+            if closest < Some(found) {
+                closest = Some(found);
+            }
+        }
+
+        self.current_to_start();
+        let fence = "#".repeat(hashes);
+        self.gcx.grcx.write().report_syncd(
+            EnsembleBuilder::new()
+                .error(|b| {
+                    let b = b
+                        .code("E0050")
+                        .short(err!(E0050, fence = fence))
+                        .label(LabelStyle::Primary, None, self.file_id, self.new_span())
+                        .label(
+                            LabelStyle::Secondary,
+                            Some("the raw string literal starts here"),
+                            self.file_id,
+                            opening,
+                        );
+                    match closest {
+                        Some(found) => b.note(format!(
+                            "note: found a `\"` followed by {} `#`, but this raw string literal must end with `\"{}`",
+                            found, fence
+                        )),
+                        None => b,
+                    }
+                })
+                .build(),
+        );
+        self.set_start(saved_start);
+        self.new_token(TokenType::Unexpected)
+    }
 
-        Ok(self.new_token(TokenType::String))
+    pub(super) fn handle_multiline_string(&mut self) -> Token<'lex> {
+        let saved_start = self.start;
+        // Handle the rest of the opening `"""`
+        self.next();
+        self.next();
+        let opening = self.new_span();
+
+        // The opening `"""` must be on a line of its own.
+        self.current_to_start();
+        while !self.is_at_end() && !self.is_at_newline() && !self.is_at_multiline_string_end() {
+            if !self.handle_escape_character() {
+                self.next();
+            }
+        }
+        let rest = self.new_span();
+        if !self.slice(rest).chars().all(is_whitespace_ch) {
+            self.gcx.grcx.write().report_syncd(
+                EnsembleBuilder::new()
+                    .error(|b| {
+                        b.code("E0052").short(err!(E0052)).label(
+                            LabelStyle::Primary,
+                            Some("expected a line break before this"),
+                            self.file_id,
+                            rest,
+                        )
+                    })
+                    .build(),
+            );
+        }
+
+        while !self.is_at_end() && !self.is_at_multiline_string_end() {
+            if !self.handle_escape_character() {
+                self.next();
+            }
+        }
+
+        if self.is_at_end() {
+            self.current_to_start();
+            self.gcx.grcx.write().report_syncd(
+                EnsembleBuilder::new()
+                    .error(|b| {
+                        b.code("E0051")
+                            .short(err!(E0051))
+                            .label(LabelStyle::Primary, None, self.file_id, self.new_span())
+                            .label(
+                                LabelStyle::Secondary,
+                                Some("the multi-line string literal starts here"),
+                                self.file_id,
+                                opening,
+                            )
+                    })
+                    .build(),
+            );
+            self.set_start(saved_start);
+            return self.new_token(TokenType::Unexpected);
+        }
+
+        self.current_to_start();
+        self.next();
+        self.next();
+        self.next();
+        let closing = self.new_span();
+        self.set_start(saved_start);
+        self.check_multiline_string_indent(rest.hi(), closing);
+
+        let span = self.new_span();
+        let value = self.literal_value(literal::decode_multiline(self.slice(span), span.lo()));
+        self.new_token(TokenType::MultilineString(Symbol::intern(&value)))
+    }
+
+    fn is_at_multiline_string_end(&self) -> bool {
+        self.peek_eq(&'"') == Some(true)
+            && self.peek2_eq(&'"') == Some(true)
+            && self.peek3_eq(&'"') == Some(true)
+    }
+
+    /// Check that the closing `"""` of a multi-line string literal is on a
+    /// line of its own, and that every line of the literal (between `lo` and
+    /// the closing `"""`) starts with the closing `"""`'s indentation.
+    fn check_multiline_string_indent(&self, lo: usize, closing: Span) {
+        let contents = &self[lo..closing.lo()];
+        let line_start = contents.rfind('\n').map_or(0, |idx| idx + 1);
+        let indent = &contents[line_start..];
+        if !indent.chars().all(is_whitespace_ch) {
+            self.gcx.grcx.write().report_syncd(
+                EnsembleBuilder::new()
+                    .error(|b| {
+                        b.code("E0053").short(err!(E0053)).label(
+                            LabelStyle::Primary,
+                            Some("expected a line break before this"),
+                            self.file_id,
+                            closing,
+                        )
+                    })
+                    .build(),
+            );
+            return;
+        }
+
+        let mut lines = contents[..line_start].split_inclusive('\n');
+        // Skip the line break after the opening `"""`.
+        let mut line_lo = lo + lines.next().map_or(0, str::len);
+        for line in lines {
+            let body = line.trim_end_matches(&['\n', '\r'][..]);
+            let trimmed = body.trim_start_matches(is_whitespace_ch);
+            // Blank lines don't need to be indented.
+            if let Some(first) = trimmed.chars().next() {
+                if !body.starts_with(indent) {
+                    let span = Span::new(
+                        line_lo,
+                        line_lo + body.len() - trimmed.len() + first.len_utf8(),
+                    );
+                    self.gcx.grcx.write().report_syncd(
+                        EnsembleBuilder::new()
+                            .error(|b| {
+                                b.code("E0054")
+                                    .short(err!(E0054))
+                                    .label(LabelStyle::Primary, None, self.file_id, span)
+                                    .label(
+                                        LabelStyle::Secondary,
+                                        Some("the indentation of this line must be at least this"),
+                                        self.file_id,
+                                        Span::new(closing.lo() - indent.len(), closing.lo()),
+                                    )
+                            })
+                            .build(),
+                    );
+                }
+            }
+            line_lo += line.len();
+        }
     }
 }
@@ -1,4 +1,6 @@
-use super::helpers::{is_valid_for, is_whitespace};
+use std::sync::Arc;
+
+use super::helpers::{is_ident_continue, is_ident_start, is_valid_for, is_whitespace};
 use super::{Lexer, Token, TokenType};
 
 use calypso_ast::expr::{Radix, Suffix};
@@ -7,18 +9,56 @@ use calypso_base::streams::Stream;
 use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
 use calypso_diagnostic::prelude::*;
 
+use crate::constant::ConstEvaluator;
+
 impl<'lex> Lexer<'lex> {
+    /// Report any errors in the digits or suffix of a number literal token.
+    /// Errors in its value (e.g. overflow) depend on whether it's negated,
+    /// so they're reported by the parser.
+    pub(super) fn check_number(&self, token: Token<'lex>) -> Token<'lex> {
+        ConstEvaluator::new(self.file_id, Arc::clone(&self.gcx)).check_literal(&token);
+        token
+    }
+
     pub(super) fn handle_number(&mut self) -> Token<'lex> {
         if self.handle_float_part() {
-            return self.new_token(TokenType::Float);
+            return self.handle_float_end();
         }
         let possibly_incomplete_tok = self.handle_int(Radix::None);
         if self.handle_float_part() {
-            return self.new_token(TokenType::Float);
+            return self.handle_float_end();
         }
         possibly_incomplete_tok
     }
 
+    /// Handle the end of a float literal. Any suffix is kept in the lexeme,
+    /// as floats do not have a suffix in their token type. Suffixes are
+    /// checked by [`Lexer::check_number`].
+    fn handle_float_end(&mut self) -> Token<'lex> {
+        // If the float ended early (e.g. `1.foo`), an error has already been
+        // reported and what follows is not a suffix.
+        if matches!(self.prev(), Some(ch) if ch.value().is_ascii_digit()) {
+            self.gorge_while(|c, _| is_ident_continue(c));
+        }
+        self.new_token(TokenType::Float)
+    }
+
+    /// Handle an invalid suffix (or invalid digits) directly after an
+    /// integer literal, returning `true` if one was found. Unlike valid
+    /// suffixes, this is kept in the lexeme so that it can be reported by
+    /// [`Lexer::check_number`].
+    fn handle_invalid_suffix(&mut self, can_be_float: bool) -> bool {
+        let is_invalid = |c: &Spanned<char>| {
+            is_ident_continue(c)
+                && !(can_be_float && (c.value_owned() == 'e' || c.value_owned() == 'E'))
+        };
+        if self.peek_cond(is_invalid) != Some(true) {
+            return false;
+        }
+        self.gorge_while(|c, _| is_ident_continue(c));
+        true
+    }
+
     pub(super) fn handle_int_leading_zero(&mut self) -> Token<'lex> {
         let ch = *self.peek().unwrap();
         match ch.value() {
@@ -39,8 +79,14 @@ impl<'lex> Lexer<'lex> {
                 self.handle_int(Radix::Binary)
             }
             '.' => {
-                self.handle_float_part();
-                self.new_token(TokenType::Float)
+                if self.handle_float_part() {
+                    self.handle_float_end()
+                } else {
+                    self.new_token(TokenType::Int {
+                        suffix: None,
+                        radix: Radix::None,
+                    })
+                }
             }
             's' => {
                 self.next();
@@ -64,18 +110,18 @@ impl<'lex> Lexer<'lex> {
                 })
             }
             ch if ch.is_ascii_digit() => {
+                let saved_start = self.start;
                 self.current_to_start();
                 self.gorge_digits();
                 // Leading zeroes are not a problem with floats.
                 if self.handle_float_part() {
-                    return self.new_token(TokenType::Float);
+                    self.set_start(saved_start);
+                    return self.handle_float_end();
                 }
                 let suffix = self.handle_suffix();
-                let len = if suffix.is_some() {
-                    self.next().unwrap().value_owned().len_utf8()
-                } else {
-                    0
-                };
+                if suffix.is_some() {
+                    self.next();
+                }
 
                 self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
@@ -90,20 +136,25 @@ impl<'lex> Lexer<'lex> {
                         })
                         .build(),
                 );
-                self.new_token_with_span(
-                    self.new_span().sub_hi(len),
-                    TokenType::Int {
-                        suffix,
-                        // We assume octal, as other languages (e.g. C) use
-                        // `0755` to mean `0o755`.
-                        radix: Radix::Octal,
-                    },
-                )
+                self.set_start(saved_start);
+                self.new_token(TokenType::Int {
+                    suffix,
+                    // We assume octal, as other languages (e.g. C) use
+                    // `0755` to mean `0o755`.
+                    radix: Radix::Octal,
+                })
+            }
+            _ => {
+                let suffix = if self.handle_invalid_suffix(true) {
+                    Some(Suffix::Invalid)
+                } else {
+                    None
+                };
+                self.new_token(TokenType::Int {
+                    suffix,
+                    radix: Radix::None,
+                })
             }
-            _ => self.new_token(TokenType::Int {
-                suffix: None,
-                radix: Radix::None,
-            }),
         }
     }
 
@@ -124,6 +175,13 @@ impl<'lex> Lexer<'lex> {
         if self.peek_cond(base_cond) != Some(true) {
             return false;
         }
+        // A `.` followed by an identifier (e.g. in `1.to(n)`) or another `.`
+        // is not part of the number.
+        if self.peek_eq(&'.') == Some(true)
+            && self.peek2_cond(|c| is_ident_start(c) || c.value_owned() == '.') == Some(true)
+        {
+            return false;
+        }
         self.handle_unexpected_underscore();
 
         if self.next_if(|c| c.value_owned() == '.').is_some() {
@@ -274,8 +332,15 @@ impl<'lex> Lexer<'lex> {
     }
 
     fn handle_int(&mut self, radix: Radix) -> Token<'lex> {
-        let n_gorged = self.gorge_digits_radix(radix);
+        // Without a prefix, the first digit has already been consumed, so an
+        // underscore can directly follow it.
+        let n_gorged = if radix == Radix::None {
+            self.gorge_while(|c, _| is_valid_for(c, radix) || c.value_owned() == '_')
+        } else {
+            self.gorge_digits_radix(radix)
+        };
         if n_gorged == 0 && radix != Radix::None {
+            let saved_start = self.start;
             self.current_to_start();
             self.gcx.grcx.write().report_syncd(
                 EnsembleBuilder::new()
@@ -289,9 +354,10 @@ impl<'lex> Lexer<'lex> {
                     })
                     .build(),
             );
+            self.set_start(saved_start);
         }
         self.handle_unexpected_underscore();
-        let suffix = self.handle_suffix();
+        let mut suffix = self.handle_suffix();
 
         if radix != Radix::None {
             if let Some(Suffix::Float) = suffix {
@@ -310,14 +376,12 @@ impl<'lex> Lexer<'lex> {
             }
         }
 
-        let len = if suffix.is_some() {
-            self.next().unwrap().value_owned().len_utf8()
-        } else {
-            0
-        };
-        self.new_token_with_span(
-            self.new_span().sub_hi(len),
-            TokenType::Int { suffix, radix },
-        )
+        if suffix.is_some() {
+            self.next();
+        }
+        if self.handle_invalid_suffix(radix == Radix::None && suffix.is_none()) {
+            suffix = Some(Suffix::Invalid);
+        }
+        self.new_token(TokenType::Int { suffix, radix })
     }
 }
@@ -0,0 +1,209 @@
+//! Incremental re-lexing, for editors that need to keep the tokens of a
+//! source file up to date as it is edited.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use calypso_base::span::Span;
+use calypso_common::gcx::GlobalCtxt;
+
+use super::ident_kw::ends_operand;
+use super::{Lexer, Token, TokenType};
+
+/// An edit to a source file, replacing the text at `span` with
+/// `replacement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The span of the replaced text, in the old source.
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    #[must_use]
+    pub fn new(span: Span, replacement: impl Into<String>) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// Apply the edit to the old source.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span of the edit is not within the
+    /// source, or does not lie on character boundaries.
+    #[must_use]
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = source.to_string();
+        edited.replace_range(self.span.lo()..self.span.hi(), &self.replacement);
+        edited
+    }
+}
+
+/// The changes to a token vector after an edit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenDelta<'lex> {
+    /// The indices of the old tokens that were replaced.
+    pub removed: Range<usize>,
+    /// The tokens that replace them, in the new source.
+    pub inserted: Vec<Token<'lex>>,
+    /// The number of bytes that the tokens after the replaced tokens moved
+    /// by.
+    pub shift: isize,
+}
+
+impl<'lex> TokenDelta<'lex> {
+    /// Apply the delta to the old tokens, producing the tokens of the new
+    /// source. The spans of the tokens after the replaced tokens are
+    /// shifted, and all of the lexemes are taken from the new source.
+    #[must_use]
+    pub fn apply(&self, old: &[Token<'_>], source: &'lex str) -> Vec<Token<'lex>> {
+        let moved = |token: &Token<'_>, shift: isize| {
+            let span = shifted(token.span(), shift);
+            Token::new(span, (token.value().0, &source[span.lo()..span.hi()]))
+        };
+        old[..self.removed.start]
+            .iter()
+            .map(|token| moved(token, 0))
+            .chain(self.inserted.iter().copied())
+            .chain(
+                old[self.removed.end..]
+                    .iter()
+                    .map(|token| moved(token, self.shift)),
+            )
+            .collect()
+    }
+}
+
+// Sources are never long enough for their lengths to wrap around.
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn shifted(span: Span, shift: isize) -> Span {
+    Span::new(
+        (span.lo() as isize + shift) as usize,
+        (span.hi() as isize + shift) as usize,
+    )
+}
+
+/// Re-lex only the part of a source file that is affected by an edit.
+///
+/// `old` must be every token of the old source (including trivia, but
+/// without the EOF), as produced by [`Lexer`], and `source` must be the new
+/// source, i.e. the result of [`TextEdit::apply`].
+///
+/// Lexing restarts after the last whitespace before the edit that is
+/// outside of any interpolated string, and stops as soon as it reaches the
+/// start of an unchanged old token after the edit, again outside of any
+/// interpolated string. From there on, the lexer would produce the same
+/// tokens as before. Comments and string literals that are unterminated
+/// (including block comments whose nesting changed) extend to the end of
+/// the file, so everything after them is re-lexed.
+///
+/// Diagnostics are only reported for the re-lexed tokens. Identifiers
+/// before the edit are still taken into account when checking for
+/// confusable identifiers.
+#[must_use]
+pub fn relex<'lex>(
+    file_id: usize,
+    old: &[Token<'_>],
+    edit: &TextEdit,
+    source: &'lex str,
+    gcx: Arc<GlobalCtxt>,
+) -> TokenDelta<'lex> {
+    // The number of interpolated strings and interpolations that each old
+    // token is nested within.
+    let mut depth = 0_usize;
+    let depths = old
+        .iter()
+        .map(|token| {
+            let before = depth;
+            match token.value().0 {
+                TokenType::StringStart | TokenType::InterpStart => depth += 1,
+                TokenType::StringEnd | TokenType::InterpEnd => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            before
+        })
+        .collect::<Vec<_>>();
+
+    // Tokens ending right where the edit starts may be extended by it (e.g.
+    // an identifier), so they're re-lexed, too.
+    let mut start = old
+        .iter()
+        .position(|token| token.span().hi() >= edit.span.lo())
+        .unwrap_or(old.len());
+    // None of the tokens look ahead past whitespace, so any whitespace
+    // before the edit is a safe place to restart from. The only token that
+    // looks behind is `:` (see `ends_operand`), which is given the token
+    // before the restart below.
+    while start > 0
+        && !(depths[start] == 0
+            && matches!(old[start - 1].value().0, TokenType::Ws | TokenType::Newline))
+    {
+        start -= 1;
+    }
+
+    let mut lexer = Lexer::new(file_id, source, gcx);
+    for token in &old[..start] {
+        if let TokenType::Ident(ident) | TokenType::Atom(ident) = token.value().0 {
+            lexer.mark_ident_seen(ident, token.span());
+        }
+    }
+    // Whether a `:` begins an atom depends on the token before it.
+    lexer.prev = old[..start].last().map(|token| token.value().0);
+    let restart = old.get(start).map_or(0, |token| token.span().lo());
+    while lexer.current().lo() < restart {
+        lexer.next();
+    }
+
+    // Where the edit ends, in both sources.
+    let old_hi = edit.span.hi();
+    let new_hi = edit.span.lo() + edit.replacement.len();
+    #[allow(clippy::cast_possible_wrap)]
+    let shift = new_hi as isize - old_hi as isize;
+
+    let mut inserted = Vec::new();
+    let mut end = start;
+    loop {
+        let pos = lexer.current().lo();
+        // A shebang may only be at the very start of the file, so the
+        // tokens there always have to be re-lexed.
+        if pos > 0 && pos >= new_hi && lexer.modes.is_empty() {
+            let old_pos = pos - new_hi + old_hi;
+            while end < old.len() && old[end].span().lo() < old_pos {
+                end += 1;
+            }
+            if end < old.len()
+                && old[end].span().lo() == old_pos
+                && depths[end] == 0
+                && !atom_changed(&lexer, &old[..end], &old[end..])
+            {
+                break;
+            }
+        }
+
+        let token = lexer.scan();
+        if token.value().0 == TokenType::Eof {
+            end = old.len();
+            break;
+        }
+        inserted.push(token);
+    }
+
+    TokenDelta {
+        removed: start..end,
+        inserted,
+        shift,
+    }
+}
+
+/// Check if a `:` at the resynchronization point could be lexed differently
+/// than it was before the edit, i.e. if it begins an atom in one source but
+/// not the other.
+fn atom_changed(lexer: &Lexer<'_>, before: &[Token<'_>], after: &[Token<'_>]) -> bool {
+    let next_is_colon = matches!(after.first(), Some(token) if token.value().1.starts_with(':'));
+    let after_operand = |ty: Option<TokenType>| matches!(ty, Some(ty) if ends_operand(ty));
+    next_is_colon
+        && after_operand(lexer.prev) != after_operand(before.last().map(|token| token.value().0))
+}
@@ -1,5 +1,5 @@
 use super::helpers::is_ident_start;
-use super::{Lexer, Token, TokenType};
+use super::{Lexer, Mode, Token, TokenType};
 
 use calypso_ast::expr::Radix;
 use calypso_base::streams::Stream;
@@ -9,23 +9,35 @@ use calypso_diagnostic::prelude::*;
 impl<'lex> Lexer<'lex> {
     /// Scan a single token.
     ///
-    /// # Errors
-    /// The errors returned by this function are of type [`CalError`].
-    /// When the error is of type [`DiagnosticError::Diagnostic`], it's
-    /// an error that was impossible to recover from.
+    /// Lexical errors (e.g. malformed literals or unterminated comments) are
+    /// reported as synchronizable errors in the global reporting context, and
+    /// the erroneous text is emitted as a [`TokenType::Unexpected`] token so
+    /// that scanning can continue. Scanning itself never fails.
     ///
     /// # Panics
     ///
     /// This function should not panic.
+    pub fn scan(&mut self) -> Token<'lex> {
+        let token = self.scan_token();
+        self.prev = Some(token.value().0);
+        token
+    }
+
+    /// Scan a single token, without keeping track of it as the previous
+    /// token.
     #[allow(clippy::too_many_lines)]
-    pub fn scan(&mut self) -> CalResult<Token<'lex>> {
-        if let Some(wstok) = self.handle_whitespace()? {
-            return Ok(wstok);
+    fn scan_token(&mut self) -> Token<'lex> {
+        if self.modes.last() == Some(&Mode::String) && !self.is_at_end() {
+            return self.handle_string_text();
+        }
+        if let Some(wstok) = self.handle_whitespace() {
+            return wstok;
         }
         self.current_to_start();
 
         if self.is_at_end() {
-            return Ok(self.new_token(TokenType::Eof));
+            self.handle_unterminated_modes();
+            return self.new_token(TokenType::Eof);
         }
 
         // We've already checked if we're at the end (which is when it gives none), so
@@ -33,31 +45,53 @@ impl<'lex> Lexer<'lex> {
         let span = self.next().unwrap();
         let ch = span.value_owned();
 
+        if ch == 'r' && self.is_raw_string_start() {
+            return self.handle_raw_string();
+        }
+        if ch == 'b' && (self.peek_eq(&'"') == Some(true) || self.peek_eq(&'\'') == Some(true)) {
+            return self.handle_byte_literal();
+        }
+
         // Is valid character for identifier's first character
         if is_ident_start(&span) {
-            return Ok(self.handle_identifier());
+            return self.handle_identifier();
         } else if ch == '\'' {
             return self.handle_char_literal();
         } else if ch == '"' {
-            return self.handle_string_literal();
+            if self.peek_eq(&'"') == Some(true) && self.peek2_eq(&'"') == Some(true) {
+                return self.handle_multiline_string();
+            }
+            return if self.is_interpolated_string() {
+                self.handle_interpolated_string()
+            } else {
+                self.handle_string_literal()
+            };
+        } else if ch == ':' && self.is_atom_start() {
+            return if self.peek_eq(&'"') == Some(true) {
+                self.handle_atom_string()
+            } else {
+                self.handle_atom()
+            };
         }
 
         if ch == '0' {
             if self.is_at_end() {
-                return Ok(self.new_token(TokenType::Int {
+                return self.new_token(TokenType::Int {
                     suffix: None,
                     radix: Radix::None,
-                }));
+                });
             }
-            return Ok(self.handle_int_leading_zero());
+            let token = self.handle_int_leading_zero();
+            return self.check_number(token);
         } else if ch.is_ascii_digit() {
-            return Ok(self.handle_number());
+            let token = self.handle_number();
+            return self.check_number(token);
         }
 
         let token_type = match ch {
             '<' if self.next_if_eq(&'<').is_some() => {
                 if self.next_if_eq(&'=').is_some() {
-                    unimplemented!() // TokenType::LtLtEq
+                    TokenType::LtLtEq
                 } else {
                     TokenType::LtLt
                 }
@@ -67,7 +101,7 @@ impl<'lex> Lexer<'lex> {
 
             '>' if self.next_if_eq(&'>').is_some() => {
                 if self.next_if_eq(&'=').is_some() {
-                    unimplemented!() // TokenType::GtGtEq
+                    TokenType::GtGtEq
                 } else {
                     TokenType::GtGt
                 }
@@ -76,69 +110,70 @@ impl<'lex> Lexer<'lex> {
             '>' => TokenType::Gt,
 
             '=' if self.next_if_eq(&'=').is_some() => TokenType::EqEq,
-            // '=' => TokenType::Eq,
+            '=' => TokenType::Eq,
             '!' if self.next_if_eq(&'=').is_some() => TokenType::BangEq,
             '!' => TokenType::Bang,
 
-            // '|' if self.next_if_eq(&'>').is_some() => TokenType::PipeGt,
+            '|' if self.next_if_eq(&'>').is_some() => TokenType::PipeGt,
             '|' if self.next_if_eq(&'|').is_some() => TokenType::PipePipe,
-            // '|' if self.next_if_eq(&'=').is_some() => TokenType::PipeEq,
+            '|' if self.next_if_eq(&'=').is_some() => TokenType::PipeEq,
             '|' => TokenType::Pipe,
             '&' if self.next_if_eq(&'&').is_some() => TokenType::AndAnd,
-            // '&' if self.next_if_eq(&'=').is_some() => TokenType::AndEq,
+            '&' if self.next_if_eq(&'=').is_some() => TokenType::AndEq,
             '&' => TokenType::And,
 
-            // '+' if self.next_if_eq(&'=').is_some() => TokenType::PlusEq,
+            '+' if self.next_if_eq(&'=').is_some() => TokenType::PlusEq,
             '+' => TokenType::Plus,
 
-            // '-' if self.next_if_eq(&'=').is_some() => TokenType::MinusEq,
-            // '-' if self.next_if_eq(&'>').is_some() => TokenType::Arrow,
+            '-' if self.next_if_eq(&'=').is_some() => TokenType::MinusEq,
+            '-' if self.next_if_eq(&'>').is_some() => TokenType::Arrow,
             '-' => TokenType::Minus,
 
             '*' if self.next_if_eq(&'*').is_some() => {
                 if self.next_if_eq(&'=').is_some() {
-                    unimplemented!() // TokenType::StarStarEq
+                    TokenType::StarStarEq
                 } else {
                     TokenType::StarStar
                 }
             }
-            // '*' if self.next_if_eq(&'=').is_some() => TokenType::StarEq,
+            '*' if self.next_if_eq(&'=').is_some() => TokenType::StarEq,
             '*' => TokenType::Star,
 
-            // '/' if self.next_if_eq(&'=').is_some() => TokenType::SlashEq,
+            '/' if self.next_if_eq(&'=').is_some() => TokenType::SlashEq,
             '/' => TokenType::Slash,
 
-            // '%' if self.next_if_eq(&'=').is_some() => TokenType::PercentEq,
+            '%' if self.next_if_eq(&'=').is_some() => TokenType::PercentEq,
             '%' => TokenType::Percent,
 
-            // '^' if self.next_if_eq(&'=').is_some() => TokenType::CaretEq,
+            '^' if self.next_if_eq(&'=').is_some() => TokenType::CaretEq,
             '^' => TokenType::Caret,
 
             '(' => TokenType::LParen,
             ')' => TokenType::RParen,
 
-            // '{' => TokenType::LBrace,
-            // '}' => TokenType::RBrace,
+            '{' => self.handle_lbrace(),
+            '}' => self.handle_rbrace(),
 
-            // '[' => TokenType::LBracket,
-            // ']' => TokenType::RBracket,
+            '[' => TokenType::LBracket,
+            ']' => TokenType::RBracket,
 
-            // ',' => TokenType::Comma,
-            // ';' => TokenType::Semi,
+            ',' => TokenType::Comma,
+            ';' => TokenType::Semi,
             ':' => TokenType::Colon,
 
-            // '.' if self.next_if_eq(&'.').is_some() => {
-            //     if self.next_if_eq(&'=').is_some() {
-            //         TokenType::DotDotEq
-            //     } else {
-            //         TokenType::DotDot
-            //     }
-            // }
-            // '.' => TokenType::Dot,
+            '.' if self.next_if_eq(&'.').is_some() => {
+                if self.next_if_eq(&'=').is_some() {
+                    TokenType::DotDotEq
+                } else {
+                    TokenType::DotDot
+                }
+            }
+            '.' => TokenType::Dot,
 
-            // // `'_' => Under` is already taken care of by idents
-            // '#' if self.next_if_eq(&'!').is_some() => TokenType::HashBang,
-            // '#' => TokenType::Hash,
+            // `'_' => Under` is already taken care of by idents
+            '#' if self.next_if_eq(&'!').is_some() => TokenType::HashBang,
+            '#' => TokenType::Hash,
+            '\\' if self.next_if_eq(&'\\').is_some() => TokenType::BackslashBackslash,
 
             // Unexpected character
             _ => {
@@ -159,6 +194,6 @@ impl<'lex> Lexer<'lex> {
             }
         };
 
-        Ok(self.new_token(token_type))
+        self.new_token(token_type)
     }
 }
@@ -0,0 +1,752 @@
+use std::sync::Arc;
+
+use calypso_base::{
+    span::{Span, Spanned},
+    symbol::Symbol,
+    ui::{termcolor::ColorChoice, Emitters},
+};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::{
+    diagnostic::{EnsembleDiagnostic, SourceMgr},
+    report::GlobalReportingCtxt,
+};
+use proptest::prelude::*;
+
+use super::relex::{relex, TextEdit};
+use super::{Lexer, Token, TokenType};
+
+fn new_gcx() -> Arc<GlobalCtxt> {
+    Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(ColorChoice::Never, ColorChoice::Never)),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    })
+}
+
+/// Lex the whole source, returning the tokens (excluding whitespace and
+/// comments) and the number of synchronizable errors that were reported.
+fn lex(source: &str) -> (Vec<(TokenType, &str)>, usize) {
+    let gcx = new_gcx();
+    let tokens = Lexer::new(0, source, Arc::clone(&gcx))
+        .into_iter()
+        .map(Spanned::value_owned)
+        .filter(|(ty, _)| {
+            !matches!(
+                ty,
+                TokenType::Ws | TokenType::LineComment { .. } | TokenType::BlockComment { .. }
+            )
+        })
+        .collect();
+    let n_errors = gcx.grcx.read().errors().len();
+    assert!(gcx.grcx.read().fatal().is_none());
+    (tokens, n_errors)
+}
+
+#[test]
+fn unterminated_string_recovers() {
+    let (tokens, n_errors) = lex("+ \"abc");
+    assert_eq!(
+        tokens,
+        vec![(TokenType::Plus, "+"), (TokenType::Unexpected, "\"abc")]
+    );
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn unterminated_char_recovers() {
+    let (tokens, n_errors) = lex("'a");
+    assert_eq!(tokens, vec![(TokenType::Unexpected, "'a")]);
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn long_char_recovers() {
+    let (tokens, n_errors) = lex("'abc' + 'd'");
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::Unexpected, "'abc'"),
+            (TokenType::Plus, "+"),
+            (TokenType::Char('d'), "'d'")
+        ]
+    );
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn invalid_escapes_recover() {
+    let (tokens, n_errors) = lex(r#""\x" "\x4" "\u" "\u{ }" "\u{12g}" - "ok""#);
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::String(Symbol::intern("\u{FFFD}")), r#""\x""#),
+            (TokenType::String(Symbol::intern("\u{FFFD}")), r#""\x4""#),
+            (TokenType::String(Symbol::intern("\u{FFFD}")), r#""\u""#),
+            (
+                TokenType::String(Symbol::intern("\u{FFFD} }")),
+                r#""\u{ }""#
+            ),
+            (
+                TokenType::String(Symbol::intern("\u{FFFD}g}")),
+                r#""\u{12g}""#
+            ),
+            (TokenType::Minus, "-"),
+            (TokenType::String(Symbol::intern("ok")), r#""ok""#),
+        ]
+    );
+    assert_eq!(n_errors, 5);
+}
+
+#[test]
+fn unterminated_block_comment_recovers() {
+    let (tokens, n_errors) = lex("+ /* /* */");
+    assert_eq!(tokens, vec![(TokenType::Plus, "+")]);
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn reports_every_error() {
+    let (tokens, n_errors) = lex("'ab' \"\\q\" $ \"end");
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::Unexpected, "'ab'"),
+            (TokenType::String(Symbol::intern("\u{FFFD}")), "\"\\q\""),
+            (TokenType::Unexpected, "$"),
+            (TokenType::Unexpected, "\"end"),
+        ]
+    );
+    assert_eq!(n_errors, 4);
+}
+
+#[test]
+fn punctuation() {
+    let (tokens, n_errors) = lex(r"{ } [ ] , ; . .. ..= -> |> # #! \\ : _ = ( )");
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::LBrace, "{"),
+            (TokenType::RBrace, "}"),
+            (TokenType::LBracket, "["),
+            (TokenType::RBracket, "]"),
+            (TokenType::Comma, ","),
+            (TokenType::Semi, ";"),
+            (TokenType::Dot, "."),
+            (TokenType::DotDot, ".."),
+            (TokenType::DotDotEq, "..="),
+            (TokenType::Arrow, "->"),
+            (TokenType::PipeGt, "|>"),
+            (TokenType::Hash, "#"),
+            (TokenType::HashBang, "#!"),
+            (TokenType::BackslashBackslash, r"\\"),
+            (TokenType::Colon, ":"),
+            (TokenType::Under, "_"),
+            (TokenType::Eq, "="),
+            (TokenType::LParen, "("),
+            (TokenType::RParen, ")"),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn compound_assignment() {
+    let (tokens, n_errors) = lex("+= -= *= **= /= %= ^= |= &= <<= >>=");
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::PlusEq, "+="),
+            (TokenType::MinusEq, "-="),
+            (TokenType::StarEq, "*="),
+            (TokenType::StarStarEq, "**="),
+            (TokenType::SlashEq, "/="),
+            (TokenType::PercentEq, "%="),
+            (TokenType::CaretEq, "^="),
+            (TokenType::PipeEq, "|="),
+            (TokenType::AndEq, "&="),
+            (TokenType::LtLtEq, "<<="),
+            (TokenType::GtGtEq, ">>="),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn operators_are_maximally_munched() {
+    let (tokens, n_errors) = lex("a<<b>>=c**d||e|>f...g");
+    let types = tokens.into_iter().map(|(ty, _)| ty).collect::<Vec<_>>();
+    assert!(matches!(
+        types.as_slice(),
+        [
+            TokenType::Ident(_),
+            TokenType::LtLt,
+            TokenType::Ident(_),
+            TokenType::GtGtEq,
+            TokenType::Ident(_),
+            TokenType::StarStar,
+            TokenType::Ident(_),
+            TokenType::PipePipe,
+            TokenType::Ident(_),
+            TokenType::PipeGt,
+            TokenType::Ident(_),
+            TokenType::DotDot,
+            TokenType::Dot,
+            TokenType::Ident(_),
+        ]
+    ));
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn ident_suffixes() {
+    let (tokens, n_errors) = lex("empty? push! save!? a!!");
+    let lexemes = tokens
+        .iter()
+        .map(|(ty, lexeme)| {
+            assert!(matches!(ty, TokenType::Ident(_) | TokenType::Bang));
+            *lexeme
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lexemes, vec!["empty?", "push!", "save!?", "a!", "!"]);
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn ident_suffixes_wrong_order() {
+    let (tokens, n_errors) = lex("save?!");
+    assert!(matches!(
+        tokens.as_slice(),
+        [(TokenType::Ident(_), "save?!")]
+    ));
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn ident_suffix_not_eq() {
+    let gcx = new_gcx();
+    let tokens = Lexer::new(0, "a!=b a?!=b", Arc::clone(&gcx))
+        .into_iter()
+        .map(Spanned::value_owned)
+        .filter(|(ty, _)| {
+            !matches!(
+                ty,
+                TokenType::Ws | TokenType::LineComment { .. } | TokenType::BlockComment { .. }
+            )
+        })
+        .map(|(_, lexeme)| lexeme)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec!["a", "!=", "b", "a?", "!=", "b"]);
+    assert!(gcx.grcx.read().errors().is_empty());
+    // There's no identifier `a!` that this could be mistaken for.
+    assert!(gcx.grcx.read().nonfatals().is_empty());
+
+    // The identifier `a!` may come before or after the `!=`.
+    assert_eq!(nonfatal_codes("a! = 1\na!=b"), vec!["E0037"]);
+    assert_eq!(nonfatal_codes("n!=0 n!=1\nn! = 2"), vec!["E0037", "E0037"]);
+    assert!(nonfatal_codes("n!? = 1\nn!=0").is_empty());
+}
+
+#[test]
+fn unicode_identifiers() {
+    // The second `café` uses a combining acute accent, so it's normalized to
+    // the same symbol as the first.
+    let (tokens, n_errors) = lex("café cafe\u{301} 日本語 _π2 :λ");
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::Ident(Symbol::intern("café")), "café"),
+            (TokenType::Ident(Symbol::intern("café")), "cafe\u{301}"),
+            (TokenType::Ident(Symbol::intern("日本語")), "日本語"),
+            (TokenType::Ident(Symbol::intern("_π2")), "_π2"),
+            (TokenType::Atom(Symbol::intern("λ")), ":λ"),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+/// Lex the whole source, returning the codes of the synchronizable errors
+/// that were reported.
+fn error_codes(source: &str) -> Vec<String> {
+    let gcx = new_gcx();
+    for _ in Lexer::new(0, source, Arc::clone(&gcx)) {}
+    let reports = gcx.grcx.read();
+    reports
+        .errors()
+        .iter()
+        .flat_map(EnsembleDiagnostic::diagnostics)
+        .filter_map(|diag| diag.code().map(ToString::to_string))
+        .collect()
+}
+
+/// Lex the whole source, returning the codes of the non-fatal diagnostics
+/// that were reported.
+fn nonfatal_codes(source: &str) -> Vec<String> {
+    let gcx = new_gcx();
+    for _ in Lexer::new(0, source, Arc::clone(&gcx)) {}
+    let reports = gcx.grcx.read();
+    assert!(reports.errors().is_empty());
+    reports
+        .nonfatals()
+        .iter()
+        .flat_map(EnsembleDiagnostic::diagnostics)
+        .filter_map(|diag| diag.code().map(ToString::to_string))
+        .collect()
+}
+
+#[test]
+fn identifier_security() {
+    // Single-script identifiers are fine, in any script.
+    assert!(nonfatal_codes("пример παράδειγμα example_1").is_empty());
+    // Cyrillic `а` in an otherwise Latin identifier. It's only reported once.
+    assert_eq!(nonfatal_codes("pаypal pаypal"), vec!["E0048"]);
+    // Cyrillic `ѕ` looks like Latin `s`. Atoms share symbols with identifiers.
+    assert_eq!(nonfatal_codes("ѕ s :s"), vec!["E0049"]);
+    // Confusables within ASCII aren't reported.
+    assert!(nonfatal_codes("l1 I1 ll").is_empty());
+    // Old Italic is a historic script.
+    assert_eq!(nonfatal_codes("𐌀"), vec!["E0047"]);
+}
+
+#[test]
+fn atoms() {
+    let (tokens, n_errors) = lex(r#":foo :ok? :"foo bar" a:b"#);
+    let atoms = tokens
+        .iter()
+        .map(|(ty, lexeme)| match ty {
+            TokenType::Atom(atom) => (atom.as_str(), *lexeme),
+            _ => ("", *lexeme),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        atoms,
+        vec![
+            ("foo", ":foo"),
+            ("ok?", ":ok?"),
+            ("foo bar", r#":"foo bar""#),
+            ("", "a"),
+            ("", ":"),
+            ("", "b"),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn colons_after_operands() {
+    // A `:` directly after an operand, or followed by whitespace, is never
+    // an atom.
+    let (tokens, n_errors) = lex(r#"n : uint n: uint "x":y :a:b f(:c)"#);
+    let atoms = tokens
+        .iter()
+        .filter_map(|(ty, lexeme)| match ty {
+            TokenType::Atom(_) => Some(*lexeme),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(atoms, vec![":a", ":c"]);
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn interpolated_strings() {
+    let (tokens, n_errors) = lex(r#""a ${ {b} } \${c}${"${d}"}""#);
+    assert_eq!(
+        tokens
+            .iter()
+            .map(|(ty, lexeme)| match ty {
+                TokenType::Ident(_) | TokenType::Ws => (TokenType::Ws, *lexeme),
+                _ => (*ty, *lexeme),
+            })
+            .collect::<Vec<_>>(),
+        vec![
+            (TokenType::StringStart, "\""),
+            (TokenType::StringFragment(Symbol::intern("a ")), "a "),
+            (TokenType::InterpStart, "${"),
+            (TokenType::LBrace, "{"),
+            (TokenType::Ws, "b"),
+            (TokenType::RBrace, "}"),
+            (TokenType::InterpEnd, "}"),
+            (
+                TokenType::StringFragment(Symbol::intern(" ${c}")),
+                r" \${c}"
+            ),
+            (TokenType::InterpStart, "${"),
+            (TokenType::StringStart, "\""),
+            (TokenType::InterpStart, "${"),
+            (TokenType::Ws, "d"),
+            (TokenType::InterpEnd, "}"),
+            (TokenType::StringEnd, "\""),
+            (TokenType::InterpEnd, "}"),
+            (TokenType::StringEnd, "\""),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn unterminated_interpolation() {
+    let (tokens, n_errors) = lex(r#""a${b"#);
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(n_errors, 1);
+
+    let (tokens, n_errors) = lex(r#""a${b}"#);
+    assert_eq!(tokens.len(), 5);
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn numbers_before_dots() {
+    use calypso_ast::expr::Radix;
+
+    let int = TokenType::Int {
+        suffix: None,
+        radix: Radix::None,
+    };
+    let (tokens, n_errors) = lex("1.to(n) 0.x 1..2 1.5");
+    assert_eq!(
+        tokens,
+        vec![
+            (int, "1"),
+            (TokenType::Dot, "."),
+            (TokenType::Ident(Symbol::intern("to")), "to"),
+            (TokenType::LParen, "("),
+            (TokenType::Ident(Symbol::intern("n")), "n"),
+            (TokenType::RParen, ")"),
+            (int, "0"),
+            (TokenType::Dot, "."),
+            (TokenType::Ident(Symbol::intern("x")), "x"),
+            (int, "1"),
+            (TokenType::DotDot, ".."),
+            (int, "2"),
+            (TokenType::Float, "1.5"),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+#[test]
+fn number_suffixes() {
+    use calypso_ast::expr::{Radix, Suffix};
+
+    let (tokens, n_errors) = lex("1_000 5u 0xffs 12abc 0b102 1e5 1.5u 1.5f 1e5x");
+    assert_eq!(
+        tokens,
+        vec![
+            (
+                TokenType::Int {
+                    suffix: None,
+                    radix: Radix::None
+                },
+                "1_000"
+            ),
+            (
+                TokenType::Int {
+                    suffix: Some(Suffix::Uint),
+                    radix: Radix::None
+                },
+                "5u"
+            ),
+            (
+                TokenType::Int {
+                    suffix: Some(Suffix::Sint),
+                    radix: Radix::Hexadecimal
+                },
+                "0xffs"
+            ),
+            (
+                TokenType::Int {
+                    suffix: Some(Suffix::Invalid),
+                    radix: Radix::None
+                },
+                "12abc"
+            ),
+            (
+                TokenType::Int {
+                    suffix: Some(Suffix::Invalid),
+                    radix: Radix::Binary
+                },
+                "0b102"
+            ),
+            (TokenType::Float, "1e5"),
+            (TokenType::Float, "1.5u"),
+            (TokenType::Float, "1.5f"),
+            (TokenType::Float, "1e5x"),
+        ]
+    );
+    // Invalid suffixes and digits are reported, but the tokens are kept.
+    assert_eq!(n_errors, 4);
+    assert_eq!(
+        error_codes("12abc 0b102 1.5u 1e5x 1e"),
+        vec!["E0042", "E0043", "E0046", "E0042", "E0032"]
+    );
+}
+
+#[test]
+fn newlines() {
+    let (tokens, n_errors) = lex("a\n// b\r\n/* c\n */\n");
+    assert_eq!(
+        tokens
+            .iter()
+            .map(|(ty, _)| *ty)
+            .filter(|ty| !matches!(ty, TokenType::Ident(_)))
+            .collect::<Vec<_>>(),
+        vec![TokenType::Newline, TokenType::Newline, TokenType::Newline]
+    );
+    assert_eq!(n_errors, 0);
+}
+
+/// Lex the whole source, returning every token's type.
+fn lex_all(source: &str) -> Vec<TokenType> {
+    Lexer::new(0, source, new_gcx())
+        .into_iter()
+        .map(|tok| tok.value().0)
+        .collect()
+}
+
+#[test]
+fn comments() {
+    let line = |doc, inner| TokenType::LineComment { doc, inner };
+    assert_eq!(
+        lex_all("// a\n/// b\n//! c\n//// d"),
+        vec![
+            line(false, false),
+            TokenType::Newline,
+            line(true, false),
+            TokenType::Newline,
+            line(true, true),
+            TokenType::Newline,
+            line(false, false),
+        ]
+    );
+
+    let block = |doc, inner, nest_level| TokenType::BlockComment {
+        doc,
+        inner,
+        nest_level,
+    };
+    assert_eq!(
+        lex_all("/* a */ /** b */ /*! c */ /**/ /* /* /* d */ */ */"),
+        vec![
+            block(false, false, 0),
+            TokenType::Ws,
+            block(true, false, 0),
+            TokenType::Ws,
+            block(true, true, 0),
+            TokenType::Ws,
+            block(false, false, 0),
+            TokenType::Ws,
+            block(false, false, 2),
+        ]
+    );
+}
+
+#[test]
+fn shebang() {
+    use calypso_ast::expr::Radix;
+
+    assert_eq!(
+        lex_all("#!/usr/bin/env calypso\n1"),
+        vec![
+            TokenType::Shebang,
+            TokenType::Newline,
+            TokenType::Int {
+                suffix: None,
+                radix: Radix::None
+            },
+        ]
+    );
+    // Only on the very first line, and never when it begins an attribute.
+    assert_eq!(
+        lex_all(" #!a"),
+        vec![
+            TokenType::Ws,
+            TokenType::HashBang,
+            TokenType::Ident(Symbol::intern("a"))
+        ]
+    );
+    assert_eq!(
+        lex_all("#! [a]"),
+        vec![
+            TokenType::HashBang,
+            TokenType::Ws,
+            TokenType::LBracket,
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::RBracket,
+        ]
+    );
+}
+
+#[test]
+fn raw_strings() {
+    let (tokens, n_errors) = lex(r###"r"a\b" r#"say "hi""# r##"x "# y"## r #"###);
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::RawString { hashes: 0 }, r#"r"a\b""#),
+            (TokenType::RawString { hashes: 1 }, "r#\"say \"hi\"\"#"),
+            (TokenType::RawString { hashes: 2 }, r###"r##"x "# y"##"###),
+            (TokenType::Ident(Symbol::intern("r")), "r"),
+            (TokenType::Hash, "#"),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+
+    let gcx = new_gcx();
+    let tokens = Lexer::new(0, r##"r##"a"# b"##, Arc::clone(&gcx))
+        .into_iter()
+        .map(Spanned::value_owned)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens[0], (TokenType::Unexpected, r##"r##"a"# b"##));
+    let reports = gcx.grcx.read();
+    assert_eq!(reports.errors().len(), 1);
+    let diag = &reports.errors()[0].diagnostics()[0];
+    assert_eq!(diag.code(), Some("E0050"));
+    assert_eq!(diag.notes().len(), 1);
+}
+
+#[test]
+fn multiline_strings() {
+    let source = "\"\"\"\n  a \"\"\n\n  b\n  \"\"\" 1";
+    let (tokens, n_errors) = lex(source);
+    assert_eq!(
+        tokens[0],
+        (
+            TokenType::MultilineString(Symbol::intern("a \"\"\n\nb")),
+            &source[..source.len() - 2]
+        )
+    );
+    assert_eq!(n_errors, 0);
+
+    // Not on lines of their own.
+    assert_eq!(lex("\"\"\"a\n  b\"\"\"").1, 2);
+    // Insufficient indentation.
+    assert_eq!(lex("\"\"\"\n  a\n b\nc\n  \"\"\"").1, 2);
+    // Unterminated.
+    let (tokens, n_errors) = lex("\"\"\"\na\"\"");
+    assert_eq!(tokens, vec![(TokenType::Unexpected, "\"\"\"\na\"\"")]);
+    assert_eq!(n_errors, 1);
+}
+
+#[test]
+fn byte_literals() {
+    let (tokens, n_errors) = lex(r#"b"\x7FELF\xff" b'\x00' b'x' b "c""#);
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::ByteString, r#"b"\x7FELF\xff""#),
+            (TokenType::Byte(0), r"b'\x00'"),
+            (TokenType::Byte(b'x'), "b'x'"),
+            (TokenType::Ident(Symbol::intern("b")), "b"),
+            (TokenType::String(Symbol::intern("c")), "\"c\""),
+        ]
+    );
+    assert_eq!(n_errors, 0);
+
+    let gcx = new_gcx();
+    for _ in Lexer::new(0, r#"b"é\u{41}" b'é'"#, Arc::clone(&gcx)) {}
+    let reports = gcx.grcx.read();
+    assert_eq!(
+        reports
+            .errors()
+            .iter()
+            .flat_map(EnsembleDiagnostic::diagnostics)
+            .filter_map(|diag| diag.code())
+            .collect::<Vec<_>>(),
+        vec!["E0055", "E0056", "E0055"]
+    );
+
+    // An empty byte literal is an error, but still a byte literal.
+    let (tokens, n_errors) = lex("b''");
+    assert_eq!(tokens, vec![(TokenType::Byte(b'?'), "b''")]);
+    assert_eq!(n_errors, 1);
+}
+
+fn lex_tokens(source: &str) -> Vec<Token<'_>> {
+    Lexer::new(0, source, new_gcx()).into_iter().collect()
+}
+
+/// Re-lex the source after replacing `lo..hi` with `replacement`, check that
+/// the result is the same as lexing the new source from scratch, and return
+/// the number of old tokens that were replaced and the number of new tokens.
+fn relexed(source: &str, lo: usize, hi: usize, replacement: &str) -> (usize, usize) {
+    let old = lex_tokens(source);
+    let edit = TextEdit::new(Span::new(lo, hi), replacement);
+    let new_source = edit.apply(source);
+    let delta = relex(0, &old, &edit, &new_source, new_gcx());
+    assert_eq!(delta.apply(&old, &new_source), lex_tokens(&new_source));
+    (delta.removed.len(), delta.inserted.len())
+}
+
+#[test]
+fn relex_resynchronizes() {
+    // Lexing restarts after the last whitespace before the edit.
+    assert_eq!(relexed("a + bc * d", 4, 6, "xyz"), (3, 3));
+    // Extending a token at the end of the edit.
+    assert_eq!(relexed("a + bc * d", 6, 6, "c"), (1, 1));
+    assert_eq!(relexed("a +\n  bc", 3, 4, ""), (2, 1));
+    // Edits within interpolated strings restart outside of them.
+    assert_eq!(relexed("x \"a ${b} c\" y", 7, 8, "bb"), (7, 7));
+    // Edits at the very start may create or remove a shebang.
+    assert_eq!(relexed("#!x\nfoo", 0, 2, ""), (1, 1));
+    assert_eq!(relexed("!x\nfoo", 0, 0, "#"), (2, 1));
+    // Whether a `:` begins an atom depends on the token before it.
+    assert_eq!(relexed("a :b", 1, 2, ""), (3, 3));
+    assert_eq!(relexed("a:b", 1, 1, " "), (3, 3));
+    assert_eq!(relexed("a:b", 0, 1, "("), (3, 2));
+    assert_eq!(relexed("(:b", 0, 1, "a"), (2, 3));
+}
+
+#[test]
+fn relex_to_end() {
+    let source = "a /* b */ c \"d\" e";
+    // Opening a block comment swallows everything after it.
+    assert_eq!(relexed(source, 0, 0, "/*").1, 1);
+    // Opening a string changes which quotes are paired.
+    assert_eq!(relexed(source, 0, 0, "\"").1, 3);
+    // Changing the nesting of a block comment does, too.
+    assert_eq!(relexed(source, 5, 5, "/*").1, 1);
+    // Closing an unterminated comment splits it up again.
+    assert_eq!(relexed("/* a /* b */ c", 3, 3, "*/"), (1, 6));
+}
+
+#[test]
+fn erroneous_ints_keep_their_prefix() {
+    use calypso_ast::expr::Radix;
+
+    let int = |radix| TokenType::Int {
+        suffix: None,
+        radix,
+    };
+    assert_eq!(lex("0755"), (vec![(int(Radix::Octal), "0755")], 1));
+    assert_eq!(lex("0b"), (vec![(int(Radix::Binary), "0b")], 1));
+    assert_eq!(lex("00.5").0, vec![(TokenType::Float, "00.5")]);
+}
+
+proptest! {
+    /// The lexer never panics, and its tokens cover every byte of the source.
+    #[test]
+    fn lexer_covers_every_byte(
+        source in prop_oneof![any::<String>(), "[ \\n\\r\\t\"'`$#!:_.(){}/*\\\\a-z0-9]{0,64}"]
+    ) {
+        let mut hi = 0;
+        for token in Lexer::new(0, &source, new_gcx()) {
+            prop_assert_eq!(token.span().lo(), hi);
+            prop_assert_eq!(token.value().1, &source[token.span().lo()..token.span().hi()]);
+            hi = token.span().hi();
+        }
+        prop_assert_eq!(hi, source.len());
+    }
+
+    /// Re-lexing after an edit gives the same tokens as lexing from scratch.
+    #[test]
+    fn relex_matches_lexer(
+        source in "[ \n\"$#:_(){}/*a-z0-9]{0,32}",
+        lo in 0..=32_usize,
+        len in 0..4_usize,
+        replacement in "[ \n\"$#:_(){}/*a-z0-9]{0,4}",
+    ) {
+        let lo = lo.min(source.len());
+        let hi = (lo + len).min(source.len());
+        relexed(&source, lo, hi, &replacement);
+    }
+}
@@ -46,6 +46,50 @@ pub enum TokenType {
     Gt,
     /// `:`
     Colon,
+    /// `=`
+    Eq,
+    /// `+=`
+    PlusEq,
+    /// `-=`
+    MinusEq,
+    /// `*=`
+    StarEq,
+    /// `**=`
+    StarStarEq,
+    /// `/=`
+    SlashEq,
+    /// `%=`
+    PercentEq,
+    /// `^=`
+    CaretEq,
+    /// `|=`
+    PipeEq,
+    /// `&=`
+    AndEq,
+    /// `<<=`
+    LtLtEq,
+    /// `>>=`
+    GtGtEq,
+    /// `->`
+    Arrow,
+    /// `|>`
+    PipeGt,
+    /// `,`
+    Comma,
+    /// `;`
+    Semi,
+    /// `.`
+    Dot,
+    /// `..`
+    DotDot,
+    /// `..=`
+    DotDotEq,
+    /// `#`
+    Hash,
+    /// `#!`
+    HashBang,
+    /// `\\`
+    BackslashBackslash,
 
     /// `_`
     Under,
@@ -53,6 +97,14 @@ pub enum TokenType {
     LParen,
     /// `)`
     RParen,
+    /// `{`
+    LBrace,
+    /// `}`
+    RBrace,
+    /// `[`
+    LBracket,
+    /// `]`
+    RBracket,
 
     /// End-of-file / end-of-input
     Eof,
@@ -60,8 +112,10 @@ pub enum TokenType {
     Unexpected,
     /// Identifier
     Ident(Symbol),
-    /// Whitespace
+    /// Whitespace, not including line breaks
     Ws,
+    /// A line break (`\n` or `\r\n`)
+    Newline,
     /// Line comments
     LineComment {
         /// Is this a documentation comment?
@@ -69,22 +123,52 @@ pub enum TokenType {
         /// Is this an inner doc comment?
         inner: bool,
     },
-    /// Comment
+    /// Block comments
     BlockComment {
         /// Is this a documentation comment?
         doc: bool,
         /// Is this an inner doc comment?
         inner: bool,
-        /// How deeply nested this comment is
+        /// How deeply nested the comments within this comment are (`0` if
+        /// there are no nested comments)
         nest_level: usize,
     },
+    /// A shebang (e.g. `#!/usr/bin/env calypso`) on the first line of a file
+    Shebang,
 
     /// Keyword
     Keyword(Symbol),
-    /// String literal
-    String,
-    /// Character literal
-    Char,
+    /// Atom (`:name` or `:"name"`), not including the `:` or quotes
+    Atom(Symbol),
+    /// String literal, with its decoded value
+    String(Symbol),
+    /// Raw string literal (`r"..."`, `r#"..."#`, etc.), without escapes
+    RawString {
+        /// The number of `#`s around the quotes
+        hashes: usize,
+    },
+    /// Multi-line string literal (`"""` ... `"""`), with its decoded value
+    /// (i.e. with the indentation of the closing `"""` stripped from each
+    /// line)
+    MultilineString(Symbol),
+    /// The opening `"` of a string literal containing interpolations
+    StringStart,
+    /// A part of an interpolated string literal that is not an
+    /// interpolation, with its decoded value
+    StringFragment(Symbol),
+    /// The closing `"` of a string literal containing interpolations
+    StringEnd,
+    /// `${`, within a string literal
+    InterpStart,
+    /// The `}` ending an interpolation
+    InterpEnd,
+    /// Character literal, with its decoded value
+    Char(char),
+    /// Byte string literal (`b"..."`), containing only ASCII characters and
+    /// escapes
+    ByteString,
+    /// Byte literal (`b'x'`), with its decoded value
+    Byte(u8),
     /// Integer literal (not split into sint/uint cause of constraints)
     Int {
         /// The integer suffix, if present
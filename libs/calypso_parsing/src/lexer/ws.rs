@@ -1,4 +1,4 @@
-use super::helpers::is_whitespace;
+use super::helpers::{is_whitespace, is_whitespace_ch};
 use super::{Lexer, Token, TokenType};
 
 use calypso_base::streams::Stream;
@@ -6,53 +6,130 @@ use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
 use calypso_diagnostic::prelude::*;
 
 impl<'lex> Lexer<'lex> {
-    // todo(parse: @ThePuzzlemaker): Split whitespace and comments, use comment
-    //                               tokens
-    pub(super) fn handle_whitespace(&mut self) -> CalResult<Option<Token<'lex>>> {
+    /// Handle whitespace, newlines and comments, returning a single token
+    /// for whichever of these is next (or `None` if none of these are next).
+    pub(super) fn handle_whitespace(&mut self) -> Option<Token<'lex>> {
         self.current_to_start();
-        self.handle_dangling_comment_ends();
-        while !self.is_at_end()
-            && (self.handle_comment()
-                || self.handle_multiline_comment()?
-                || self.next_if(is_whitespace).is_some())
-        {
-            self.handle_dangling_comment_ends();
+        if self.handle_shebang() {
+            return Some(self.new_token(TokenType::Shebang));
+        }
+        if self.handle_newline() {
+            return Some(self.new_token(TokenType::Newline));
+        }
+        if let Some((doc, inner)) = self.handle_comment() {
+            return Some(self.new_token(TokenType::LineComment { doc, inner }));
+        }
+        if let Some((doc, inner, nest_level)) = self.handle_multiline_comment() {
+            return Some(self.new_token(TokenType::BlockComment {
+                doc,
+                inner,
+                nest_level,
+            }));
         }
+        // Dangling comment ends are reported, and then treated as whitespace.
+        self.handle_dangling_comment_ends();
+        while !self.is_at_newline() && self.next_if(is_whitespace).is_some() {}
         if self.new_span().is_empty() {
-            Ok(None)
+            None
         } else {
-            Ok(Some(self.new_token(TokenType::Ws)))
+            Some(self.new_token(TokenType::Ws))
+        }
+    }
+
+    /// Handle a shebang, which may only be at the very start of a file.
+    /// `#![` is never a shebang, so that it can begin an inner attribute.
+    fn handle_shebang(&mut self) -> bool {
+        if self.start.lo() != 0
+            || !self.stream[..].starts_with("#!")
+            || self.stream[2..]
+                .trim_start_matches(is_whitespace_ch)
+                .starts_with('[')
+        {
+            return false;
+        }
+        while !self.is_at_end() && !self.is_at_newline() {
+            self.next();
+        }
+        true
+    }
+
+    pub(super) fn is_at_newline(&self) -> bool {
+        self.peek_eq(&'\n') == Some(true)
+            || (self.peek_eq(&'\r') == Some(true) && self.peek2_eq(&'\n') == Some(true))
+    }
+
+    /// Handle a line break. Line breaks are not folded into whitespace, as
+    /// they are significant to the grammar in some places.
+    fn handle_newline(&mut self) -> bool {
+        if !self.is_at_newline() {
+            return false;
         }
+        self.next_if_eq(&'\r');
+        self.next();
+        true
     }
 
-    pub(super) fn handle_comment(&mut self) -> bool {
+    /// Handle a line comment, returning whether it is a doc comment and
+    /// whether it is an inner doc comment.
+    pub(super) fn handle_comment(&mut self) -> Option<(bool, bool)> {
         // xx -> 11 -> 1
         // x/ -> 10 -> 1
         // /x -> 01 -> 1
         // // -> 00 -> 0
         if self.peek_eq(&'/') != Some(true) || self.peek2_eq(&'/') != Some(true) {
-            return false;
+            return None;
         }
+        self.next();
+        self.next();
+        // `///` is an outer doc comment (but `////` is not), and `//!` is an
+        // inner doc comment.
+        let (doc, inner) = if self.peek_eq(&'/') == Some(true) && self.peek2_eq(&'/') != Some(true)
+        {
+            (true, false)
+        } else if self.peek_eq(&'!') == Some(true) {
+            (true, true)
+        } else {
+            (false, false)
+        };
         // A comment goes until the end of the line,
         // so gorge all the characters until we get to the newline
         // (or the end, when it automatically stops gorging).
-        self.gorge_while(|spanned, _| spanned != &'\n');
-        true
+        while !self.is_at_end() && !self.is_at_newline() {
+            self.next();
+        }
+        Some((doc, inner))
     }
 
-    pub(super) fn handle_multiline_comment(&mut self) -> CalResult<bool> {
+    /// Handle a (possibly nested) block comment, returning whether it is a
+    /// doc comment, whether it is an inner doc comment, and how deeply nested
+    /// the comments within it are.
+    pub(super) fn handle_multiline_comment(&mut self) -> Option<(bool, bool, usize)> {
         // xx -> 11 -> 1
         // x* -> 10 -> 1
         // /x -> 01 -> 1
         // /* -> 00 -> 0
         if self.peek_eq(&'/') != Some(true) || self.peek2_eq(&'*') != Some(true) {
-            return Ok(false);
+            return None;
         }
         let start = self.start;
         self.current_to_start();
         self.next();
         self.next();
         let mut stack = vec![self.new_span()];
+        let mut nest_level = 0;
+
+        // `/**` is an outer doc comment (but `/***` and `/**/` are not), and
+        // `/*!` is an inner doc comment.
+        let (doc, inner) = if self.peek_eq(&'*') == Some(true)
+            && self.peek2_eq(&'*') != Some(true)
+            && self.peek2_eq(&'/') != Some(true)
+        {
+            (true, false)
+        } else if self.peek_eq(&'!') == Some(true) {
+            (true, true)
+        } else {
+            (false, false)
+        };
 
         loop {
             let span = self.peek();
@@ -67,6 +144,7 @@ impl<'lex> Lexer<'lex> {
                 self.next();
                 self.next();
                 stack.push(self.new_span());
+                nest_level = nest_level.max(stack.len() - 1);
             } else if ch == Some('*') && self.peek2_eq(&'/') == Some(true) {
                 self.current_to_start();
                 self.next();
@@ -78,9 +156,8 @@ impl<'lex> Lexer<'lex> {
 
             if self.is_at_end() && !stack.is_empty() {
                 // There's no way to tell whether stuff after a /* was intended to be a comment
-                // or code, so we make this a fatal error.
-
-                self.gcx.grcx.write().report_fatal(
+                // or code, so we treat the rest of the input as a comment.
+                self.gcx.grcx.write().report_syncd(
                     EnsembleBuilder::new()
                         .error(|b| {
                             b.code("E0002").short(err!(E0002)).label(
@@ -92,21 +169,12 @@ impl<'lex> Lexer<'lex> {
                         })
                         .build(),
                 );
-                return Err(DiagnosticError::Diagnostic.into());
-
-                // gen_error!(self.grcx.borrow(), Err(self => {
-                //     E0002;
-                //     labels: [
-                //         LabelStyle::Primary =>
-                //             (self.source_id, stack.pop().unwrap());
-                //             "this multi-line comment's beginning has no corresponding end"
-                //     ]
-                // }) as ())?
+                break;
             }
         }
 
         self.set_start(start);
-        Ok(true)
+        Some((doc, inner, nest_level))
     }
 
     pub(super) fn handle_dangling_comment_ends(&mut self) {
@@ -1,6 +1,9 @@
 #![doc(html_root_url = "https://calypso-lang.github.io/rustdoc/calypso_parsing/index.html")]
 #![warn(clippy::pedantic)]
 
+pub mod constant;
+pub mod cst;
 pub mod lexer;
+pub mod literal;
 pub mod parser;
 pub mod pretty;
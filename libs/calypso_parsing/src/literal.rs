@@ -0,0 +1,583 @@
+//! Decoding of string and character literals into their actual values.
+//!
+//! The lexer only checks that escape sequences are well-formed, and leaves
+//! them as-is within the lexeme. This module turns the contents of a literal
+//! into the value it represents, and keeps track of where each escape
+//! sequence came from so that later errors can point at the source.
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+use std::sync::Arc;
+
+use calypso_base::span::{Span, Spanned};
+use calypso_common::gcx::GlobalCtxt;
+use calypso_diagnostic::diagnostic::{EnsembleBuilder, LabelStyle};
+use calypso_diagnostic::prelude::*;
+
+use crate::lexer::helpers::is_whitespace_ch;
+use crate::lexer::{Token, TokenType};
+
+/// An escape sequence within a literal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Escape {
+    /// The span of the escape sequence in the source.
+    pub span: Span,
+    /// The byte index in the decoded value at which the escaped character
+    /// begins.
+    pub index: usize,
+    /// The escaped character, or `None` if this was a line continuation
+    /// (`\` followed by a newline). In byte literals, this is the character
+    /// with the same value as the escaped byte.
+    pub value: Option<char>,
+}
+
+/// An error found while decoding a literal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A Unicode escape representing a surrogate codepoint.
+    Surrogate(u32),
+    /// A Unicode escape representing a codepoint greater than `0x10FFFF`.
+    OutOfRange(u32),
+    /// A hex escape representing a non-ASCII character.
+    NonAsciiHex(u32),
+    /// A malformed escape sequence, or a Unicode escape or non-ASCII
+    /// character in a byte literal. These have already been reported by the
+    /// lexer.
+    Malformed,
+}
+
+/// The decoded value of a literal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decoded<T> {
+    /// The decoded value. Invalid escape sequences are replaced with
+    /// [`char::REPLACEMENT_CHARACTER`], or with `?` in byte literals.
+    pub value: T,
+    /// The escape sequences within the literal, in order.
+    pub escapes: Vec<Escape>,
+    /// The errors found while decoding the literal, with the span of the
+    /// escape sequence that caused them.
+    pub errors: Vec<Spanned<DecodeError>>,
+}
+
+impl<T> Decoded<T> {
+    /// Get the span of the escape sequence that produced the character at
+    /// the given byte index in the decoded value, if any.
+    #[must_use]
+    pub fn escape_at(&self, index: usize) -> Option<Span> {
+        self.escapes
+            .iter()
+            .find(|escape| escape.index == index && escape.value.is_some())
+            .map(|escape| escape.span)
+    }
+}
+
+/// Decode the contents of a string or character literal (without the
+/// delimiting quotes). `lo` is the byte index of the contents in the source.
+#[must_use]
+pub fn decode(contents: &str, lo: usize) -> Decoded<String> {
+    let mut decoded = Decoded {
+        value: String::with_capacity(contents.len()),
+        escapes: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut chars = contents.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        if ch != '\\' {
+            decoded.value.push(ch);
+            continue;
+        }
+
+        let (value, error) = decode_escape(&mut chars);
+        let end = chars.peek().map_or(contents.len(), |&(idx, _)| idx);
+        let span = Span::new(lo + start, lo + end);
+
+        decoded.escapes.push(Escape {
+            span,
+            index: decoded.value.len(),
+            value,
+        });
+        if let Some(error) = error {
+            decoded.errors.push(Spanned::new(span, error));
+        }
+        if let Some(value) = value {
+            decoded.value.push(value);
+        }
+    }
+
+    decoded
+}
+
+/// Decode the contents of a character literal (without the delimiting
+/// quotes). `lo` is the byte index of the contents in the source.
+#[must_use]
+pub fn decode_char(contents: &str, lo: usize) -> Decoded<char> {
+    let decoded = decode(contents, lo);
+    Decoded {
+        // The lexer checks that there is exactly one character, but an
+        // invalid one may have been reported already.
+        value: decoded
+            .value
+            .chars()
+            .next()
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+        escapes: decoded.escapes,
+        errors: decoded.errors,
+    }
+}
+
+/// Decode the contents of a byte string or byte literal (without the prefix
+/// and the delimiting quotes). `lo` is the byte index of the contents in the
+/// source.
+#[must_use]
+pub fn decode_bytes(contents: &str, lo: usize) -> Decoded<Vec<u8>> {
+    let mut decoded = Decoded {
+        value: Vec::with_capacity(contents.len()),
+        escapes: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut chars = contents.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        if ch != '\\' {
+            if ch.is_ascii() {
+                decoded.value.push(ch as u8);
+            } else {
+                let span = Span::new(lo + start, lo + start + ch.len_utf8());
+                decoded.value.push(b'?');
+                decoded
+                    .errors
+                    .push(Spanned::new(span, DecodeError::Malformed));
+            }
+            continue;
+        }
+
+        // Hex escapes can represent any byte, not just ASCII characters.
+        let (value, error) = match chars.next() {
+            Some((_, 'x')) => match gorge_hex_digits(&mut chars, 2) {
+                (2, value) => (std::char::from_u32(value), None),
+                _ => (Some('?'), Some(DecodeError::Malformed)),
+            },
+            Some((_, 'u')) => {
+                decode_unicode_escape(&mut chars);
+                (Some('?'), Some(DecodeError::Malformed))
+            }
+            Some((_, ch)) => match decode_escape_char(ch, &mut chars) {
+                (value, None) => (value, None),
+                (_, Some(error)) => (Some('?'), Some(error)),
+            },
+            None => (Some('?'), Some(DecodeError::Malformed)),
+        };
+        let end = chars.peek().map_or(contents.len(), |&(idx, _)| idx);
+        let span = Span::new(lo + start, lo + end);
+
+        decoded.escapes.push(Escape {
+            span,
+            index: decoded.value.len(),
+            value,
+        });
+        if let Some(error) = error {
+            decoded.errors.push(Spanned::new(span, error));
+        }
+        if let Some(value) = value {
+            // All of the values are at most `0xFF`.
+            decoded.value.push(value as u8);
+        }
+    }
+
+    decoded
+}
+
+/// The value of the contents of a byte string literal (without the prefix
+/// and the delimiting quotes) that has already been checked by the lexer.
+#[must_use]
+pub fn bytes_value(contents: &str) -> Vec<u8> {
+    // The spans of escapes are only needed to report errors.
+    decode_bytes(contents, 0).value
+}
+
+/// Decode the contents of a multi-line string literal (including the
+/// delimiting `"""`s). `lo` is the byte index of the literal in the source.
+///
+/// The contents start on the line after the opening `"""`, and end at the
+/// line break before the closing `"""`. The indentation of the closing
+/// `"""` is stripped from each line, and line breaks are normalized to
+/// `\n`.
+#[must_use]
+pub fn decode_multiline(literal: &str, lo: usize) -> Decoded<String> {
+    let mut decoded = Decoded {
+        value: String::with_capacity(literal.len()),
+        escapes: Vec::new(),
+        errors: Vec::new(),
+    };
+    let contents = &literal[3..literal.len() - 3];
+    let (start, end) = match (contents.find('\n'), contents.rfind('\n')) {
+        (Some(start), Some(end)) if start < end => (start + 1, end + 1),
+        // Either there are no lines between the opening and closing `"""`,
+        // or the lexer has already reported that they aren't on lines of
+        // their own.
+        _ => return decoded,
+    };
+    let indent = &contents[end..];
+    let indent = if indent.chars().all(is_whitespace_ch) {
+        indent
+    } else {
+        ""
+    };
+
+    let mut line_lo = start;
+    let mut lines = contents[start..end].split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        let body = line.trim_end_matches(&['\n', '\r'][..]);
+        // Lines with less indentation have already been reported by the
+        // lexer, unless they are blank.
+        let stripped = body
+            .strip_prefix(indent)
+            .unwrap_or_else(|| body.trim_start_matches(is_whitespace_ch));
+        let segment = &line[body.len() - stripped.len()..];
+        let segment_lo = lo + 3 + line_lo + body.len() - stripped.len();
+        let mut line_decoded = decode(segment, segment_lo);
+
+        // Unless the line break was escaped by a line continuation, replace
+        // it with `\n`, or remove it if this is the last line.
+        let continued = matches!(
+            line_decoded.escapes.last(),
+            Some(escape) if escape.value.is_none() && escape.span.hi() == segment_lo + segment.len()
+        );
+        if !continued {
+            let len = line_decoded.value.len() - (line.len() - body.len());
+            line_decoded.value.truncate(len);
+            if lines.peek().is_some() {
+                line_decoded.value.push('\n');
+            }
+        }
+
+        let index = decoded.value.len();
+        decoded.value.push_str(&line_decoded.value);
+        decoded
+            .escapes
+            .extend(line_decoded.escapes.into_iter().map(|escape| Escape {
+                index: index + escape.index,
+                ..escape
+            }));
+        decoded.errors.extend(line_decoded.errors);
+        line_lo += line.len();
+    }
+
+    decoded
+}
+
+/// Decode a single escape sequence, after its `\`.
+fn decode_escape(chars: &mut Peekable<CharIndices>) -> (Option<char>, Option<DecodeError>) {
+    match chars.next() {
+        Some((_, ch)) => decode_escape_char(ch, chars),
+        None => (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::Malformed),
+        ),
+    }
+}
+
+fn decode_escape_char(
+    ch: char,
+    chars: &mut Peekable<CharIndices>,
+) -> (Option<char>, Option<DecodeError>) {
+    let value = match ch {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        '\\' | '\'' | '"' | '$' => ch,
+        '\n' | '\r' => {
+            // Line continuation: skip the newline and any leading whitespace
+            // on the next line.
+            while chars.next_if(|&(_, ch)| is_whitespace_ch(ch)).is_some() {}
+            return (None, None);
+        }
+        'x' => return decode_hex_escape(chars),
+        'u' => return decode_unicode_escape(chars),
+        _ => {
+            return (
+                Some(char::REPLACEMENT_CHARACTER),
+                Some(DecodeError::Malformed),
+            )
+        }
+    };
+    (Some(value), None)
+}
+
+fn decode_hex_escape(chars: &mut Peekable<CharIndices>) -> (Option<char>, Option<DecodeError>) {
+    let (n_digits, value) = gorge_hex_digits(chars, 2);
+    if n_digits != 2 {
+        (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::Malformed),
+        )
+    } else if value > 0x7F {
+        (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::NonAsciiHex(value)),
+        )
+    } else {
+        (std::char::from_u32(value), None)
+    }
+}
+
+fn decode_unicode_escape(chars: &mut Peekable<CharIndices>) -> (Option<char>, Option<DecodeError>) {
+    if chars.next_if(|&(_, ch)| ch == '{').is_none() {
+        return (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::Malformed),
+        );
+    }
+    let (n_digits, value) = gorge_hex_digits(chars, 6);
+    let closed = chars.next_if(|&(_, ch)| ch == '}').is_some();
+    if n_digits == 0 || !closed {
+        return (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::Malformed),
+        );
+    }
+
+    match std::char::from_u32(value) {
+        Some(ch) => (Some(ch), None),
+        None if (0xD800..=0xDFFF).contains(&value) => (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::Surrogate(value)),
+        ),
+        None => (
+            Some(char::REPLACEMENT_CHARACTER),
+            Some(DecodeError::OutOfRange(value)),
+        ),
+    }
+}
+
+/// Consume up to `max` hex digits, returning the number of digits consumed
+/// and their value.
+fn gorge_hex_digits(chars: &mut Peekable<CharIndices>, max: usize) -> (usize, u32) {
+    let mut value = 0;
+    let mut count = 0;
+    while count < max {
+        match chars.next_if(|&(_, ch)| ch.is_ascii_hexdigit()) {
+            Some((_, ch)) => value = value * 16 + ch.to_digit(16).unwrap(),
+            None => break,
+        }
+        count += 1;
+    }
+    (count, value)
+}
+
+/// Decodes literal tokens and reports any errors found while decoding.
+pub struct LiteralDecoder {
+    file_id: usize,
+    gcx: Arc<GlobalCtxt>,
+}
+
+impl LiteralDecoder {
+    #[must_use]
+    pub fn new(file_id: usize, gcx: Arc<GlobalCtxt>) -> Self {
+        Self { file_id, gcx }
+    }
+
+    /// Decode a string literal token ([`TokenType::String`],
+    /// [`TokenType::RawString`], [`TokenType::MultilineString`] or
+    /// [`TokenType::StringFragment`]). The lexer already keeps the value of
+    /// these tokens, so this is only needed for the spans of their escape
+    /// sequences. Any errors are reported again.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the token is not a string literal token.
+    #[must_use]
+    pub fn decode_string(&self, token: &Token<'_>) -> Decoded<String> {
+        let (ty, lexeme) = *token.value();
+        let span = token.span();
+        let decoded = match ty {
+            TokenType::String(_) => decode(&lexeme[1..lexeme.len() - 1], span.lo() + 1),
+            TokenType::RawString { hashes } => Decoded {
+                value: lexeme[hashes + 2..lexeme.len() - hashes - 1].to_string(),
+                escapes: Vec::new(),
+                errors: Vec::new(),
+            },
+            TokenType::MultilineString(_) => decode_multiline(lexeme, span.lo()),
+            TokenType::StringFragment(_) => decode(lexeme, span.lo()),
+            _ => panic!("expected a string literal token, found {:?}", ty),
+        };
+        self.report(&decoded);
+        decoded
+    }
+
+    /// Decode a character literal token ([`TokenType::Char`]).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the token is not a character literal
+    /// token.
+    #[must_use]
+    pub fn decode_char(&self, token: &Token<'_>) -> Decoded<char> {
+        let (ty, lexeme) = *token.value();
+        assert!(
+            matches!(ty, TokenType::Char(_)),
+            "expected a character literal token, found {:?}",
+            ty
+        );
+        let decoded = decode_char(&lexeme[1..lexeme.len() - 1], token.span().lo() + 1);
+        self.report(&decoded);
+        decoded
+    }
+
+    /// Decode a byte string or byte literal token ([`TokenType::ByteString`]
+    /// or [`TokenType::Byte`]).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the token is not a byte string or byte
+    /// literal token.
+    #[must_use]
+    pub fn decode_bytes(&self, token: &Token<'_>) -> Decoded<Vec<u8>> {
+        let (ty, lexeme) = *token.value();
+        assert!(
+            matches!(ty, TokenType::ByteString | TokenType::Byte(_)),
+            "expected a byte string or byte literal token, found {:?}",
+            ty
+        );
+        let decoded = decode_bytes(&lexeme[2..lexeme.len() - 1], token.span().lo() + 2);
+        self.report(&decoded);
+        decoded
+    }
+
+    /// Report the errors found while decoding a literal. Malformed escape
+    /// sequences are not reported, as the lexer has already reported them.
+    pub fn report<T>(&self, decoded: &Decoded<T>) {
+        for error in &decoded.errors {
+            let span = error.span();
+            let diag = match error.value_owned() {
+                DecodeError::Surrogate(value) => EnsembleBuilder::new().error(|b| {
+                    b.code("E0039")
+                        .short(err!(E0039, cp = format!("U+{:04X}", value)))
+                        .label(LabelStyle::Primary, None, self.file_id, span)
+                        .note("surrogates are only used in UTF-16 and are not valid characters.")
+                }),
+                DecodeError::OutOfRange(value) => EnsembleBuilder::new().error(|b| {
+                    b.code("E0040")
+                        .short(err!(E0040, cp = format!("U+{:04X}", value)))
+                        .label(LabelStyle::Primary, None, self.file_id, span)
+                }),
+                DecodeError::NonAsciiHex(value) => EnsembleBuilder::new().error(|b| {
+                    b.code("E0041")
+                        .short(err!(E0041, val = format!("{:02X}", value)))
+                        .label(LabelStyle::Primary, None, self.file_id, span)
+                        .note(format!(
+                            "help: if you meant the character U+{0:04X}, use a Unicode escape: `\\u{{{0:X}}}`",
+                            value
+                        ))
+                }),
+                DecodeError::Malformed => continue,
+            };
+            self.gcx.grcx.write().report_syncd(diag.build());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_escapes() {
+        let decoded = decode(r#"a\n\t\\\"\$\0"#, 10);
+        assert_eq!(decoded.value, "a\n\t\\\"$\0");
+        assert!(decoded.errors.is_empty());
+        assert_eq!(
+            decoded
+                .escapes
+                .iter()
+                .map(|escape| (escape.span, escape.index))
+                .collect::<Vec<_>>(),
+            vec![
+                (Span::new(11, 13), 1),
+                (Span::new(13, 15), 2),
+                (Span::new(15, 17), 3),
+                (Span::new(17, 19), 4),
+                (Span::new(19, 21), 5),
+                (Span::new(21, 23), 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_and_unicode_escapes() {
+        let decoded = decode(r"\x41\u{1F600}\u{e9}!", 0);
+        assert_eq!(decoded.value, "A\u{1F600}\u{e9}!");
+        assert!(decoded.errors.is_empty());
+        assert_eq!(decoded.escape_at(0), Some(Span::new(0, 4)));
+        assert_eq!(decoded.escape_at(1), Some(Span::new(4, 13)));
+        assert_eq!(decoded.escape_at(5), Some(Span::new(13, 19)));
+        assert_eq!(decoded.escape_at(7), None);
+    }
+
+    #[test]
+    fn invalid_scalar_values() {
+        let decoded = decode(r"\u{D800}\u{110000}\x80", 1);
+        assert_eq!(decoded.value, "\u{FFFD}\u{FFFD}\u{FFFD}");
+        assert_eq!(
+            decoded.errors,
+            vec![
+                Spanned::new(Span::new(1, 9), DecodeError::Surrogate(0xD800)),
+                Spanned::new(Span::new(9, 19), DecodeError::OutOfRange(0x0011_0000)),
+                Spanned::new(Span::new(19, 23), DecodeError::NonAsciiHex(0x80)),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_continuation() {
+        let decoded = decode("a\\\n    b", 0);
+        assert_eq!(decoded.value, "ab");
+        assert_eq!(decoded.escapes.len(), 1);
+        assert_eq!(decoded.escapes[0].value, None);
+        assert_eq!(decoded.escape_at(1), None);
+    }
+
+    #[test]
+    fn malformed_escapes() {
+        let decoded = decode(r"\q\x4\u{}", 0);
+        assert_eq!(decoded.value, "\u{FFFD}\u{FFFD}\u{FFFD}");
+        assert!(decoded
+            .errors
+            .iter()
+            .all(|error| error.value_owned() == DecodeError::Malformed));
+    }
+
+    #[test]
+    fn byte_strings() {
+        let decoded = decode_bytes(r"\x7FELF\x00\xff\n", 2);
+        assert_eq!(decoded.value, b"\x7FELF\x00\xff\n");
+        assert!(decoded.errors.is_empty());
+        assert_eq!(decoded.escape_at(5), Some(Span::new(13, 17)));
+
+        let decoded = decode_bytes(r"\u{41}é", 0);
+        assert_eq!(decoded.value, b"??");
+        assert_eq!(
+            decoded.errors,
+            vec![
+                Spanned::new(Span::new(0, 6), DecodeError::Malformed),
+                Spanned::new(Span::new(6, 8), DecodeError::Malformed),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let literal = "\"\"\"\n    a\n\n      b\\t\r\n    c \\\n    d\n    \"\"\"";
+        let decoded = decode_multiline(literal, 10);
+        assert_eq!(decoded.value, "a\n\n  b\t\nc d");
+        assert!(decoded.errors.is_empty());
+        // The `\t` escape, after the stripped indentation.
+        assert_eq!(decoded.escape_at(6), Some(Span::new(28, 30)));
+
+        assert_eq!(decode_multiline("\"\"\"\n\"\"\"", 0).value, "");
+        assert_eq!(decode_multiline("\"\"\"\n\n\"\"\"", 0).value, "");
+        assert_eq!(decode_multiline("\"\"\"\n\\n\n\"\"\"", 0).value, "\n");
+    }
+}
@@ -1,6 +1,78 @@
-// todo(@ThePuzzlemaker: parse|frame):
-//   add span information to AST and parser
+use std::sync::Arc;
+
+use calypso_ast::{expr::Expr, stmt::Block};
+use calypso_common::gcx::GlobalCtxt;
+use calypso_diagnostic::prelude::*;
+
+use crate::constant::ConstEvaluator;
+use crate::lexer::Lexer;
+use error::{ErrorRecovery, ParseError};
+use tokens::Tok;
+
+pub mod error;
 pub mod expr;
 #[rustfmt::skip]
 pub mod grammar;
+pub mod pratt;
 pub mod tokens;
+
+#[cfg(test)]
+mod tests;
+
+/// Lex and parse a whole file, ignoring whitespace and comments.
+///
+/// Syntax errors are handled the same as in [`parse_expr`].
+///
+/// # Errors
+///
+/// See [`parse_expr`].
+pub fn parse_program<'tok>(
+    file_id: usize,
+    source: &'tok str,
+    gcx: &Arc<GlobalCtxt>,
+) -> CalResult<Block<'tok>> {
+    parse_with(file_id, source, gcx, |errors, tokens| {
+        grammar::ProgramParser::new().parse(file_id, errors, tokens.iter().copied().map(Ok))
+    })
+}
+
+/// Lex and parse an expression, ignoring whitespace and comments.
+///
+/// Syntax errors are reported as synchronizable errors in the global
+/// reporting context, and the parts of the expression that could not be
+/// parsed are replaced with [`ExprKind::Error`](calypso_ast::expr::ExprKind::Error) nodes.
+///
+/// # Errors
+///
+/// This function errors if the parser could not recover from a syntax error.
+/// Such syntax errors are reported as fatal errors in the global reporting
+/// context (see [`error::report_parse_error`]).
+pub fn parse_expr<'tok>(
+    file_id: usize,
+    source: &'tok str,
+    gcx: &Arc<GlobalCtxt>,
+) -> CalResult<Box<Expr<'tok>>> {
+    parse_with(file_id, source, gcx, |errors, tokens| {
+        grammar::ExprParser::new().parse(file_id, errors, tokens.iter().copied().map(Ok))
+    })
+}
+
+/// Lex a file and run one of the LALRPOP parsers on its tokens, reporting
+/// any syntax errors and any number literals that are out of range.
+fn parse_with<'tok, T>(
+    file_id: usize,
+    source: &'tok str,
+    gcx: &Arc<GlobalCtxt>,
+    parse: impl FnOnce(
+        &mut Vec<ErrorRecovery<'tok>>,
+        &[(usize, Tok<'tok>, usize)],
+    ) -> Result<T, ParseError<'tok>>,
+) -> CalResult<T> {
+    let lexer = Lexer::new(file_id, source, Arc::clone(gcx));
+    let tokens = tokens::process_iter(lexer.into_iter(), true).collect::<Vec<_>>();
+    ConstEvaluator::new(file_id, Arc::clone(gcx)).check_values(&tokens);
+    let mut errors = Vec::new();
+    let result = parse(&mut errors, &tokens);
+    error::report_recovered_errors(errors, file_id, source, &tokens, gcx);
+    result.map_err(|err| error::report_parse_error(err, file_id, source, &tokens, gcx))
+}
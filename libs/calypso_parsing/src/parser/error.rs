@@ -0,0 +1,598 @@
+//! Conversion of LALRPOP's parse errors into diagnostics.
+
+use calypso_base::span::Span;
+use calypso_base::symbol::kw::Keyword;
+use calypso_common::gcx::GlobalCtxt;
+use calypso_diagnostic::diagnostic::{EnsembleBuilder, EnsembleDiagnostic, LabelStyle};
+use calypso_diagnostic::prelude::*;
+
+use super::tokens::Tok;
+use crate::lexer::TokenType;
+
+/// An error produced by the parser.
+pub type ParseError<'tok> = lalrpop_util::ParseError<usize, Tok<'tok>, CalError>;
+/// A syntax error that the parser recovered from.
+pub type ErrorRecovery<'tok> = lalrpop_util::ErrorRecovery<usize, Tok<'tok>, CalError>;
+
+/// The terminals of the grammar that begin an expression.
+const EXPR_START: &[&str] = &[
+    "(",
+    "-",
+    "!",
+    "NumberLit",
+    "Atom",
+    "Ident",
+    "StringLit",
+    "CharLit",
+    "BytesLit",
+    "ByteLit",
+    "StringStart",
+    "true",
+    "false",
+    "do",
+    "case",
+    "if",
+    "while",
+    "for",
+    "loop",
+    "break",
+    "continue",
+    "ret",
+];
+
+/// The terminals of the grammar that are binary operators.
+const BINOPS: &[&str] = &[
+    "+", "-", "*", "/", "%", "**", "||", "&&", "|", "^", "&", "<<", ">>", "==", "!=", "<", ">",
+    "<=", ">=",
+];
+
+/// The terminals of the grammar that are assignment operators.
+const ASSIGN_OPS: &[&str] = &[
+    "=", "+=", "-=", "*=", "/=", "%=", "**=", "|=", "^=", "&=", "<<=", ">>=",
+];
+
+/// Report a parse error as a fatal error in the global reporting context.
+///
+/// `tokens` are the tokens that were given to the parser, which are used to
+/// find any unclosed delimiters. Errors from the lexer are returned as-is, as
+/// they have already been reported. Any other error is converted into a
+/// diagnostic, and a [`DiagnosticError::Diagnostic`] is returned.
+pub fn report_parse_error(
+    err: ParseError<'_>,
+    file_id: usize,
+    source: &str,
+    tokens: &[(usize, Tok<'_>, usize)],
+    gcx: &GlobalCtxt,
+) -> CalError {
+    match diagnostic(err, file_id, source, tokens) {
+        Ok(diag) => {
+            gcx.grcx.write().report_fatal(diag);
+            DiagnosticError::Diagnostic.into()
+        }
+        Err(err) => err,
+    }
+}
+
+/// Report the syntax errors that the parser recovered from as synchronizable
+/// errors in the global reporting context.
+///
+/// See [`report_parse_error`] for the meaning of `tokens`.
+pub fn report_recovered_errors(
+    errors: Vec<ErrorRecovery<'_>>,
+    file_id: usize,
+    source: &str,
+    tokens: &[(usize, Tok<'_>, usize)],
+    gcx: &GlobalCtxt,
+) {
+    for recovery in errors {
+        // The parser only recovers from its own errors, not those of the
+        // lexer, so there's always a diagnostic.
+        if let Ok(diag) = diagnostic(recovery.error, file_id, source, tokens) {
+            gcx.grcx.write().report_syncd(diag);
+        }
+    }
+}
+
+/// Convert a parse error into a diagnostic, or return the error if it came
+/// from the lexer.
+fn diagnostic(
+    err: ParseError<'_>,
+    file_id: usize,
+    source: &str,
+    tokens: &[(usize, Tok<'_>, usize)],
+) -> Result<EnsembleDiagnostic, CalError> {
+    let location = match &err {
+        ParseError::User { .. } => 0,
+        ParseError::InvalidToken { location } | ParseError::UnrecognizedEOF { location, .. } => {
+            *location
+        }
+        ParseError::UnrecognizedToken { token, .. } | ParseError::ExtraToken { token } => token.0,
+    };
+    let cx = context(tokens, location);
+
+    let diag = match err {
+        ParseError::User { error } => return Err(error),
+        ParseError::InvalidToken { location } => EnsembleBuilder::new().error(|b| {
+            b.code("E0060").short(err!(E0060)).label(
+                LabelStyle::Primary,
+                None,
+                file_id,
+                Span::new(location, location),
+            )
+        }),
+        ParseError::UnrecognizedEOF { location, expected } => {
+            let expected = terminals(&expected, cx, true);
+            EnsembleBuilder::new().error(|b| {
+                let mut b = b
+                    .code("E0058")
+                    .short(err!(E0058, expected = describe_terminals(&expected)))
+                    .label(
+                        LabelStyle::Primary,
+                        None,
+                        file_id,
+                        Span::new(location, location),
+                    );
+                if let Some((span, label, help)) = unclosed(cx.open) {
+                    b = b
+                        .label(LabelStyle::Secondary, Some(label), file_id, span)
+                        .note(help);
+                }
+                b
+            })
+        }
+        // The parser only reports the tokens it expected when there are any,
+        // so an error without them is the same as an extra token.
+        ParseError::UnrecognizedToken { token, expected } if !expected.is_empty() => {
+            let (lo, tok, hi) = token;
+            let expected = terminals(&expected, cx, false);
+            let found = describe_tok(tok, &source[lo..hi]);
+            EnsembleBuilder::new().error(|b| {
+                let mut b = b
+                    .code("E0057")
+                    .short(err!(
+                        E0057,
+                        expected = describe_terminals(&expected),
+                        found = found
+                    ))
+                    .label(LabelStyle::Primary, None, file_id, Span::new(lo, hi));
+                if tok == Tok::Unprocessed(TokenType::RParen) && !is_paren_open(cx.open) {
+                    b = b.note("help: this `)` does not have a matching `(`");
+                } else if tok == Tok::Unprocessed(TokenType::Eq) && expected.contains(&"==") {
+                    b = b.note("help: use `==` to check if two values are equal");
+                } else if starts_expr(tok) && expected.contains(&"+") {
+                    b = b.note("help: there may be an operator missing before this");
+                }
+                b
+            })
+        }
+        ParseError::UnrecognizedToken { token, .. } | ParseError::ExtraToken { token } => {
+            let (lo, tok, hi) = token;
+            let found = describe_tok(tok, &source[lo..hi]);
+            EnsembleBuilder::new().error(|b| {
+                let mut b = b.code("E0059").short(err!(E0059, found = found)).label(
+                    LabelStyle::Primary,
+                    None,
+                    file_id,
+                    Span::new(lo, hi),
+                );
+                if tok == Tok::Unprocessed(TokenType::RParen) && !is_paren_open(cx.open) {
+                    b = b.note("help: this `)` does not have a matching `(`");
+                } else if starts_expr(tok) {
+                    b = b.note("help: there may be an operator missing before this");
+                }
+                b
+            })
+        }
+    };
+    Ok(diag.build())
+}
+
+/// A construct that is still open at the location of a syntax error, and
+/// which determines the terminals that could actually follow.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Delim {
+    /// `(`
+    Paren,
+    /// The `(` of a comma-separated list, e.g. the arguments of a call.
+    Args,
+    /// `${`
+    Interp,
+    /// A `do` block.
+    Do,
+    /// The scrutinees of a `case` expression, before its `do`.
+    CaseHead,
+    /// The patterns of an arm of a `case` expression.
+    CaseArms,
+    /// The guard of an arm of a `case` expression, after its `where`.
+    CaseGuard,
+    /// The body of an arm of a `case` expression, after its `->`.
+    CaseBody,
+    /// The header of a function declaration, before its `->`.
+    FnHeader,
+    /// The condition of an `if` expression, before its `->` or `do`.
+    IfHead,
+    /// The `then` branch of a one-line `if` expression. It ends along with
+    /// the statement (or delimiter) it's in.
+    IfBody,
+    /// The `then` block of an `if` expression, before its `else` or `end`.
+    IfBlock,
+    /// The `then` block after the `->` of an `if` expression, before its
+    /// `else` or `end`.
+    IfArrowBlock,
+    /// The `else` of a one-line `if` expression, before its `->`, `do` or
+    /// `if`.
+    Else,
+    /// The `else` of an `if` expression with blocks after `->`, before its
+    /// `->` or `if`. An `if` after it is part of the same expression, and
+    /// shares its `end`.
+    ElseArrow,
+    /// The `else` block of an `if` expression, before its `end`.
+    ElseBlock,
+    /// The header of a loop (after the `in` of a `for` loop), before its
+    /// `->` or `do`.
+    LoopHead,
+    /// The block after the `->` of a loop, before its `end`.
+    LoopBlock,
+    /// The pattern of a `for` loop, before its `in`.
+    ForHead,
+}
+
+/// The constructs that are still open at the location of a syntax error.
+#[derive(Copy, Clone, Debug)]
+struct Context<'tok> {
+    /// The innermost delimiter (e.g. a parenthesis or `do` block), other than
+    /// the `then` branch of a one-line `if`, and the span of the token that
+    /// opened it.
+    open: Option<(Span, Delim)>,
+    /// Whether the error is directly in the `then` branch of a one-line `if`,
+    /// so an `else` could follow.
+    then_branch: bool,
+    /// The token before the error, if any.
+    prev: Option<Tok<'tok>>,
+}
+
+/// Find the constructs that are still open before `location`.
+fn context<'tok>(tokens: &[(usize, Tok<'tok>, usize)], location: usize) -> Context<'tok> {
+    let mut stack = Vec::new();
+    let mut prev = None;
+    let mut tokens = tokens
+        .iter()
+        .take_while(|(lo, _, _)| *lo < location)
+        .peekable();
+    while let Some(&(lo, tok, hi)) = tokens.next() {
+        let after_arrow = prev == Some(Tok::Unprocessed(TokenType::Arrow));
+        // A `(` after an operand or `fn` (in a function type) begins a list.
+        let begins_list = matches!(prev, Some(tok) if ends_operand(tok))
+            || prev == Some(Tok::Keyword(Keyword::Fn));
+        prev = Some(tok);
+        let span = Span::new(lo, hi);
+        // A `->` at the end of a line begins a block.
+        let ends_line = matches!(
+            tokens.peek(),
+            Some((_, Tok::Unprocessed(TokenType::Newline), _))
+        );
+        if matches!(
+            tok,
+            Tok::Unprocessed(TokenType::Newline)
+                | Tok::Unprocessed(TokenType::Comma)
+                | Tok::Unprocessed(TokenType::RParen)
+                | Tok::Unprocessed(TokenType::InterpEnd)
+                | Tok::Keyword(Keyword::End)
+        ) {
+            while let Some((_, Delim::IfBody)) = stack.last() {
+                stack.pop();
+            }
+        }
+
+        let top = stack.last().map(|&(_, delim)| delim);
+        if let Some(next) = top.and_then(|delim| next_part(tok, delim, ends_line, after_arrow)) {
+            stack.last_mut().unwrap().1 = next;
+            continue;
+        }
+        match (tok, top) {
+            // Parts that begin at a token of their own (e.g. the `do` block
+            // of a loop) replace the part before them.
+            (Tok::Keyword(Keyword::Else), Some(Delim::IfBody)) => {
+                *stack.last_mut().unwrap() = (span, Delim::Else);
+            }
+            (Tok::Keyword(Keyword::If), Some(Delim::Else))
+            | (Tok::Keyword(Keyword::If), Some(Delim::ElseArrow)) => {
+                *stack.last_mut().unwrap() = (span, Delim::IfHead);
+            }
+            (Tok::Keyword(Keyword::Do), Some(Delim::LoopHead))
+            | (Tok::Keyword(Keyword::Do), Some(Delim::Else)) => {
+                *stack.last_mut().unwrap() = (span, Delim::Do);
+            }
+            (Tok::Unprocessed(TokenType::Arrow), Some(Delim::FnHeader))
+            | (Tok::Unprocessed(TokenType::Arrow), Some(Delim::LoopHead))
+            | (Tok::Unprocessed(TokenType::Arrow), Some(Delim::Else))
+            | (Tok::Unprocessed(TokenType::RParen), _)
+            | (Tok::Unprocessed(TokenType::InterpEnd), _)
+            | (Tok::Keyword(Keyword::End), _) => {
+                stack.pop();
+            }
+            (Tok::Unprocessed(TokenType::LParen), _) if begins_list => {
+                stack.push((span, Delim::Args));
+            }
+            (Tok::Unprocessed(TokenType::LParen), _) => stack.push((span, Delim::Paren)),
+            (Tok::Unprocessed(TokenType::InterpStart), _) => stack.push((span, Delim::Interp)),
+            (Tok::Keyword(Keyword::Do), _) => stack.push((span, Delim::Do)),
+            (Tok::Keyword(Keyword::Case), _) => stack.push((span, Delim::CaseHead)),
+            (Tok::Keyword(Keyword::If), _) => stack.push((span, Delim::IfHead)),
+            (Tok::Keyword(Keyword::While), _) | (Tok::Keyword(Keyword::Loop), _) => {
+                stack.push((span, Delim::LoopHead));
+            }
+            (Tok::Keyword(Keyword::For), _) => stack.push((span, Delim::ForHead)),
+            // Function types (e.g. `fn(T): U`) don't have a `->`.
+            (Tok::Keyword(Keyword::Fn), _)
+                if matches!(tokens.peek(), Some((_, Tok::Ident(_), _))) =>
+            {
+                stack.push((span, Delim::FnHeader));
+            }
+            _ => {}
+        }
+    }
+
+    Context {
+        open: stack
+            .iter()
+            .rev()
+            .find(|(_, delim)| *delim != Delim::IfBody)
+            .copied(),
+        then_branch: matches!(stack.last(), Some((_, Delim::IfBody))),
+        prev,
+    }
+}
+
+/// The part of a construct that follows `tok` within another part, `delim`
+/// (e.g. the arms of a `case` expression after its `do`), if `tok` begins
+/// one. `ends_line` is whether `tok` is followed by a newline, and
+/// `after_arrow` whether it follows a `->`.
+fn next_part(tok: Tok, delim: Delim, ends_line: bool, after_arrow: bool) -> Option<Delim> {
+    let next = match (tok, delim) {
+        (Tok::Keyword(Keyword::Where), Delim::CaseArms) => Delim::CaseGuard,
+        (Tok::Unprocessed(TokenType::Arrow), Delim::CaseArms)
+        | (Tok::Unprocessed(TokenType::Arrow), Delim::CaseGuard) => Delim::CaseBody,
+        // The body of an arm may start on the line after its `->`.
+        (Tok::Unprocessed(TokenType::Newline), Delim::CaseBody) if after_arrow => Delim::CaseBody,
+        (Tok::Keyword(Keyword::Do), Delim::CaseHead)
+        | (Tok::Unprocessed(TokenType::Comma), Delim::CaseBody)
+        | (Tok::Unprocessed(TokenType::Newline), Delim::CaseBody) => Delim::CaseArms,
+        (Tok::Keyword(Keyword::Do), Delim::IfHead) => Delim::IfBlock,
+        (Tok::Unprocessed(TokenType::Arrow), Delim::IfHead) if ends_line => Delim::IfArrowBlock,
+        (Tok::Unprocessed(TokenType::Arrow), Delim::IfHead) => Delim::IfBody,
+        (Tok::Keyword(Keyword::Else), Delim::IfBlock)
+        | (Tok::Unprocessed(TokenType::Arrow), Delim::ElseArrow) => Delim::ElseBlock,
+        (Tok::Keyword(Keyword::Else), Delim::IfArrowBlock) => Delim::ElseArrow,
+        (Tok::Keyword(Keyword::In), Delim::ForHead) => Delim::LoopHead,
+        (Tok::Unprocessed(TokenType::Arrow), Delim::LoopHead)
+        | (Tok::Unprocessed(TokenType::Arrow), Delim::Else)
+            if ends_line =>
+        {
+            Delim::LoopBlock
+        }
+        _ => return None,
+    };
+    Some(next)
+}
+
+/// The label and help note for a construct that is still open at the end
+/// of the input, along with its span.
+fn unclosed(open: Option<(Span, Delim)>) -> Option<(Span, &'static str, &'static str)> {
+    let (span, delim) = open?;
+    let (label, help) = match delim {
+        Delim::Paren | Delim::Args => ("unclosed `(`", "help: add a `)` to close the unclosed `(`"),
+        Delim::Do => (
+            "unclosed `do`",
+            "help: add an `end` to close the `do` block",
+        ),
+        Delim::IfBlock | Delim::IfArrowBlock | Delim::ElseArrow | Delim::ElseBlock => (
+            "unclosed `if`",
+            "help: add an `end` to close the `if` expression",
+        ),
+        Delim::LoopBlock => ("unclosed loop", "help: add an `end` to close the loop"),
+        Delim::CaseArms | Delim::CaseGuard | Delim::CaseBody => (
+            "unclosed `case`",
+            "help: add an `end` to close the `case` expression",
+        ),
+        _ => return None,
+    };
+    Some((span, label, help))
+}
+
+/// Check if the innermost open construct is a parenthesis.
+fn is_paren_open(open: Option<(Span, Delim)>) -> bool {
+    matches!(open, Some((_, Delim::Paren)) | Some((_, Delim::Args)))
+}
+
+/// Check if `tok` can end an operand, so that a `(` after it begins the
+/// arguments of a call.
+fn ends_operand(tok: Tok) -> bool {
+    match tok {
+        Tok::Number(..)
+        | Tok::Ident(_)
+        | Tok::String(_)
+        | Tok::Char(_)
+        | Tok::Bytes(_)
+        | Tok::Byte(_)
+        | Tok::Atom(_) => true,
+        Tok::Keyword(kw) => matches!(kw, Keyword::True | Keyword::False | Keyword::End),
+        Tok::Unprocessed(ty) => matches!(ty, TokenType::RParen | TokenType::StringEnd),
+        Tok::StringFragment(_) => false,
+    }
+}
+
+/// Get the names of LALRPOP's expected terminals, without the quotes around
+/// them.
+///
+/// The parser's lookahead is not exact, so terminals are only kept if they
+/// could follow within the innermost open construct, e.g. closing
+/// delimiters only if they would close it, and newlines only if they could
+/// end a statement. LALRPOP doesn't include the end of the input, so it's
+/// added (as `EOF`) wherever a statement could end it.
+// todo(parse): compute the terminals that can be shifted in the parser's
+//   state instead, so that this doesn't have to follow every change to the
+//   grammar.
+fn terminals<'e>(expected: &'e [String], cx: Context<'_>, at_eof: bool) -> Vec<&'e str> {
+    let open = cx.open.map(|(_, delim)| delim);
+    let expected = expected
+        .iter()
+        .map(|terminal| terminal.trim_start_matches('"').trim_end_matches('"'))
+        .collect::<Vec<_>>();
+    let any_expr = expected.contains(&"NumberLit");
+    // A single operator (other than `|`, which also separates the
+    // alternatives of a pattern) is only left when the parser has already
+    // reduced the operand before it, but any operator could have followed
+    // it.
+    let any_binop = expected.iter().filter(|t| BINOPS.contains(t)).count() > 1;
+    // Whether a statement could end here.
+    let in_block = matches!(
+        open,
+        None | Some(Delim::Do)
+            | Some(Delim::IfBlock)
+            | Some(Delim::IfArrowBlock)
+            | Some(Delim::ElseBlock)
+            | Some(Delim::LoopBlock)
+    );
+    let mut terminals = expected
+        .into_iter()
+        .filter(|terminal| match *terminal {
+            ")" => matches!(open, Some(Delim::Paren) | Some(Delim::Args)),
+            "InterpEnd" => open == Some(Delim::Interp),
+            "end" => (in_block && open.is_some()) || open == Some(Delim::CaseBody),
+            "\\n" => in_block || open == Some(Delim::CaseBody),
+            "," => matches!(
+                open,
+                Some(Delim::Args)
+                    | Some(Delim::CaseHead)
+                    | Some(Delim::CaseArms)
+                    | Some(Delim::CaseBody)
+            ),
+            terminal if terminal != "|" && BINOPS.contains(&terminal) => any_binop,
+            // Only statements are assignments.
+            terminal if ASSIGN_OPS.contains(&terminal) => in_block || cx.then_branch,
+            "->" => {
+                matches!(
+                    open,
+                    Some(Delim::FnHeader)
+                        | Some(Delim::CaseArms)
+                        | Some(Delim::CaseGuard)
+                        | Some(Delim::IfHead)
+                        | Some(Delim::Else)
+                        | Some(Delim::ElseArrow)
+                        | Some(Delim::LoopHead)
+                ) || cx.prev == Some(Tok::Keyword(Keyword::Do))
+            }
+            "where" => open == Some(Delim::CaseArms),
+            "in" => open == Some(Delim::ForHead),
+            "else" => {
+                cx.then_branch || matches!(open, Some(Delim::IfBlock) | Some(Delim::IfArrowBlock))
+            }
+            // `do` also begins an expression.
+            "do" => {
+                any_expr
+                    || matches!(
+                        open,
+                        Some(Delim::CaseHead)
+                            | Some(Delim::IfHead)
+                            | Some(Delim::Else)
+                            | Some(Delim::LoopHead)
+                    )
+            }
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+    if open.is_none() && terminals.contains(&"\\n") {
+        terminals.push("EOF");
+    }
+    // Newlines at the end of the input are removed, so a block that's still
+    // open there can only be closed.
+    let in_end_block = (in_block && open.is_some()) || open == Some(Delim::CaseBody);
+    if at_eof && in_end_block {
+        if in_block {
+            terminals.retain(|&terminal| terminal != "\\n");
+        }
+        if !terminals.contains(&"end") {
+            terminals.push("end");
+        }
+    }
+    terminals
+}
+
+/// Describe a list of expected terminals in Calypso syntax, e.g. "`)` or an
+/// operator". Terminals that only make sense as a group are merged.
+fn describe_terminals(expected: &[&str]) -> String {
+    let mut descriptions = Vec::new();
+    let mut push = |description: String| {
+        if !descriptions.contains(&description) {
+            descriptions.push(description);
+        }
+    };
+    let any_expr = expected.contains(&"NumberLit");
+    let any_binop = expected.iter().filter(|t| BINOPS.contains(t)).count() > 1;
+    let any_assign = expected.iter().filter(|t| ASSIGN_OPS.contains(t)).count() > 1;
+    for terminal in expected {
+        if any_expr && EXPR_START.contains(terminal) {
+            push("an expression".to_string());
+        } else if any_binop && BINOPS.contains(terminal) {
+            push("an operator".to_string());
+        } else if any_assign && ASSIGN_OPS.contains(terminal) {
+            push("an assignment operator".to_string());
+        } else {
+            push(describe_terminal(terminal));
+        }
+    }
+
+    match descriptions.split_last() {
+        None => "nothing".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+fn describe_terminal(terminal: &str) -> String {
+    match terminal {
+        "NumberLit" => "a number".to_string(),
+        "Atom" => "an atom".to_string(),
+        "Ident" => "an identifier".to_string(),
+        "StringLit" | "StringStart" => "a string".to_string(),
+        "CharLit" => "a character".to_string(),
+        "BytesLit" => "a byte string".to_string(),
+        "ByteLit" => "a byte".to_string(),
+        "StringFragment" => "string contents".to_string(),
+        "StringEnd" => "the end of the string".to_string(),
+        "InterpEnd" => "`}`".to_string(),
+        "\\n" => "a newline".to_string(),
+        "\\\\\\\\" => "`\\\\`".to_string(),
+        "EOF" => "the end of the input".to_string(),
+        terminal => format!("`{}`", terminal),
+    }
+}
+
+/// Describe a token that was found, given its lexeme.
+fn describe_tok(tok: Tok, lexeme: &str) -> String {
+    match tok {
+        Tok::Unprocessed(TokenType::Newline) => "a newline".to_string(),
+        Tok::Unprocessed(TokenType::StringStart) | Tok::String(_) => "a string".to_string(),
+        Tok::Unprocessed(TokenType::InterpEnd) => "`}`".to_string(),
+        Tok::Char(_) => "a character".to_string(),
+        Tok::Bytes(_) => "a byte string".to_string(),
+        Tok::Byte(_) => "a byte".to_string(),
+        Tok::Number(..) => format!("the number `{}`", lexeme),
+        Tok::Ident(_) => format!("the identifier `{}`", lexeme),
+        Tok::Atom(_) => format!("the atom `{}`", lexeme),
+        Tok::Keyword(_) => format!("the keyword `{}`", lexeme),
+        _ => format!("`{}`", lexeme),
+    }
+}
+
+fn starts_expr(tok: Tok) -> bool {
+    match tok {
+        Tok::Unprocessed(ty) => matches!(
+            ty,
+            TokenType::LParen | TokenType::Bang | TokenType::StringStart
+        ),
+        Tok::StringFragment(_) => false,
+        _ => true,
+    }
+}
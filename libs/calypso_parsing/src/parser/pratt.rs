@@ -0,0 +1,1320 @@
+//! A hand-written Pratt parser, as an alternative to the LALRPOP grammar.
+//!
+//! For well-formed programs, it produces exactly the same ASTs as the
+//! LALRPOP grammar (including spans). Statements are parsed by recursive
+//! descent, but the precedence of operators is described by
+//! [`BINARY_OPERATORS`] instead of a nonterminal for every level, and the
+//! parser can recover from any syntax error. For malformed programs, the
+//! errors it reports (and so the ASTs it recovers) may differ from those of
+//! the grammar.
+
+use std::sync::Arc;
+
+use calypso_ast::expr::{BinOpKind, CaseArm, Expr, ExprKind, Primary, StringPart, UnOpKind};
+use calypso_ast::item::{FnDecl, Item, ItemKind, Param};
+use calypso_ast::pattern::{Pattern, PatternKind};
+use calypso_ast::stmt::{Block, Stmt, StmtKind};
+use calypso_ast::ty::{Ty, TyKind};
+use calypso_base::span::{Span, Spanned};
+use calypso_base::streams::{Stream, Streamed};
+use calypso_base::symbol::{kw::Keyword, Symbol};
+use calypso_common::gcx::GlobalCtxt;
+
+use super::error::{self, ErrorRecovery, ParseError};
+use super::tokens::{process_iter, Tok};
+use crate::constant::ConstEvaluator;
+use crate::lexer::{Lexer, TokenType};
+use crate::literal;
+
+/// The associativity of a binary operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+/// The binary operators, with their precedence (operators with a higher
+/// precedence bind more tightly) and associativity.
+pub const BINARY_OPERATORS: &[(TokenType, BinOpKind, u8, Assoc)] = &[
+    (TokenType::PipePipe, BinOpKind::LogicalOr, 1, Assoc::Left),
+    (TokenType::AndAnd, BinOpKind::LogicalAnd, 2, Assoc::Left),
+    (TokenType::Pipe, BinOpKind::BitOr, 3, Assoc::Left),
+    (TokenType::Caret, BinOpKind::BitXor, 4, Assoc::Left),
+    (TokenType::And, BinOpKind::BitAnd, 5, Assoc::Left),
+    (TokenType::EqEq, BinOpKind::Equal, 6, Assoc::Left),
+    (TokenType::BangEq, BinOpKind::NotEqual, 6, Assoc::Left),
+    (TokenType::Lt, BinOpKind::Lt, 7, Assoc::Left),
+    (TokenType::Gt, BinOpKind::Gt, 7, Assoc::Left),
+    (TokenType::LtEq, BinOpKind::LtEq, 7, Assoc::Left),
+    (TokenType::GtEq, BinOpKind::GtEq, 7, Assoc::Left),
+    (TokenType::LtLt, BinOpKind::BitShiftLeft, 8, Assoc::Left),
+    (TokenType::GtGt, BinOpKind::BitShiftRight, 8, Assoc::Left),
+    (TokenType::Plus, BinOpKind::Add, 9, Assoc::Left),
+    (TokenType::Minus, BinOpKind::Subtract, 9, Assoc::Left),
+    (TokenType::Star, BinOpKind::Multiply, 10, Assoc::Left),
+    (TokenType::Slash, BinOpKind::Divide, 10, Assoc::Left),
+    (TokenType::Percent, BinOpKind::Modulo, 10, Assoc::Left),
+    (TokenType::StarStar, BinOpKind::Exponent, 11, Assoc::Right),
+];
+
+/// The unary (prefix) operators. They bind more tightly than any binary
+/// operator, so e.g. `-a ** b` is `(-a) ** b`.
+pub const UNARY_OPERATORS: &[(TokenType, UnOpKind)] = &[
+    (TokenType::Minus, UnOpKind::Negative),
+    (TokenType::Bang, UnOpKind::UnaryNot),
+];
+
+/// The operators of compound assignments, e.g. `*=`.
+const ASSIGN_OPERATORS: &[(TokenType, BinOpKind)] = &[
+    (TokenType::PlusEq, BinOpKind::Add),
+    (TokenType::MinusEq, BinOpKind::Subtract),
+    (TokenType::StarEq, BinOpKind::Multiply),
+    (TokenType::SlashEq, BinOpKind::Divide),
+    (TokenType::PercentEq, BinOpKind::Modulo),
+    (TokenType::StarStarEq, BinOpKind::Exponent),
+    (TokenType::PipeEq, BinOpKind::BitOr),
+    (TokenType::CaretEq, BinOpKind::BitXor),
+    (TokenType::AndEq, BinOpKind::BitAnd),
+    (TokenType::LtLtEq, BinOpKind::BitShiftLeft),
+    (TokenType::GtGtEq, BinOpKind::BitShiftRight),
+];
+
+/// The terminals (as named in the LALRPOP grammar) that can begin an
+/// operand of a binary operator.
+const OPERAND_START: &[&str] = &[
+    "!",
+    "(",
+    "-",
+    "Atom",
+    "ByteLit",
+    "BytesLit",
+    "CharLit",
+    "Ident",
+    "NumberLit",
+    "StringLit",
+    "StringStart",
+    "case",
+    "do",
+    "false",
+    "true",
+];
+
+/// The terminals that can begin a control flow expression, which can't be
+/// an operand without parentheses.
+const CONTROL_START: &[&str] = &["break", "continue", "for", "if", "loop", "ret", "while"];
+
+/// The terminals that can begin a pattern.
+const PATTERN_START: &[&str] = &[
+    "Atom",
+    "ByteLit",
+    "BytesLit",
+    "CharLit",
+    "Ident",
+    "NumberLit",
+    "StringLit",
+    "_",
+    "false",
+    "mut",
+    "true",
+];
+
+/// The terminals that can begin a type.
+const TY_START: &[&str] = &["Ident", "fn"];
+
+/// The tokens that can follow a pattern, where skipping an invalid pattern
+/// stops.
+const PATTERN_FOLLOW: &[Tok<'static>] = &[
+    Tok::Unprocessed(TokenType::Arrow),
+    Tok::Unprocessed(TokenType::Comma),
+    Tok::Unprocessed(TokenType::Eq),
+    Tok::Unprocessed(TokenType::Pipe),
+    Tok::Keyword(Keyword::In),
+    Tok::Keyword(Keyword::Where),
+];
+
+/// The tokens that can follow a type, where skipping an invalid type stops.
+const TY_FOLLOW: &[Tok<'static>] = &[
+    Tok::Unprocessed(TokenType::Arrow),
+    Tok::Unprocessed(TokenType::BackslashBackslash),
+    Tok::Unprocessed(TokenType::Comma),
+    Tok::Unprocessed(TokenType::Eq),
+];
+
+// The tokens that end each kind of construct. Recovery from a syntax error
+// skips tokens until one of those of the constructs that the parser is in.
+const PAREN: &[Tok<'static>] = &[Tok::Unprocessed(TokenType::RParen)];
+const INTERP: &[Tok<'static>] = &[Tok::Unprocessed(TokenType::InterpEnd)];
+const PROGRAM: &[Tok<'static>] = &[NEWLINE];
+const BLOCK: &[Tok<'static>] = &[NEWLINE, Tok::Keyword(Keyword::End)];
+const THEN_BLOCK: &[Tok<'static>] = &[
+    NEWLINE,
+    Tok::Keyword(Keyword::Else),
+    Tok::Keyword(Keyword::End),
+];
+const CASE_ARMS: &[Tok<'static>] = &[
+    NEWLINE,
+    Tok::Unprocessed(TokenType::Comma),
+    Tok::Keyword(Keyword::End),
+];
+
+const NEWLINE: Tok<'static> = Tok::Unprocessed(TokenType::Newline);
+
+type SpannedTok<'tok> = (usize, Tok<'tok>, usize);
+
+/// Lex and parse a whole file with the Pratt parser, ignoring whitespace and
+/// comments.
+///
+/// This behaves the same as [`super::parse_program`], except that the parser
+/// can always recover from syntax errors, so they are never fatal.
+// The method itself isn't general enough over the lifetime of the tokens.
+#[allow(clippy::redundant_closure_for_method_calls)]
+#[must_use]
+pub fn parse_program<'tok>(
+    file_id: usize,
+    source: &'tok str,
+    gcx: &Arc<GlobalCtxt>,
+) -> Block<'tok> {
+    parse_with(file_id, source, gcx, |parser| parser.parse_program())
+}
+
+/// Lex and parse an expression with the Pratt parser, ignoring whitespace
+/// and comments.
+///
+/// This behaves the same as [`super::parse_expr`], except that the parser
+/// can always recover from syntax errors, so they are never fatal.
+// The method itself isn't general enough over the lifetime of the tokens.
+#[allow(clippy::redundant_closure_for_method_calls)]
+#[must_use]
+pub fn parse_expr<'tok>(
+    file_id: usize,
+    source: &'tok str,
+    gcx: &Arc<GlobalCtxt>,
+) -> Box<Expr<'tok>> {
+    parse_with(file_id, source, gcx, |parser| parser.parse())
+}
+
+/// Lex a file and run the parser on its tokens, reporting any syntax errors
+/// and any number literals that are out of range.
+fn parse_with<'tok, T>(
+    file_id: usize,
+    source: &'tok str,
+    gcx: &Arc<GlobalCtxt>,
+    parse: impl for<'s> FnOnce(&mut Parser<'tok, 's>) -> T,
+) -> T {
+    let lexer = Lexer::new(file_id, source, Arc::clone(gcx));
+    let tokens = process_iter(lexer.into_iter(), true).collect::<Vec<_>>();
+    ConstEvaluator::new(file_id, Arc::clone(gcx)).check_values(&tokens);
+    let mut parser = Parser::new(file_id, &tokens);
+    let result = parse(&mut parser);
+    error::report_recovered_errors(parser.errors, file_id, source, &tokens, gcx);
+    result
+}
+
+/// A Pratt parser for programs and expressions.
+pub struct Parser<'tok, 's> {
+    file_id: usize,
+    stream: Streamed<'s, SpannedTok<'tok>>,
+    /// The location of the end of the input.
+    eof: usize,
+    /// The tokens that end each of the constructs (e.g. parentheses, string
+    /// interpolations and blocks) that we're in, innermost last.
+    closers: Vec<&'static [Tok<'static>]>,
+    errors: Vec<ErrorRecovery<'tok>>,
+}
+
+impl<'tok, 's> Parser<'tok, 's> {
+    /// Create a new parser over the (processed and filtered) tokens of the
+    /// file with the ID `file_id`.
+    #[must_use]
+    pub fn new(file_id: usize, tokens: &'s [SpannedTok<'tok>]) -> Self {
+        Self {
+            file_id,
+            stream: Streamed::new(tokens),
+            eof: tokens.last().map_or(0, |&(_, _, hi)| hi),
+            closers: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// The syntax errors that the parser encountered (and recovered from) so
+    /// far.
+    #[must_use]
+    pub fn errors(&self) -> &[ErrorRecovery<'tok>] {
+        &self.errors
+    }
+
+    /// Parse the statements that make up the rest of the input. Statements
+    /// that could not be parsed are skipped up to the next newline.
+    pub fn parse_program(&mut self) -> Block<'tok> {
+        self.block(PROGRAM)
+    }
+
+    /// Parse an expression that makes up the rest of the input. Parts of
+    /// the expression that could not be parsed are replaced with
+    /// [`ExprKind::Error`] nodes.
+    ///
+    /// # Panics
+    ///
+    /// This function should not panic.
+    pub fn parse(&mut self) -> Box<Expr<'tok>> {
+        let (_, expr) = self.expr();
+        if self.stream.peek().is_some() {
+            let follow = self.follow();
+            self.unexpected(follow);
+            let dropped = self.stream.by_ref().collect();
+            self.errors.last_mut().unwrap().dropped_tokens = dropped;
+        }
+        Box::new(expr)
+    }
+
+    /// Parse statements separated by newlines, up to a token that ends the
+    /// block (one of `closers`) or an enclosing construct.
+    fn block(&mut self, closers: &'static [Tok<'static>]) -> Block<'tok> {
+        self.closers.push(closers);
+        let lo = self.location();
+        let mut stmts = Vec::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(tok) if tok != NEWLINE && self.closes(tok) => break,
+                Some(_) => {}
+            }
+            stmts.push(self.stmt());
+            match self.peek() {
+                Some(NEWLINE) => {
+                    self.stream.next();
+                }
+                Some(tok) if !self.closes(tok) => {
+                    let follow = self.follow();
+                    self.unexpected(follow);
+                    self.recover(false);
+                    self.eat(NEWLINE);
+                }
+                _ => {}
+            }
+        }
+        self.closers.pop();
+
+        // An empty block is located at the token after it.
+        let span = if stmts.is_empty() {
+            Span::new(lo, lo)
+        } else {
+            self.span(lo)
+        };
+        Block::new(stmts, span, self.file_id)
+    }
+
+    fn stmt(&mut self) -> Stmt<'tok> {
+        let lo = self.location();
+        let kind = match (self.peek(), self.peek2()) {
+            (Some(Tok::Keyword(Keyword::Let)), _) => {
+                self.stream.next();
+                let pattern = self.pattern();
+                let value = match self.peek() {
+                    None => None,
+                    Some(tok) if tok != Tok::Unprocessed(TokenType::Eq) && self.closes(tok) => None,
+                    Some(tok) => {
+                        // A missing `=` is recovered from by parsing the
+                        // value anyway.
+                        if tok == Tok::Unprocessed(TokenType::Eq) {
+                            self.stream.next();
+                        } else {
+                            let closers = self.closers.last().map_or(&[][..], |closers| *closers);
+                            let expected = std::iter::once("=")
+                                .chain(closers.iter().map(|&closer| terminal(closer)))
+                                .map(str::to_string)
+                                .collect::<Vec<_>>();
+                            self.unexpected(expected);
+                        }
+                        let (_, value) = self.expr();
+                        Some(Box::new(value))
+                    }
+                };
+                StmtKind::Let { pattern, value }
+            }
+            (Some(Tok::Ident(_)), Some(Tok::Unprocessed(TokenType::Eq))) => {
+                let target = self.ident().unwrap();
+                self.stream.next();
+                let (_, value) = self.expr();
+                StmtKind::Assign {
+                    target,
+                    value: Box::new(value),
+                }
+            }
+            (Some(Tok::Ident(_)), Some(Tok::Unprocessed(ty))) if assign_op(ty).is_some() => {
+                let target = self.ident().unwrap();
+                let (op_lo, _, op_hi) = self.stream.next().unwrap();
+                let op = Spanned::new(Span::new(op_lo, op_hi), assign_op(ty).unwrap());
+                let (_, value) = self.expr();
+                StmtKind::AssignOp {
+                    target,
+                    op,
+                    value: Box::new(value),
+                }
+            }
+            (Some(Tok::Keyword(Keyword::Pub)), _) | (Some(Tok::Keyword(Keyword::Fn)), _) => {
+                self.item(lo)
+            }
+            _ => StmtKind::Expr(Box::new(self.expr().1)),
+        };
+        Stmt::new(kind, self.span(lo), self.file_id)
+    }
+
+    /// Parse a function declaration, starting at `lo`. If its name is
+    /// missing, the declaration is skipped and replaced with an
+    /// [`ExprKind::Error`] node.
+    fn item(&mut self, lo: usize) -> StmtKind<'tok> {
+        let public = self.eat(Tok::Keyword(Keyword::Pub));
+        self.expect(Tok::Keyword(Keyword::Fn), "fn");
+        if let Some(name) = self.ident() {
+            let decl = self.fn_decl(public, name);
+            StmtKind::Item(Item::new(ItemKind::Fn(decl), self.span(lo), self.file_id))
+        } else {
+            self.unexpected(to_strings(&["Ident"]));
+            StmtKind::Expr(Box::new(self.recover(false)))
+        }
+    }
+
+    /// Parse the rest of a function declaration, after its name.
+    fn fn_decl(&mut self, public: bool, name: Spanned<Symbol>) -> FnDecl<'tok> {
+        let params = if self.eat(Tok::Unprocessed(TokenType::LParen)) {
+            let params = self.list(Self::param, &["Ident"], to_strings(&[":", "\\\\\\\\"]));
+            params.into_iter().flatten().collect()
+        } else {
+            self.unexpected(to_strings(&["("]));
+            Vec::new()
+        };
+        let ret = if self.eat(Tok::Unprocessed(TokenType::Colon)) {
+            Some(self.ty())
+        } else {
+            None
+        };
+        let arrow_lo = self.location();
+        self.expect(Tok::Unprocessed(TokenType::Arrow), "->");
+
+        // The body is either an expression, or a block of statements after
+        // `-> do` or a `->` that ends a line.
+        let body = if self.eat(NEWLINE) {
+            let block = self.block(BLOCK);
+            self.block_end(block, arrow_lo)
+        } else if self.peek() == Some(Tok::Keyword(Keyword::Do))
+            && self.peek2() != Some(Tok::Unprocessed(TokenType::Arrow))
+        {
+            let lo = self.location();
+            self.stream.next();
+            let block = self.block(BLOCK);
+            self.block_end(block, lo)
+        } else {
+            self.expr().1
+        };
+
+        FnDecl {
+            public,
+            name,
+            params,
+            ret,
+            body: Box::new(body),
+        }
+    }
+
+    /// Parse a parameter of a function. If its name is missing, it's
+    /// skipped.
+    fn param(&mut self) -> Option<Param<'tok>> {
+        let lo = self.location();
+        if let Some(name) = self.ident() {
+            let ty = if self.eat(Tok::Unprocessed(TokenType::Colon)) {
+                Some(self.ty())
+            } else {
+                None
+            };
+            let default = if self.eat(Tok::Unprocessed(TokenType::BackslashBackslash)) {
+                Some(Box::new(self.expr().1))
+            } else {
+                None
+            };
+            Some(Param {
+                name,
+                ty,
+                default,
+                span: self.span(lo),
+            })
+        } else {
+            self.unexpected(to_strings(&["Ident"]));
+            self.recover(false);
+            None
+        }
+    }
+
+    fn ty(&mut self) -> Ty {
+        let lo = self.location();
+        let kind = match self.peek() {
+            Some(Tok::Ident(name)) => {
+                self.stream.next();
+                let args = if self.eat(Tok::Unprocessed(TokenType::LParen)) {
+                    self.list(Self::ty, TY_START, Vec::new())
+                } else {
+                    Vec::new()
+                };
+                TyKind::Named { name, args }
+            }
+            Some(Tok::Keyword(Keyword::Fn)) => {
+                self.stream.next();
+                let params = if self.eat(Tok::Unprocessed(TokenType::LParen)) {
+                    self.list(Self::ty, TY_START, Vec::new())
+                } else {
+                    self.unexpected(to_strings(&["("]));
+                    Vec::new()
+                };
+                let ret = if self.eat(Tok::Unprocessed(TokenType::Colon)) {
+                    Some(Box::new(self.ty()))
+                } else {
+                    None
+                };
+                TyKind::Fn { params, ret }
+            }
+            _ => {
+                self.unexpected(to_strings(TY_START));
+                self.skip(|tok| TY_FOLLOW.contains(&tok));
+                TyKind::Error
+            }
+        };
+        Ty::new(kind, self.span(lo), self.file_id)
+    }
+
+    /// Parse a pattern, which may have alternatives separated by `|`.
+    fn pattern(&mut self) -> Pattern<'tok> {
+        let lo = self.location();
+        let first = self.single_pattern();
+        if self.peek() != Some(Tok::Unprocessed(TokenType::Pipe)) {
+            return first;
+        }
+        let mut alternatives = vec![first];
+        while self.eat(Tok::Unprocessed(TokenType::Pipe)) {
+            alternatives.push(self.single_pattern());
+        }
+        Pattern::new(PatternKind::Or(alternatives), self.span(lo), self.file_id)
+    }
+
+    /// Parse a pattern without any alternatives.
+    fn single_pattern(&mut self) -> Pattern<'tok> {
+        let lo = self.location();
+        let kind = match (self.peek(), self.peek2()) {
+            (Some(Tok::Keyword(Keyword::Mut)), Some(Tok::Ident(name))) => {
+                self.stream.next();
+                self.stream.next();
+                PatternKind::Binding {
+                    name,
+                    mutable: true,
+                }
+            }
+            (Some(Tok::Keyword(Keyword::Mut)), _) => {
+                self.stream.next();
+                self.unexpected(to_strings(&["Ident"]));
+                self.skip(|tok| PATTERN_FOLLOW.contains(&tok));
+                PatternKind::Error
+            }
+            (Some(Tok::Ident(_)), Some(Tok::Unprocessed(TokenType::LParen))) => {
+                let name = self.ident().unwrap();
+                self.stream.next();
+                let fields = self.list(Self::pattern, PATTERN_START, to_strings(&["|"]));
+                PatternKind::Constructor { name, fields }
+            }
+            (Some(Tok::Ident(name)), _) => {
+                self.stream.next();
+                PatternKind::Binding {
+                    name,
+                    mutable: false,
+                }
+            }
+            (Some(Tok::Unprocessed(TokenType::Under)), _) => {
+                self.stream.next();
+                PatternKind::Wildcard
+            }
+            (Some(tok), _) if literal(tok).is_some() => {
+                self.stream.next();
+                PatternKind::Literal(literal(tok).unwrap())
+            }
+            _ => {
+                self.unexpected(to_strings(PATTERN_START));
+                self.skip(|tok| PATTERN_FOLLOW.contains(&tok));
+                PatternKind::Error
+            }
+        };
+        Pattern::new(kind, self.span(lo), self.file_id)
+    }
+
+    /// Parse an expression, which may be a control flow expression. Returns
+    /// the location of its first token along with it, like
+    /// [`Self::binary`].
+    fn expr(&mut self) -> (usize, Expr<'tok>) {
+        let lo = self.location();
+        let kind = match self.peek() {
+            Some(Tok::Keyword(Keyword::If)) => {
+                self.stream.next();
+                self.if_expr()
+            }
+            Some(Tok::Keyword(Keyword::While)) => {
+                self.stream.next();
+                let cond = Box::new(self.cond());
+                let body = Box::new(self.branch(&["->", "do"]));
+                ExprKind::While { cond, body }
+            }
+            Some(Tok::Keyword(Keyword::For)) => {
+                self.stream.next();
+                let pattern = self.pattern();
+                self.expect(Tok::Keyword(Keyword::In), "in");
+                let iter = Box::new(self.cond());
+                let body = Box::new(self.branch(&["->", "do"]));
+                ExprKind::For {
+                    pattern,
+                    iter,
+                    body,
+                }
+            }
+            Some(Tok::Keyword(Keyword::Loop)) => {
+                self.stream.next();
+                ExprKind::Loop(Box::new(self.branch(&["->", "do"])))
+            }
+            Some(Tok::Keyword(Keyword::Break)) => {
+                self.stream.next();
+                ExprKind::Break
+            }
+            Some(Tok::Keyword(Keyword::Continue)) => {
+                self.stream.next();
+                ExprKind::Continue
+            }
+            Some(Tok::Keyword(Keyword::Ret)) => {
+                self.stream.next();
+                if matches!(self.peek(), Some(tok) if starts_expr(tok)) {
+                    ExprKind::Ret(Some(Box::new(self.expr().1)))
+                } else {
+                    ExprKind::Ret(None)
+                }
+            }
+            _ => return self.binary(0),
+        };
+        (lo, self.node(kind, lo))
+    }
+
+    /// Parse the condition of a control flow expression, which can't be
+    /// another control flow expression.
+    fn cond(&mut self) -> Expr<'tok> {
+        self.binary(0).1
+    }
+
+    /// Parse the rest of an `if` expression, after the `if`: either on one
+    /// line (`if a -> b else -> c`) or with blocks (`if a do ... else ...
+    /// end`, or `if a ->` with the blocks on the following lines).
+    fn if_expr(&mut self) -> ExprKind<'tok> {
+        let cond = Box::new(self.cond());
+        let lo = self.location();
+        match (self.peek(), self.peek2()) {
+            (Some(Tok::Keyword(Keyword::Do)), _) => {
+                self.stream.next();
+                let block = self.block(THEN_BLOCK);
+                if self.peek() != Some(Tok::Keyword(Keyword::Else)) {
+                    let then_branch = self.block_end(block, lo);
+                    return ExprKind::If {
+                        cond,
+                        then_branch: Box::new(then_branch),
+                        else_branch: None,
+                    };
+                }
+                let then_branch = Box::new(self.then_branch(block, lo));
+                let else_lo = self.location();
+                self.stream.next();
+                let block = self.block(BLOCK);
+                let else_branch = self.block_end(block, else_lo);
+                ExprKind::If {
+                    cond,
+                    then_branch,
+                    else_branch: Some(Box::new(else_branch)),
+                }
+            }
+            (Some(Tok::Unprocessed(TokenType::Arrow)), Some(NEWLINE)) => {
+                self.stream.next();
+                self.stream.next();
+                self.arrow_if(cond, lo)
+            }
+            (Some(Tok::Unprocessed(TokenType::Arrow)), _) => {
+                self.stream.next();
+                // An `else` on one line belongs to the innermost `if`, so
+                // the `then` branch can't be another control flow
+                // expression.
+                if !self.at_operand() {
+                    let then_branch = Box::new(self.branch_stmt());
+                    self.eat(Tok::Keyword(Keyword::End));
+                    return ExprKind::If {
+                        cond,
+                        then_branch,
+                        else_branch: None,
+                    };
+                }
+                let then_branch = Box::new(self.binary(0).1);
+                let else_branch = if self.eat(Tok::Keyword(Keyword::Else)) {
+                    Some(Box::new(self.else_branch()))
+                } else {
+                    self.eat(Tok::Keyword(Keyword::End));
+                    None
+                };
+                ExprKind::If {
+                    cond,
+                    then_branch,
+                    else_branch,
+                }
+            }
+            _ => {
+                self.unexpected(to_strings(&["->", "do"]));
+                ExprKind::If {
+                    cond,
+                    then_branch: Box::new(self.recover(false)),
+                    else_branch: None,
+                }
+            }
+        }
+    }
+
+    /// Parse the rest of an `if` expression with blocks after `->`, after
+    /// the `->` at `lo` and the newline. Each statement of a `then` block
+    /// must end with a newline, and the whole `else if` chain is closed by a
+    /// single `end`.
+    fn arrow_if(&mut self, cond: Box<Expr<'tok>>, lo: usize) -> ExprKind<'tok> {
+        let block = self.block(THEN_BLOCK);
+        if self.peek() != Some(Tok::Keyword(Keyword::Else)) {
+            let then_branch = self.block_end(block, lo);
+            return ExprKind::If {
+                cond,
+                then_branch: Box::new(then_branch),
+                else_branch: None,
+            };
+        }
+        let newline = self.stream.prev().map(|&(_, tok, _)| tok) == Some(NEWLINE);
+        if !block.stmts.is_empty() && !newline {
+            self.unexpected(to_strings(&["\\n"]));
+        }
+        let then_branch = Box::new(self.then_branch(block, lo));
+        self.stream.next();
+
+        let else_lo = self.location();
+        let else_branch = if self.eat(Tok::Keyword(Keyword::If)) {
+            let cond = Box::new(self.cond());
+            let arrow_lo = self.location();
+            if self.arrow_newline() {
+                let kind = self.arrow_if(cond, arrow_lo);
+                self.node(kind, else_lo)
+            } else {
+                self.recover(false)
+            }
+        } else if self.peek() == Some(Tok::Unprocessed(TokenType::Arrow)) {
+            if self.arrow_newline() {
+                let block = self.block(BLOCK);
+                self.block_end(block, else_lo)
+            } else {
+                self.recover(false)
+            }
+        } else {
+            self.unexpected(to_strings(&["->", "if"]));
+            self.recover(false)
+        };
+        ExprKind::If {
+            cond,
+            then_branch,
+            else_branch: Some(Box::new(else_branch)),
+        }
+    }
+
+    /// Create the `then` branch of an `if` expression with blocks from the
+    /// `block` starting at `lo`, which is followed by an `else`.
+    fn then_branch(&self, block: Block<'tok>, lo: usize) -> Expr<'tok> {
+        let span = Span::new(lo, block.span.hi().max(lo));
+        Expr::new(ExprKind::Do(block), span, self.file_id)
+    }
+
+    /// Parse the branch after the `else` of an `if` expression on one line,
+    /// which may be another `if` expression.
+    fn else_branch(&mut self) -> Expr<'tok> {
+        if self.peek() == Some(Tok::Keyword(Keyword::If)) {
+            let lo = self.location();
+            self.stream.next();
+            let kind = self.if_expr();
+            self.node(kind, lo)
+        } else {
+            self.branch(&["->", "do", "if"])
+        }
+    }
+
+    /// Parse the body of a control flow expression: a single statement after
+    /// `->`, or a block after `do` or a `->` that ends a line. A single
+    /// statement may be closed by an `end` on the same line, which belongs to
+    /// the innermost body. If there's no body, `expected` are the terminals
+    /// that could have begun it.
+    fn branch(&mut self, expected: &[&str]) -> Expr<'tok> {
+        let lo = self.location();
+        match (self.peek(), self.peek2()) {
+            (Some(Tok::Unprocessed(TokenType::Arrow)), Some(NEWLINE))
+            | (Some(Tok::Keyword(Keyword::Do)), _) => {
+                self.stream.next();
+                self.eat(NEWLINE);
+                let block = self.block(BLOCK);
+                self.block_end(block, lo)
+            }
+            (Some(Tok::Unprocessed(TokenType::Arrow)), _) => {
+                self.stream.next();
+                let stmt = self.branch_stmt();
+                self.eat(Tok::Keyword(Keyword::End));
+                stmt
+            }
+            _ => {
+                self.unexpected(to_strings(expected));
+                self.recover(false)
+            }
+        }
+    }
+
+    /// Parse the statement after the `->` of a control flow expression. A
+    /// statement that isn't an expression (e.g. an assignment) becomes a
+    /// block of its own.
+    fn branch_stmt(&mut self) -> Expr<'tok> {
+        let stmt = self.stmt();
+        let span = stmt.span;
+        match stmt.kind {
+            StmtKind::Expr(expr) => *expr,
+            kind => {
+                let block = Block::new(
+                    vec![Stmt::new(kind, span, self.file_id)],
+                    span,
+                    self.file_id,
+                );
+                Expr::new(ExprKind::Do(block), span, self.file_id)
+            }
+        }
+    }
+
+    /// Expect the `end` of a `block`, and create a `do` block spanning from
+    /// `lo` to it.
+    fn block_end(&mut self, block: Block<'tok>, lo: usize) -> Expr<'tok> {
+        self.expect(Tok::Keyword(Keyword::End), "end");
+        self.node(ExprKind::Do(block), lo)
+    }
+
+    /// Parse an expression containing only binary operators with a
+    /// precedence of at least `min_prec`. Returns the location of the first
+    /// token of the expression (which may be a parenthesis that's not
+    /// included in its span) along with it.
+    fn binary(&mut self, min_prec: u8) -> (usize, Expr<'tok>) {
+        let (lo, mut lhs) = self.unary();
+        while let Some((tok_lo, op, prec, assoc)) = self.peek_binary_op() {
+            if prec < min_prec {
+                break;
+            }
+            let (_, _, tok_hi) = self.stream.next().unwrap();
+            let next_prec = match assoc {
+                Assoc::Left => prec + 1,
+                Assoc::Right => prec,
+            };
+            let (_, rhs) = self.binary(next_prec);
+            let op = Spanned::new(Span::new(tok_lo, tok_hi), op);
+            lhs = self.node(ExprKind::BinOp(Box::new(lhs), op, Box::new(rhs)), lo);
+        }
+        (lo, lhs)
+    }
+
+    fn unary(&mut self) -> (usize, Expr<'tok>) {
+        if let Some(&(lo, Tok::Unprocessed(ty), hi)) = self.stream.peek() {
+            if let Some(&(_, op)) = UNARY_OPERATORS.iter().find(|(op_ty, _)| *op_ty == ty) {
+                self.stream.next();
+                let (_, expr) = self.unary();
+                let op = Spanned::new(Span::new(lo, hi), op);
+                return (lo, self.node(ExprKind::UnOp(op, Box::new(expr)), lo));
+            }
+        }
+        self.term()
+    }
+
+    /// Parse an operand, along with any calls of it and accesses of its
+    /// fields, e.g. `f(a).b(c)`.
+    fn term(&mut self) -> (usize, Expr<'tok>) {
+        let (lo, mut expr) = self.operand();
+        loop {
+            match self.peek() {
+                Some(Tok::Unprocessed(TokenType::LParen)) => {
+                    self.stream.next();
+                    let args = self.args();
+                    expr = self.node(ExprKind::Call(Box::new(expr), args), lo);
+                }
+                Some(Tok::Unprocessed(TokenType::Dot)) => {
+                    self.stream.next();
+                    if let Some(field) = self.ident() {
+                        expr = self.node(ExprKind::Field(Box::new(expr), field), lo);
+                    } else {
+                        self.unexpected(to_strings(&["Ident"]));
+                        self.recover(true);
+                        expr = self.node(ExprKind::Error, lo);
+                    }
+                }
+                _ => break,
+            }
+        }
+        (lo, expr)
+    }
+
+    fn operand(&mut self) -> (usize, Expr<'tok>) {
+        let lo = self.location();
+        let kind = match self.peek() {
+            Some(tok) if literal(tok).is_some() => ExprKind::Primary(literal(tok).unwrap()),
+            Some(Tok::Ident(ident)) => ExprKind::Ident(ident),
+            Some(Tok::Unprocessed(TokenType::LParen)) => {
+                self.stream.next();
+                let expr = self.delimited(PAREN);
+                return (lo, expr);
+            }
+            Some(Tok::Unprocessed(TokenType::StringStart)) => {
+                self.stream.next();
+                return (lo, self.interpolated(lo));
+            }
+            Some(Tok::Keyword(Keyword::Do)) => {
+                self.stream.next();
+                if self.eat(Tok::Unprocessed(TokenType::Arrow)) {
+                    self.eat(NEWLINE);
+                } else {
+                    self.unexpected(to_strings(&["->"]));
+                }
+                let block = self.block(BLOCK);
+                return (lo, self.block_end(block, lo));
+            }
+            Some(Tok::Keyword(Keyword::Case)) => {
+                self.stream.next();
+                return (lo, self.case(lo));
+            }
+            _ => {
+                self.unexpected(to_strings(OPERAND_START));
+                return (lo, self.recover(true));
+            }
+        };
+        self.stream.next();
+        (lo, self.node(kind, lo))
+    }
+
+    /// Parse the rest of a `case` expression starting at `lo`, after the
+    /// `case`.
+    fn case(&mut self, lo: usize) -> Expr<'tok> {
+        let mut scrutinees = vec![self.binary(0).1];
+        while self.eat(Tok::Unprocessed(TokenType::Comma)) {
+            scrutinees.push(self.binary(0).1);
+        }
+        self.expect(Tok::Keyword(Keyword::Do), "do");
+
+        // The arms are separated by newlines or commas.
+        self.closers.push(CASE_ARMS);
+        let mut arms = Vec::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(tok) if !CASE_ARMS[..2].contains(&tok) && self.closes(tok) => break,
+                Some(_) => {}
+            }
+            arms.push(self.case_arm());
+            match self.peek() {
+                Some(NEWLINE) | Some(Tok::Unprocessed(TokenType::Comma)) => {
+                    self.stream.next();
+                }
+                Some(tok) if !self.closes(tok) => {
+                    let follow = self.follow();
+                    self.unexpected(follow);
+                    self.recover(false);
+                }
+                _ => {}
+            }
+        }
+        self.closers.pop();
+
+        self.expect(Tok::Keyword(Keyword::End), "end");
+        self.node(ExprKind::Case { scrutinees, arms }, lo)
+    }
+
+    /// Parse an arm of a `case` expression, e.g. `n, acc where n > 0 -> n *
+    /// acc`.
+    fn case_arm(&mut self) -> CaseArm<'tok> {
+        let lo = self.location();
+        let mut patterns = vec![self.pattern()];
+        while self.eat(Tok::Unprocessed(TokenType::Comma)) {
+            patterns.push(self.pattern());
+        }
+        let guard = if self.eat(Tok::Keyword(Keyword::Where)) {
+            Some(Box::new(self.expr().1))
+        } else {
+            None
+        };
+        self.expect(Tok::Unprocessed(TokenType::Arrow), "->");
+        self.eat(NEWLINE);
+        let (_, body) = self.expr();
+        CaseArm {
+            patterns,
+            guard,
+            body: Box::new(body),
+            span: self.span(lo),
+        }
+    }
+
+    /// Parse the rest of an interpolated string, after its `StringStart`.
+    fn interpolated(&mut self, lo: usize) -> Expr<'tok> {
+        let mut parts = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Tok::StringFragment(fragment)) => {
+                    self.stream.next();
+                    parts.push(StringPart::Fragment(fragment));
+                }
+                Some(Tok::Unprocessed(TokenType::InterpStart)) => {
+                    self.stream.next();
+                    parts.push(StringPart::Interpolation(Box::new(self.delimited(INTERP))));
+                }
+                Some(Tok::Unprocessed(TokenType::StringEnd)) => {
+                    self.stream.next();
+                    return self.node(ExprKind::Interpolated(parts), lo);
+                }
+                _ => {
+                    // The lexer always ends strings (or reports that they
+                    // are unterminated), so this only happens at EOF.
+                    self.unexpected(to_strings(&["${", "StringEnd", "StringFragment"]));
+                    return self.node(ExprKind::Interpolated(parts), lo);
+                }
+            }
+        }
+    }
+
+    /// Parse the comma-separated arguments of a call, after the `(`.
+    fn args(&mut self) -> Vec<Expr<'tok>> {
+        let start: Vec<_> = OPERAND_START.iter().chain(CONTROL_START).copied().collect();
+        let follow = BINARY_OPERATORS
+            .iter()
+            .map(|(_, op, ..)| op.to_string())
+            .collect();
+        self.list(|parser| parser.expr().1, &start, follow)
+    }
+
+    /// Parse a comma-separated list (with an optional trailing comma) of
+    /// items that begin with one of the `start` terminals, after the `(`.
+    /// `follow` are the terminals that can follow an item, other than `,`
+    /// and `)`.
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> T,
+        start: &[&str],
+        follow: Vec<String>,
+    ) -> Vec<T> {
+        self.closers.push(PAREN);
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Tok::Unprocessed(TokenType::RParen)) => break,
+                Some(_) => {}
+                None => {
+                    self.unexpected(start.iter().chain(&[")"]).map(|t| (*t).to_string()));
+                    break;
+                }
+            }
+            items.push(item(self));
+            match self.peek() {
+                Some(Tok::Unprocessed(TokenType::Comma)) => {
+                    self.stream.next();
+                }
+                Some(Tok::Unprocessed(TokenType::RParen)) => break,
+                _ => {
+                    self.unexpected(follow.into_iter().chain(to_strings(&[")", ","])));
+                    if self.stream.peek().is_some() {
+                        self.recover(false);
+                    }
+                    break;
+                }
+            }
+        }
+
+        self.closers.pop();
+        self.eat(Tok::Unprocessed(TokenType::RParen));
+        items
+    }
+
+    /// Parse an expression followed by one of `closers` (a closing
+    /// delimiter), after the opening delimiter.
+    fn delimited(&mut self, closers: &'static [Tok<'static>]) -> Expr<'tok> {
+        self.closers.push(closers);
+        let (lo, mut expr) = self.expr();
+
+        match self.peek() {
+            Some(tok) if closers.contains(&tok) => {}
+            Some(_) => {
+                // The whole expression is replaced, as it's unclear what
+                // was meant.
+                let follow = self.follow();
+                self.unexpected(follow);
+                self.recover(false);
+                expr = self.node(ExprKind::Error, lo);
+            }
+            None => {
+                let follow = self.follow();
+                self.unexpected(follow);
+            }
+        }
+
+        self.closers.pop();
+        if matches!(self.peek(), Some(tok) if closers.contains(&tok)) {
+            self.stream.next();
+        }
+        expr
+    }
+
+    /// Skip tokens until one that could continue the expression: one that
+    /// ends an enclosing construct or, if `at_operators` is `true`, a binary
+    /// operator. Returns an [`ExprKind::Error`] node spanning the skipped
+    /// tokens, which are recorded in the last error.
+    fn recover(&mut self, at_operators: bool) -> Expr<'tok> {
+        let lo = self.location();
+        self.skip(|tok| at_operators && binary_op(tok).is_some());
+        self.node(ExprKind::Error, lo)
+    }
+
+    /// Skip tokens until one that ends an enclosing construct, or that
+    /// `stop`s the skipping, and record them in the last error. Parentheses,
+    /// string interpolations and `do` blocks are skipped as a whole.
+    fn skip(&mut self, stop: impl Fn(Tok<'tok>) -> bool) {
+        let mut dropped = Vec::new();
+        let mut closers = Vec::new();
+        while let Some(tok) = self.peek() {
+            if closers.is_empty() && (self.closes(tok) || stop(tok)) {
+                break;
+            }
+            match tok {
+                Tok::Unprocessed(TokenType::LParen) => {
+                    closers.push(Tok::Unprocessed(TokenType::RParen));
+                }
+                Tok::Unprocessed(TokenType::InterpStart) => {
+                    closers.push(Tok::Unprocessed(TokenType::InterpEnd));
+                }
+                Tok::Keyword(Keyword::Do) => closers.push(Tok::Keyword(Keyword::End)),
+                _ if closers.last() == Some(&tok) => {
+                    closers.pop();
+                }
+                _ => {}
+            }
+            dropped.push(self.stream.next().unwrap());
+        }
+        self.errors.last_mut().unwrap().dropped_tokens = dropped;
+    }
+
+    /// Check if `tok` ends one of the constructs that we're in, up to the
+    /// innermost parenthesis or string interpolation (as e.g. a newline
+    /// can't end a block within them).
+    fn closes(&self, tok: Tok<'tok>) -> bool {
+        for &closers in self.closers.iter().rev() {
+            if closers.contains(&tok) {
+                return true;
+            }
+            if closers == PAREN || closers == INTERP {
+                break;
+            }
+        }
+        false
+    }
+
+    /// The terminals that can follow a complete expression: any binary
+    /// operator, or a token that ends the innermost construct, if any.
+    fn follow(&self) -> Vec<String> {
+        let closers = self.closers.last().map_or(&[][..], |closers| *closers);
+        BINARY_OPERATORS
+            .iter()
+            .map(|(_, op, ..)| op.to_string())
+            .chain(closers.iter().map(|&closer| terminal(closer).to_string()))
+            .collect()
+    }
+
+    /// Check if the next statement is an expression that's made up of
+    /// operands and operators, i.e. not a control flow expression, a
+    /// binding, an assignment or an item.
+    fn at_operand(&self) -> bool {
+        match (self.peek(), self.peek2()) {
+            (Some(Tok::Keyword(kw)), _) => !matches!(
+                kw,
+                Keyword::Let
+                    | Keyword::Pub
+                    | Keyword::Fn
+                    | Keyword::If
+                    | Keyword::While
+                    | Keyword::For
+                    | Keyword::Loop
+                    | Keyword::Break
+                    | Keyword::Continue
+                    | Keyword::Ret
+            ),
+            (Some(Tok::Ident(_)), Some(Tok::Unprocessed(ty))) => {
+                ty != TokenType::Eq && assign_op(ty).is_none()
+            }
+            _ => true,
+        }
+    }
+
+    /// Consume a `->` followed by a newline, or record a syntax error if
+    /// either is missing. Returns whether both were there.
+    fn arrow_newline(&mut self) -> bool {
+        if !self.eat(Tok::Unprocessed(TokenType::Arrow)) {
+            self.unexpected(to_strings(&["->"]));
+            false
+        } else if !self.eat(NEWLINE) {
+            self.unexpected(to_strings(&["\\n"]));
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Consume the next token if it's an identifier.
+    fn ident(&mut self) -> Option<Spanned<Symbol>> {
+        match self.stream.peek() {
+            Some(&(lo, Tok::Ident(name), hi)) => {
+                self.stream.next();
+                Some(Spanned::new(Span::new(lo, hi), name))
+            }
+            _ => None,
+        }
+    }
+
+    /// Consume the next token if it's `tok`. Returns whether it was.
+    fn eat(&mut self, tok: Tok<'tok>) -> bool {
+        let eaten = self.peek() == Some(tok);
+        if eaten {
+            self.stream.next();
+        }
+        eaten
+    }
+
+    /// Consume the next token if it's `tok`, or record a syntax error
+    /// (expecting the `terminal`) otherwise. Parsing continues as if the
+    /// token was there.
+    fn expect(&mut self, tok: Tok<'tok>, terminal: &str) {
+        if !self.eat(tok) {
+            self.unexpected(to_strings(&[terminal]));
+        }
+    }
+
+    /// Record a syntax error at the next token, which was not one of the
+    /// `expected` terminals (named as in the LALRPOP grammar).
+    fn unexpected(&mut self, expected: impl IntoIterator<Item = String>) {
+        let expected = expected
+            .into_iter()
+            .map(|terminal| format!("\"{}\"", terminal))
+            .collect();
+        let error = match self.stream.peek() {
+            Some(&token) => ParseError::UnrecognizedToken { token, expected },
+            None => ParseError::UnrecognizedEOF {
+                location: self.eof,
+                expected,
+            },
+        };
+        self.errors.push(ErrorRecovery {
+            error,
+            dropped_tokens: Vec::new(),
+        });
+    }
+
+    fn peek(&self) -> Option<Tok<'tok>> {
+        self.stream.peek().map(|&(_, tok, _)| tok)
+    }
+
+    fn peek2(&self) -> Option<Tok<'tok>> {
+        self.stream.peek2().map(|&(_, tok, _)| tok)
+    }
+
+    fn peek_binary_op(&self) -> Option<(usize, BinOpKind, u8, Assoc)> {
+        let &(lo, tok, _) = self.stream.peek()?;
+        binary_op(tok).map(|&(_, op, prec, assoc)| (lo, op, prec, assoc))
+    }
+
+    /// The location of the next token, or of the end of the input.
+    fn location(&self) -> usize {
+        self.stream.peek().map_or(self.eof, |&(lo, _, _)| lo)
+    }
+
+    /// The span from `lo` to the end of the last token.
+    fn span(&self, lo: usize) -> Span {
+        let hi = self.stream.prev().map_or(lo, |&(_, _, hi)| hi).max(lo);
+        Span::new(lo, hi)
+    }
+
+    /// Create an expression node spanning from `lo` to the end of the last
+    /// token.
+    fn node(&self, kind: ExprKind<'tok>, lo: usize) -> Expr<'tok> {
+        Expr::new(kind, self.span(lo), self.file_id)
+    }
+}
+
+fn binary_op(tok: Tok) -> Option<&'static (TokenType, BinOpKind, u8, Assoc)> {
+    match tok {
+        Tok::Unprocessed(ty) => BINARY_OPERATORS.iter().find(|&&(op, ..)| op == ty),
+        _ => None,
+    }
+}
+
+fn assign_op(ty: TokenType) -> Option<BinOpKind> {
+    ASSIGN_OPERATORS
+        .iter()
+        .find(|&&(op, _)| op == ty)
+        .map(|&(_, op)| op)
+}
+
+/// The literal that `tok` is, if any.
+fn literal(tok: Tok) -> Option<Primary> {
+    match tok {
+        Tok::Number(number, radix, suffix) => Some(Primary::Number(number, radix, suffix)),
+        Tok::Atom(atom) => Some(Primary::Atom(atom)),
+        Tok::String(string) => Some(Primary::String(string)),
+        Tok::Char(ch) => Some(Primary::Char(ch)),
+        Tok::Bytes(contents) => Some(Primary::Bytes(literal::bytes_value(contents))),
+        Tok::Byte(byte) => Some(Primary::Byte(byte)),
+        Tok::Keyword(Keyword::True) => Some(Primary::Bool(true)),
+        Tok::Keyword(Keyword::False) => Some(Primary::Bool(false)),
+        _ => None,
+    }
+}
+
+/// Check if `tok` can begin an expression.
+fn starts_expr(tok: Tok) -> bool {
+    match tok {
+        Tok::Keyword(kw) => matches!(
+            kw,
+            Keyword::True
+                | Keyword::False
+                | Keyword::Do
+                | Keyword::Case
+                | Keyword::If
+                | Keyword::While
+                | Keyword::For
+                | Keyword::Loop
+                | Keyword::Break
+                | Keyword::Continue
+                | Keyword::Ret
+        ),
+        Tok::Unprocessed(ty) => matches!(
+            ty,
+            TokenType::LParen | TokenType::Minus | TokenType::Bang | TokenType::StringStart
+        ),
+        Tok::StringFragment(_) => false,
+        _ => true,
+    }
+}
+
+/// The name of a token that ends a construct, as in the LALRPOP grammar.
+fn terminal(closer: Tok) -> &'static str {
+    match closer {
+        Tok::Unprocessed(TokenType::RParen) => ")",
+        Tok::Unprocessed(TokenType::InterpEnd) => "InterpEnd",
+        Tok::Unprocessed(TokenType::Newline) => "\\n",
+        Tok::Unprocessed(TokenType::Comma) => ",",
+        Tok::Keyword(Keyword::Else) => "else",
+        Tok::Keyword(Keyword::End) => "end",
+        _ => unreachable!("not a closing token: {:?}", closer),
+    }
+}
+
+fn to_strings(terminals: &[&str]) -> Vec<String> {
+    terminals.iter().map(|t| (*t).to_string()).collect()
+}
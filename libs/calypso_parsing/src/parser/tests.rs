@@ -0,0 +1,882 @@
+use std::sync::Arc;
+
+use calypso_ast::{
+    expr::{BinOpKind, Expr, ExprKind, StringPart},
+    pretty::PrettyPrinter,
+    traverse::Visitor,
+};
+use calypso_base::span::Span;
+use calypso_base::ui::{termcolor::ColorChoice, Emitters};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::{diagnostic::SourceMgr, report::GlobalReportingCtxt};
+use proptest::{prelude::*, sample::select};
+
+use super::{
+    parse_expr, parse_program, pratt,
+    tokens::{process_iter, Tok},
+};
+use crate::lexer::{Lexer, TokenType};
+
+fn new_gcx() -> Arc<GlobalCtxt> {
+    Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(ColorChoice::Never, ColorChoice::Never)),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    })
+}
+
+/// Parse an expression and pretty-print it. The Pratt parser must produce
+/// exactly the same expression.
+fn parse(source: &str) -> String {
+    let gcx = new_gcx();
+    let expr = parse_expr(0, source, &gcx).unwrap();
+    assert!(gcx.grcx.read().errors().is_empty());
+    assert_eq!(pratt::parse_expr(0, source, &gcx), expr);
+    assert!(gcx.grcx.read().errors().is_empty());
+
+    let mut printer = PrettyPrinter::default();
+    printer.visit_expr(&expr).unwrap();
+    printer.to_string()
+}
+
+#[test]
+fn precedence() {
+    assert_eq!(parse("1 + 2 * 3 ** 4"), "(+ 1 (* 2 (** 3 4)))");
+    assert_eq!(parse("-(1 - 2) == !true"), "(== (- (- 1 2)) (! true))");
+}
+
+#[test]
+fn atoms() {
+    assert_eq!(parse(":foo == :\"foo\""), "(== :foo :foo)");
+    assert_eq!(parse(":\"foo bar\" != :ok?"), "(!= :\"foo bar\" :ok?)");
+}
+
+#[test]
+fn strings() {
+    assert_eq!(parse(r#""a\"b" == "c""#), r#"(== "a\"b" "c")"#);
+    assert_eq!(
+        parse(r#""a ${1 + 2} b ${"c${true}"}""#),
+        r#""a ${(+ 1 2)} b ${"c${true}"}""#
+    );
+}
+
+#[test]
+fn calls() {
+    assert_eq!(
+        parse("f(1, g(2)) + -h()"),
+        "(+ (call f 1 (call g 2)) (- (call h)))"
+    );
+    assert_eq!(parse("(f)(x)(\n  y,\n)"), "(call (call f x) y)");
+    assert_eq!(
+        parse("-a.b(c).d\n  .e ** 2"),
+        "(** (- (. (. (call (. a b) c) d) e)) 2)"
+    );
+    assert_eq!(parse("1.to(n)"), "(call (. 1 to) n)");
+}
+
+/// Parse a program and pretty-print it. The Pratt parser must produce
+/// exactly the same block.
+fn parse_stmts(source: &str) -> String {
+    let gcx = new_gcx();
+    let block = parse_program(0, source, &gcx).unwrap();
+    assert!(gcx.grcx.read().errors().is_empty());
+    assert_eq!(pratt::parse_program(0, source, &gcx), block);
+    assert!(gcx.grcx.read().errors().is_empty());
+
+    let mut printer = PrettyPrinter::default();
+    printer.visit_block(&block).unwrap();
+    printer.to_string()
+}
+
+#[test]
+fn statements() {
+    assert_eq!(
+        parse_stmts("let x = 1\nlet mut y = x + 2\ny = y * 3\ny"),
+        "(block (let x 1) (let (mut y) (+ x 2)) (= y (* y 3)) y)"
+    );
+    // Shadowing refers to the old binding in the new value.
+    assert_eq!(
+        parse_stmts("let a = 5\nlet a = a + 5\nlet _ = a"),
+        "(block (let a 5) (let a (+ a 5)) (let _ a))"
+    );
+    assert_eq!(
+        parse_stmts("let x = do ->\n  let y = 2\n  y * y\nend\nx + 1"),
+        "(block (let x (do (let y 2) (* y y))) (+ x 1))"
+    );
+    // The value can be assigned later.
+    assert_eq!(
+        parse_stmts("let y\nlet mut z\ny = 1\nz = y"),
+        "(block (let y) (let (mut z)) (= y 1) (= z y))"
+    );
+    assert_eq!(parse_stmts("do ->\n  let y\nend"), "(block (do (let y)))");
+    assert_eq!(parse_stmts(""), "(block)");
+}
+
+#[test]
+fn functions() {
+    assert_eq!(
+        parse_stmts("pub fn add(a: uint, b: uint \\\\ 1): uint -> a + b"),
+        r"(block (pub (fn add ((: a uint) (\\ (: b uint) 1)) uint (+ a b))))"
+    );
+    assert_eq!(
+        parse_stmts("fn map(self, f: fn(T): U): Option(U) ->\n  f(self)\nend"),
+        "(block (fn map (self (: f (fn (T) U))) (Option U) (do (call f self))))"
+    );
+    assert_eq!(
+        parse_stmts("fn main() -> do\n  let x = 1\n  print(x)\nend\nmain()"),
+        "(block (fn main () _ (do (let x 1) (call print x))) (call main))"
+    );
+    assert_eq!(
+        parse_stmts("fn id(x : T) : T -> x"),
+        "(block (fn id ((: x T)) T x))"
+    );
+    // From `experiments/pseudocode-design/tour.cal`.
+    assert_eq!(
+        parse_stmts(
+            r"
+fn pt1() ->
+    // A comment
+    let mut x = 0
+    println(x)
+
+    x = 7
+end
+pt1()
+"
+        ),
+        "(block (fn pt1 () _ (do (let (mut x) 0) (call println x) (= x 7))) (call pt1))"
+    );
+}
+
+#[test]
+fn case() {
+    assert_eq!(
+        parse_stmts("case n, acc do\n  0, acc -> acc\n  n, acc -> f(n - 1, n * acc)\nend"),
+        "(block (case (n acc) (arm (0 acc) acc) (arm (n acc) (call f (- n 1) (* n acc)))))"
+    );
+    assert_eq!(
+        parse_stmts(
+            "case a, b do\n  Some(v), _ -> Some(v),\n  None, Some(v) -> Some(v),\n  _ -> None\nend"
+        ),
+        "(block (case (a b) (arm ((Some v) _) (call Some v)) \
+         (arm (None (Some v)) (call Some v)) (arm (_) None)))"
+    );
+    assert_eq!(
+        parse_stmts(
+            r#"case x do 0 | 1 -> :small, n where n > 1 -> "big", :a | "b" | true -> x end"#
+        ),
+        r#"(block (case (x) (arm ((| 0 1)) :small) (arm (n) (where (> n 1)) "big") (arm ((| :a "b" true)) x)))"#
+    );
+    assert_eq!(
+        parse_stmts("let Pair(mut a, _) = p"),
+        "(block (let (Pair (mut a) _) p))"
+    );
+}
+
+#[test]
+fn control_flow() {
+    assert_eq!(
+        parse_stmts("if n == 0 -> ret 1"),
+        "(block (if (== n 0) (ret 1)))"
+    );
+    assert_eq!(
+        parse_stmts("let x = if a -> 1 else if b -> 2 else -> 3"),
+        "(block (let x (if a 1 (if b 2 3))))"
+    );
+    assert_eq!(
+        parse_stmts("if c do\n  a\nelse\n  b\n  c\nend"),
+        "(block (if c (do a) (do b c)))"
+    );
+    assert_eq!(
+        parse_stmts("while x > 0 -> x = x - 1"),
+        "(block (while (> x 0) (do (= x (- x 1)))))"
+    );
+    assert_eq!(
+        parse_stmts("for Pair(k, v) in xs -> res = res * v"),
+        "(block (for (Pair k v) xs (do (= res (* res v)))))"
+    );
+    assert_eq!(
+        parse_stmts("loop do\n  if done -> break\n  continue\nend"),
+        "(block (loop (do (if done (break)) (continue))))"
+    );
+    assert_eq!(
+        parse_stmts("if a -> 1 else do\n  2\nend"),
+        "(block (if a 1 (do 2)))"
+    );
+    // `else` belongs to the innermost `if`.
+    assert_eq!(
+        parse_stmts("if a -> if b -> 1 else -> 2"),
+        "(block (if a (if b 1 2)))"
+    );
+    assert_eq!(
+        parse_stmts("fn f() -> do\n  ret\nend"),
+        "(block (fn f () _ (do (ret))))"
+    );
+}
+
+#[test]
+fn control_flow_closed_by_end() {
+    // A body after `->` may be closed by an `end` on the same line, which
+    // belongs to the innermost body.
+    assert_eq!(
+        parse_stmts("loop ->\n  if foo == bar -> break end\nend"),
+        "(block (loop (do (if (== foo bar) (break)))))"
+    );
+    assert_eq!(
+        parse_stmts("while a -> if b -> c end end"),
+        "(block (while a (if b c)))"
+    );
+    assert_eq!(
+        parse_stmts("loop do if a -> b else -> c end end"),
+        "(block (loop (do (if a b c))))"
+    );
+    assert_eq!(
+        parse_stmts("if a do b else c end"),
+        "(block (if a (do b) (do c)))"
+    );
+    assert_eq!(
+        parse_stmts("for x in xs do if x -> f(x) else -> break end end"),
+        "(block (for x xs (do (if x (call f x) (break)))))"
+    );
+}
+
+#[test]
+fn control_flow_blocks() {
+    // From the "Control Flow" section of `experiments/pseudocode-design/tour.cal`.
+    assert_eq!(
+        parse_stmts(
+            r#"
+    if age < 18 ->
+        println("Sorry kid, you can't be in here.")
+    else if age > 99 ->
+        println("Sorry, you can't be in here.")
+    else ->
+        println("Would you like a drink?")
+    end
+"#
+        ),
+        "(block (if (< age 18) (do (call println \"Sorry kid, you can't be in here.\")) \
+         (if (> age 99) (do (call println \"Sorry, you can't be in here.\")) \
+         (do (call println \"Would you like a drink?\")))))"
+    );
+    assert_eq!(
+        parse_stmts(
+            r#"
+    loop ->
+        if some_condition ->
+            continue // Skip past the println() later
+        end
+        println("Didn't skip")
+    end
+
+    while some_condition ->
+        println("Do something")
+    end
+"#
+        ),
+        "(block (loop (do (if some_condition (do (continue))) (call println \"Didn't skip\"))) \
+         (while some_condition (do (call println \"Do something\"))))"
+    );
+    assert_eq!(
+        parse_stmts("if a ->\n  b\nelse if c ->\n  d\nend"),
+        "(block (if a (do b) (if c (do d))))"
+    );
+    assert_eq!(
+        parse_stmts("for x in xs ->\n\n  f(x)\n\nend"),
+        "(block (for x xs (do (call f x))))"
+    );
+}
+
+#[test]
+fn factorial() {
+    // From `experiments/pseudocode-design/factorial.svm`, but with an `end`
+    // after the body that starts on its own line, as in `tour.cal`.
+    assert_eq!(
+        parse_stmts(
+            r"
+pub fn factorial_iter(n: uint): uint ->
+    1.to(n).product()
+end
+
+pub fn factorial_iter_loop(n: uint): uint -> do
+    let mut res = 1
+
+    if n == 0 -> ret 1
+    
+    for i in 1.to(n) -> res *= i
+
+    res
+end
+"
+        ),
+        "(block (pub (fn factorial_iter ((: n uint)) uint (do (call (. (call (. 1 to) n) product))))) \
+         (pub (fn factorial_iter_loop ((: n uint)) uint \
+         (do (let (mut res) 1) (if (== n 0) (ret 1)) (for i (call (. 1 to) n) (do (*= res i))) res))))"
+    );
+}
+
+#[test]
+fn compound_assignment() {
+    assert_eq!(
+        parse_stmts("a += 1\nb **= 2\nc <<= d >> 1"),
+        "(block (+= a 1) (**= b 2) (<<= c (>> d 1)))"
+    );
+}
+
+/// Parse a program that is expected to be invalid, returning the code,
+/// message and notes of the first syntax error.
+fn parse_error(source: &str) -> (String, String, Vec<String>) {
+    let gcx = new_gcx();
+    let _ = parse_program(0, source, &gcx);
+
+    let reports = gcx.grcx.read();
+    let diag = &reports.errors()[0].diagnostics()[0];
+    (
+        diag.code().unwrap().to_string(),
+        diag.message().to_string(),
+        diag.notes().to_vec(),
+    )
+}
+
+#[test]
+fn parse_errors() {
+    let (code, message, notes) = parse_error("(1 + 2");
+    assert_eq!((&*code, &*message), ("E0058", "Expected `)`, found EOF."));
+    assert_eq!(notes, vec!["help: add a `)` to close the unclosed `(`"]);
+
+    let (code, message, notes) = parse_error("1 + 2)");
+    assert_eq!(
+        (&*code, &*message),
+        (
+            "E0057",
+            "Expected a newline or the end of the input, found `)`."
+        )
+    );
+    assert_eq!(notes, vec!["help: this `)` does not have a matching `(`"]);
+
+    let (code, message, _) = parse_error("(1 * )");
+    assert_eq!(
+        (&*code, &*message),
+        ("E0057", "Expected an expression, found `)`.")
+    );
+
+    // Closing delimiters are only expected if something is open.
+    let (_, message, notes) = parse_error("1 2");
+    assert_eq!(
+        message,
+        "Expected a newline, an operator, `(`, `.` or the end of the input, found the number `2`."
+    );
+    assert_eq!(
+        notes,
+        vec!["help: there may be an operator missing before this"]
+    );
+    let (_, message, _) = parse_error(r#""${1 2}""#);
+    assert_eq!(
+        message,
+        "Expected an operator, `(`, `.` or `}`, found the number `2`."
+    );
+
+    let (_, _, notes) = parse_error("1 = 2");
+    assert_eq!(
+        notes,
+        vec!["help: use `==` to check if two values are equal"]
+    );
+
+    let (code, message, notes) = parse_error("do ->\n  let x = 1\n");
+    assert_eq!((&*code, &*message), ("E0058", "Expected `end`, found EOF."));
+    assert_eq!(notes, vec!["help: add an `end` to close the `do` block"]);
+
+    let (_, message, notes) = parse_error("case x do\n  0 -> 1\n");
+    assert_eq!(message, "Expected a newline, `,` or `end`, found EOF.");
+    assert_eq!(
+        notes,
+        vec!["help: add an `end` to close the `case` expression"]
+    );
+    let (_, message, _) = parse_error("case x do 0 1 -> 2 end");
+    assert_eq!(
+        message,
+        "Expected `,`, `->`, `where` or `|`, found the number `1`."
+    );
+    let (_, message, _) = parse_error("case x do n where n > 1 2 end");
+    assert_eq!(
+        message,
+        "Expected an operator, `(`, `->` or `.`, found the number `2`."
+    );
+}
+
+#[test]
+fn control_flow_errors() {
+    let (_, message, notes) = parse_error("if c do\n  a\nelse\n  b\n");
+    assert_eq!(message, "Expected `end`, found EOF.");
+    assert_eq!(
+        notes,
+        vec!["help: add an `end` to close the `if` expression"]
+    );
+    let (_, message, _) = parse_error("if c 1");
+    assert_eq!(
+        message,
+        "Expected an operator, `(`, `->`, `.` or `do`, found the number `1`."
+    );
+    let (_, message, _) = parse_error("for x xs -> x");
+    assert_eq!(
+        message,
+        "Expected `(`, `in` or `|`, found the identifier `xs`."
+    );
+
+    let (_, message, notes) = parse_error("if a -> 1 else");
+    assert_eq!(message, "Expected `->`, `do` or `if`, found EOF.");
+    assert!(notes.is_empty());
+    let (_, message, _) = parse_error("if a -> 1 else 2");
+    assert_eq!(
+        message,
+        "Expected `->`, `do` or `if`, found the number `2`."
+    );
+    let (_, message, notes) = parse_error("if a -> 1 else do\n  2\n");
+    assert_eq!(message, "Expected `end`, found EOF.");
+    assert_eq!(notes, vec!["help: add an `end` to close the `do` block"]);
+    let (_, message, _) = parse_error("if a -> x y");
+    assert_eq!(
+        message,
+        "Expected a newline, an operator, an assignment operator, `(`, `.`, `else` or the end \
+         of the input, found the identifier `y`."
+    );
+
+    for source in &[
+        "if a ->\n  b\n",
+        "if a ->\n  b\nelse ->\n  c\n",
+        "if a ->\n  b\nelse if c ->\n  d\n",
+    ] {
+        let (_, message, notes) = parse_error(source);
+        assert_eq!(message, "Expected `end`, found EOF.");
+        assert_eq!(
+            notes,
+            vec!["help: add an `end` to close the `if` expression"]
+        );
+    }
+    let (_, message, _) = parse_error("if a ->\n  b\nelse 1");
+    assert_eq!(message, "Expected `->` or `if`, found the number `1`.");
+    // A single `end` closes a whole `else if` chain.
+    let (_, message, notes) = parse_error("if a ->\n  b\nelse if c ->\n  d\nend\n)");
+    assert_eq!(
+        message,
+        "Expected an expression, `fn`, `let` or `pub`, found `)`."
+    );
+    assert_eq!(notes, vec!["help: this `)` does not have a matching `(`"]);
+
+    for source in &[
+        "loop ->\n  a\n",
+        "while a ->\n  b\n",
+        "for x in xs ->\n  b\n",
+    ] {
+        let (_, message, notes) = parse_error(source);
+        assert_eq!(message, "Expected `end`, found EOF.");
+        assert_eq!(notes, vec!["help: add an `end` to close the loop"]);
+    }
+    let (_, message, _) = parse_error("case x do\n  0 ->\n    1\n  1 2\nend");
+    assert_eq!(
+        message,
+        "Expected `,`, `->`, `where` or `|`, found the number `2`."
+    );
+
+    let (_, message, _) = parse_error("a.");
+    assert_eq!(message, "Expected an identifier, found EOF.");
+    let (_, message, _) = parse_error("(a += 1)");
+    assert_eq!(
+        message,
+        "Expected an operator, `(`, `)` or `.`, found `+=`."
+    );
+    let (_, message, _) = parse_error("f(a += 1)");
+    assert_eq!(
+        message,
+        "Expected an operator, `(`, `)`, `,` or `.`, found `+=`."
+    );
+    let (_, message, _) = parse_error("a += ");
+    assert_eq!(message, "Expected an expression, found EOF.");
+}
+
+#[test]
+fn number_values() {
+    // Only a prefix `-` negates a literal.
+    assert_eq!(parse("-9223372036854775808s"), "(- 9223372036854775808s)");
+    assert_eq!(parse("2 - 5u"), "(- 2 5u)");
+
+    let (code, _, notes) = parse_error("let x = -5u");
+    assert_eq!(code, "E0045");
+    assert_eq!(
+        notes,
+        vec!["help: perhaps you meant to use a `sint` literal: `-5s`?"]
+    );
+    let (code, message, _) = parse_error("18446744073709551616");
+    assert_eq!(
+        (&*code, &*message),
+        ("E0044", "Number literal out of range for `uint`.")
+    );
+}
+
+/// Parse an expression with the LALRPOP or Pratt parser, recovering from
+/// syntax errors. Returns the pretty-printed expression and the codes of the
+/// syntax errors.
+fn parse_recovering(source: &str, use_pratt: bool) -> (String, Vec<String>) {
+    let gcx = new_gcx();
+    let expr = if use_pratt {
+        pratt::parse_expr(0, source, &gcx)
+    } else {
+        parse_expr(0, source, &gcx).unwrap()
+    };
+    let mut printer = PrettyPrinter::default();
+    printer.visit_expr(&expr).unwrap();
+
+    let reports = gcx.grcx.read();
+    assert!(reports.fatal().is_none());
+    let codes = reports
+        .errors()
+        .iter()
+        .map(|diag| diag.diagnostics()[0].code().unwrap().to_string())
+        .collect();
+    (printer.to_string(), codes)
+}
+
+#[test]
+fn error_recovery() {
+    assert_eq!(
+        parse_recovering("(1 * ) + (2 / ) + 3", false),
+        (
+            "(+ (+ (* 1 <error>) (/ 2 <error>)) 3)".to_string(),
+            vec!["E0057".to_string(), "E0057".to_string()]
+        )
+    );
+    assert_eq!(
+        parse_recovering("(1 2 3) * 4 + ) - 5", false),
+        (
+            "(- (+ (* <error> 4) <error>) 5)".to_string(),
+            vec!["E0057".to_string(), "E0057".to_string()]
+        )
+    );
+    assert_eq!(
+        parse_recovering("1 +", false),
+        ("(+ 1 <error>)".to_string(), vec!["E0058".to_string()])
+    );
+    assert_eq!(
+        parse_recovering(r#""a ${1 2} b" + 1"#, false),
+        (
+            r#"(+ "a ${<error>} b" 1)"#.to_string(),
+            vec!["E0057".to_string()]
+        )
+    );
+
+    // The error node covers the tokens that were skipped.
+    let expr = parse_expr(0, "1 + (2 3)", &new_gcx()).unwrap();
+    match expr.kind {
+        ExprKind::BinOp(_, _, rhs) => {
+            assert_eq!((rhs.kind, rhs.span), (ExprKind::Error, Span::new(5, 8)));
+        }
+        kind => panic!("expected a binary operation, found {:?}", kind),
+    }
+}
+
+#[test]
+fn pratt_error_recovery() {
+    assert_eq!(
+        parse_recovering("(1 2 3) * 4 + ) - 5", true),
+        (
+            "(- (+ (* <error> 4) <error>) 5)".to_string(),
+            vec!["E0057".to_string(), "E0057".to_string()]
+        )
+    );
+    assert_eq!(
+        parse_recovering("* 2 + (3", true),
+        (
+            "(+ (* <error> 2) 3)".to_string(),
+            vec!["E0057".to_string(), "E0058".to_string()]
+        )
+    );
+    assert_eq!(
+        parse_recovering("f(1 2, 3) + 4", true),
+        ("(+ (call f 1) 4)".to_string(), vec!["E0057".to_string()])
+    );
+    // Extra tokens are skipped, keeping the expression before them.
+    assert_eq!(
+        parse_recovering("1 + 2) 3", true),
+        ("(+ 1 2)".to_string(), vec!["E0057".to_string()])
+    );
+
+    // The Pratt parser knows exactly what it expected.
+    let gcx = new_gcx();
+    let _ = pratt::parse_expr(0, "(1 + 2", &gcx);
+    assert_eq!(
+        gcx.grcx.read().errors()[0].diagnostics()[0].message(),
+        "Expected an operator or `)`, found EOF."
+    );
+}
+
+#[test]
+fn pratt_statement_recovery() {
+    let recovering = |source| {
+        let gcx = new_gcx();
+        let block = pratt::parse_program(0, source, &gcx);
+        let mut printer = PrettyPrinter::default();
+        printer.visit_block(&block).unwrap();
+        let reports = gcx.grcx.read();
+        let messages = reports
+            .errors()
+            .iter()
+            .map(|diag| diag.diagnostics()[0].message().to_string())
+            .collect::<Vec<_>>();
+        (printer.to_string(), messages)
+    };
+
+    // Statements that could not be parsed are skipped up to the next line.
+    assert_eq!(
+        recovering("while x y\nz"),
+        (
+            "(block (while x <error>) z)".to_string(),
+            vec!["Expected `->` or `do`, found the identifier `y`.".to_string()]
+        )
+    );
+    assert_eq!(
+        recovering("let x 1\nlet = 2 )\ny"),
+        (
+            "(block (let x 1) (let <error> 2) y)".to_string(),
+            vec![
+                "Expected `=`, a newline or the end of the input, found the number `1`."
+                    .to_string(),
+                "Expected an expression, `_` or `mut`, found `=`.".to_string(),
+                "Expected an operator, a newline or the end of the input, found `)`.".to_string()
+            ]
+        )
+    );
+    assert_eq!(
+        recovering("fn (x) -> 1\nf(1 2)"),
+        (
+            "(block <error> (call f 1))".to_string(),
+            vec![
+                "Expected an identifier, found `(`.".to_string(),
+                "Expected an operator, `)` or `,`, found the number `2`.".to_string()
+            ]
+        )
+    );
+    // Blocks are closed at their `end`, even after a syntax error.
+    assert_eq!(
+        recovering("case x do\n  0 -> 1\n  -> 2\nend\nx"),
+        (
+            "(block (case (x) (arm (0) 1) (arm (<error>) 2)) x)".to_string(),
+            vec!["Expected an expression, `_` or `mut`, found `->`.".to_string()]
+        )
+    );
+    assert_eq!(
+        recovering("loop -> ) + 1\nx"),
+        (
+            "(block (loop (+ <error> 1)) x)".to_string(),
+            vec!["Expected an expression, found `)`.".to_string()]
+        )
+    );
+    assert_eq!(
+        recovering("if a ->\n  b else ->\n  c\nend"),
+        (
+            "(block (if a (do b) (do c)))".to_string(),
+            vec!["Expected a newline, found the keyword `else`.".to_string()]
+        )
+    );
+    assert_eq!(
+        recovering("do ->\n  1 +\n  ) 2\nend\nx"),
+        (
+            "(block (do (+ 1 <error>)) x)".to_string(),
+            vec!["Expected an expression, found `)`.".to_string()]
+        )
+    );
+}
+
+/// Process the tokens of the source, returning the lexemes that were kept.
+fn filtered(source: &str) -> Vec<&str> {
+    let lexer = Lexer::new(0, source, new_gcx());
+    process_iter(lexer.into_iter(), true)
+        .map(|(lo, _, hi)| &source[lo..hi])
+        .collect()
+}
+
+#[test]
+fn significant_newlines() {
+    assert_eq!(filtered("a\nb"), vec!["a", "\n", "b"]);
+    assert_eq!(filtered("a\n  |> b"), vec!["a", "\n", "|>", "b"]);
+    assert_eq!(filtered("\n\na // c\r\n\n  b\n"), vec!["a", "\r\n", "b"]);
+    assert_eq!(filtered("a /* \n */ b"), vec!["a", "b"]);
+    assert_eq!(filtered("a ->\n\n  b"), vec!["a", "->", "\n", "b"]);
+    assert_eq!(
+        filtered("#!/usr/bin/env calypso\na\nb"),
+        vec!["a", "\n", "b"]
+    );
+}
+
+#[test]
+fn insignificant_newlines() {
+    assert_eq!(filtered("a +\n b"), vec!["a", "+", "b"]);
+    assert_eq!(filtered("a\n  .b\n  .c"), vec!["a", ".", "b", ".", "c"]);
+    assert_eq!(filtered("(a\n,\nb\n)"), vec!["(", "a", ",", "b", ")"]);
+    assert_eq!(filtered("\"${a\n}\""), vec!["\"", "${", "a", "}", "\""]);
+    assert_eq!(
+        filtered("-> do\n  a\nend"),
+        vec!["->", "do", "a", "\n", "end"]
+    );
+    assert_eq!(filtered("else\n  a\nend"), vec!["else", "a", "\n", "end"]);
+    assert_eq!(parse("(1\n+ 2)\n"), "(+ 1 2)");
+}
+
+#[test]
+fn keeps_everything_unfiltered() {
+    let source = "a /* b */ // c\n\n";
+    let lexer = Lexer::new(0, source, new_gcx());
+    let toks = process_iter(lexer.into_iter(), false)
+        .map(|tok| tok.1)
+        .collect::<Vec<_>>();
+    assert_eq!(toks.len(), 7);
+    assert_eq!(toks[1], Tok::Unprocessed(TokenType::Ws));
+    assert!(matches!(
+        toks[4],
+        Tok::Unprocessed(TokenType::LineComment { .. })
+    ));
+    assert_eq!(toks[6], Tok::Unprocessed(TokenType::Newline));
+}
+
+#[test]
+fn raw_and_multiline_strings() {
+    // Strings are printed as normal string literals, with their values
+    // escaped.
+    assert_eq!(parse(r##"r#"a "b" \c"#"##), r#""a \"b\" \\c""#);
+    assert_eq!(parse("\"\"\"\n  a\n    b\n  \"\"\""), r#""a\n  b""#);
+    assert_eq!(parse(r#"r"a" == "a""#), r#"(== "a" "a")"#);
+}
+
+#[test]
+fn escapes() {
+    let expr = parse_expr(0, r#""\x41\u{1F600}\t${x}""#, &new_gcx()).unwrap();
+    assert!(matches!(
+        &expr.kind,
+        ExprKind::Interpolated(parts)
+            if matches!(&parts[0], StringPart::Fragment(s) if s.as_ref() == "A\u{1F600}\t")
+    ));
+    assert_eq!(parse(r#":"a\"b" == "\${""#), r#"(== :"a\"b" "\${")"#);
+
+    let (code, _, _) = parse_error(r#""\u{D800}""#);
+    assert_eq!(code, "E0039");
+}
+
+#[test]
+fn byte_literals() {
+    // Byte strings and bytes are printed with their values escaped.
+    assert_eq!(
+        parse(r#"b"\x7FELF" != b'\xff'"#),
+        r#"(!= b"\x7FELF" b'\xFF')"#
+    );
+    assert_eq!(parse(r#"b"\x41\"'\n" == b'\''"#), r#"(== b"A\"'\n" b'\'')"#);
+}
+
+#[test]
+fn char_literals() {
+    assert_eq!(parse(r"'a' != '\u{1F600}'"), "(!= 'a' '\u{1F600}')");
+    assert_eq!(parse(r#"'\'' == '"'"#), r#"(== '\'' '"')"#);
+}
+
+#[test]
+fn numbers() {
+    assert_eq!(parse("0xffu + 5s + 0u"), "(+ (+ 0xffu 5s) 0u)");
+    assert_eq!(parse("1_0f * 1.5e3"), "(* 1_0f 1.5e3)");
+}
+
+#[test]
+fn spans() {
+    let source = "(1 + -20) ** 3";
+    let expr = parse_expr(7, source, &new_gcx()).unwrap();
+    assert_eq!((expr.span, expr.file_id), (Span::new(0, 14), 7));
+
+    let (lhs, op, rhs) = match expr.kind {
+        ExprKind::BinOp(lhs, op, rhs) => (lhs, op, rhs),
+        kind => panic!("expected a binary operation, found {:?}", kind),
+    };
+    assert_eq!(
+        (op.span(), op.value()),
+        (Span::new(10, 12), &BinOpKind::Exponent)
+    );
+    // The parentheses aren't part of the inner expression.
+    assert_eq!(lhs.span, Span::new(1, 8));
+    assert_eq!(rhs.span, Span::new(13, 14));
+
+    match lhs.kind {
+        ExprKind::BinOp(_, op, neg) => {
+            assert_eq!(op.span(), Span::new(3, 4));
+            assert_eq!(neg.span, Span::new(5, 8));
+        }
+        kind => panic!("expected a binary operation, found {:?}", kind),
+    }
+}
+
+const BINOPS: &[&str] = &[
+    "+", "-", "*", "/", "%", "**", "||", "&&", "|", "^", "&", "<<", ">>", "==", "!=", "<", ">",
+    "<=", ">=",
+];
+const UNOPS: &[&str] = &["-", "!"];
+
+/// Generate a literal, which is pretty-printed as-is.
+fn literal() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (0_u32..=255, select(&["", "s", "u", "f"][..]))
+            .prop_map(|(n, suffix)| format!("{}{}", n, suffix)),
+        (0_u32..1000, 0_u32..1000).prop_map(|(int, frac)| format!("{}.{}", int, frac)),
+        any::<bool>().prop_map(|b| b.to_string()),
+        "[a-z][a-z0-9_]{0,8}".prop_map(|name| format!(":{}", name)),
+        "[a-z ]{0,8}".prop_map(|text| format!("\"{}\"", text)),
+    ]
+}
+
+/// Generate a well-formed expression, along with how it should be
+/// pretty-printed. Operands are always parenthesized, so that precedence
+/// doesn't need to be taken into account.
+fn expr() -> impl Strategy<Value = (String, String)> {
+    let trivia = select(&["", " ", " /* c */ "][..]);
+    literal()
+        .prop_map(|lit| (lit.clone(), lit))
+        .prop_recursive(8, 64, 2, move |inner| {
+            prop_oneof![
+                (inner.clone(), select(BINOPS), inner.clone(), trivia.clone()).prop_map(
+                    |((lhs, lhs_pretty), op, (rhs, rhs_pretty), ws)| (
+                        format!("({}){}{}{}({})", lhs, ws, op, ws, rhs),
+                        format!("({} {} {})", op, lhs_pretty, rhs_pretty)
+                    )
+                ),
+                (select(UNOPS), inner).prop_map(|(op, (expr, pretty))| (
+                    format!("{}({})", op, expr),
+                    format!("({} {})", op, pretty)
+                )),
+            ]
+        })
+}
+
+/// Print an expression made up of literals and operators back to source,
+/// parenthesizing every operand.
+fn unparse(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::BinOp(lhs, op, rhs) => {
+            format!("({}) {} ({})", unparse(lhs), op.value(), unparse(rhs))
+        }
+        ExprKind::UnOp(op, expr) => format!("{}({})", op.value(), unparse(expr)),
+        // Literals are pretty-printed as they're written.
+        ExprKind::Primary(primary) => {
+            let mut printer = PrettyPrinter::default();
+            printer.visit_primary(primary).unwrap();
+            printer.to_string()
+        }
+        kind => panic!("expected a literal or an operator, found {:?}", kind),
+    }
+}
+
+proptest! {
+    #[test]
+    fn generated_exprs((source, pretty) in expr()) {
+        prop_assert_eq!(parse(&source), pretty.clone());
+
+        // Printing the expression and parsing it again gives the same
+        // expression, apart from its spans (which the pretty-printed form
+        // doesn't include).
+        let expr = parse_expr(0, &source, &new_gcx()).unwrap();
+        prop_assert_eq!(parse(&unparse(&expr)), pretty);
+    }
+}
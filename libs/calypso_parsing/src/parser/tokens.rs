@@ -1,8 +1,9 @@
 //! Processed versions of [`crate::lexer::TokenType`] for use with LALRPOP.
 
+use std::iter::Peekable;
+
 use calypso_ast::expr::{Radix, Suffix};
 use calypso_base::symbol::{kw::Keyword, PotentiallyInterned, Symbol};
-use calypso_diagnostic::prelude::*;
 
 use crate::lexer::{Token, TokenType};
 
@@ -18,13 +19,36 @@ pub enum Tok<'tok> {
     Keyword(Keyword),
     /// String literal
     String(PotentiallyInterned<'tok>),
+    /// Character literal
+    Char(char),
+    /// Byte string literal, without the prefix and the quotes. It's decoded
+    /// by the parser, as its value may not be valid UTF-8.
+    Bytes(&'tok str),
+    /// Byte literal
+    Byte(u8),
+    /// Text within an interpolated string literal
+    StringFragment(PotentiallyInterned<'tok>),
+    /// Atom
+    Atom(Symbol),
 }
 
-fn trim_number(string: &str, radix: Radix) -> &str {
-    if radix == Radix::None {
-        string
-    } else {
+/// Trim the radix prefix (e.g. `0x`) and valid suffix (e.g. `u`) from the
+/// lexeme of a number literal. Invalid suffixes are kept, so that they can be
+/// reported.
+pub(crate) fn trim_number(string: &str, radix: Radix, suffix: Option<Suffix>) -> &str {
+    // Octal literals with a leading zero (e.g. `0755`) don't have a prefix.
+    let string = if radix != Radix::None
+        && matches!(string.as_bytes().get(1), Some(ch) if ch.is_ascii_alphabetic())
+    {
         &string[2..]
+    } else {
+        string
+    };
+    match suffix {
+        Some(suffix) if suffix != Suffix::Invalid && suffix != Suffix::TrueFloat => {
+            &string[..string.len() - 1]
+        }
+        _ => string,
     }
 }
 
@@ -32,34 +56,162 @@ fn trim_number(string: &str, radix: Radix) -> &str {
 pub fn process(tok: Token) -> Tok {
     match tok.value_owned() {
         (TokenType::Int { suffix, radix }, string) => {
-            Tok::Number(trim_number(string, radix), radix, suffix)
+            Tok::Number(trim_number(string, radix, suffix), radix, suffix)
         }
         (TokenType::Float, string) => Tok::Number(
-            trim_number(string, Radix::None),
+            trim_number(string, Radix::None, None),
             Radix::None,
             Some(Suffix::TrueFloat),
         ),
         (TokenType::Ident(symbol), _) => Tok::Ident(symbol),
         (TokenType::Keyword(symbol), _) => Tok::Keyword(Keyword::from(symbol)),
-        (TokenType::String, string) => Tok::String(PotentiallyInterned::potentially_intern(string)),
+        (TokenType::Atom(symbol), _) => Tok::Atom(symbol),
+        (TokenType::String(value), _) | (TokenType::MultilineString(value), _) => {
+            Tok::String(PotentiallyInterned::Interned(value))
+        }
+        // Raw string literals don't have escapes to decode.
+        (TokenType::RawString { hashes }, string) => Tok::String(
+            PotentiallyInterned::potentially_intern(&string[hashes + 2..string.len() - hashes - 1]),
+        ),
+        (TokenType::Char(value), _) => Tok::Char(value),
+        (TokenType::ByteString, string) => Tok::Bytes(&string[2..string.len() - 1]),
+        (TokenType::Byte(value), _) => Tok::Byte(value),
+        (TokenType::StringFragment(value), _) => {
+            Tok::StringFragment(PotentiallyInterned::Interned(value))
+        }
         (tok, _) => Tok::Unprocessed(tok),
     }
 }
 
+/// Process an iterator of tokens for use with LALRPOP.
+///
+/// If `filter_ws` is `true`, whitespace and comments are removed, as are
+/// newlines that the grammar does not care about (i.e. where the EBNF uses
+/// `~~`). Newlines are kept only where they could end a statement, so they
+/// are removed:
+/// - within parentheses, brackets, curly brackets and string interpolations,
+/// - after a token that cannot end an expression (e.g. a binary operator, a
+///   comma, `do` or `else`), other than `->`,
+/// - before a `.` that continues an expression from the previous line,
+/// - at the start and end of the input, and when repeated.
 pub fn process_iter<'tok>(
-    iter: impl Iterator<Item = CalResult<Token<'tok>>> + 'tok,
+    iter: impl Iterator<Item = Token<'tok>> + 'tok,
     filter_ws: bool,
-) -> impl Iterator<Item = CalResult<(usize, Tok<'tok>, usize)>> + 'tok {
-    iter.map(|tok| tok.map(|tok| (tok.span().lo(), process(tok), tok.span().hi())))
-        .filter(move |tok| {
-            if filter_ws {
-                !matches!(
-                    tok,
-                    Ok((_, Tok::Unprocessed(TokenType::Ws), _))
-                        | Ok((_, Tok::Unprocessed(TokenType::BlockComment { .. }), _))
-                )
-            } else {
-                true
+) -> impl Iterator<Item = (usize, Tok<'tok>, usize)> + 'tok {
+    let iter = iter.map(|tok| (tok.span().lo(), process(tok), tok.span().hi()));
+    WsFilter {
+        iter: iter.peekable(),
+        filter_ws,
+        depth: 0,
+        continues: true,
+    }
+}
+
+struct WsFilter<'tok, I>
+where
+    I: Iterator<Item = (usize, Tok<'tok>, usize)>,
+{
+    iter: Peekable<I>,
+    filter_ws: bool,
+    /// How deeply nested in brackets we are.
+    depth: usize,
+    /// Whether the last token could not end an expression.
+    continues: bool,
+}
+
+impl<'tok, I> WsFilter<'tok, I>
+where
+    I: Iterator<Item = (usize, Tok<'tok>, usize)>,
+{
+    /// Skip whitespace, comments and newlines, returning `true` if the next
+    /// token is a `.` that continues an expression from the previous line,
+    /// or if there is no next token.
+    fn skip_to_continuation(&mut self) -> bool {
+        while let Some((_, tok, _)) = self.iter.peek() {
+            if !is_trivia(*tok) && *tok != Tok::Unprocessed(TokenType::Newline) {
+                break;
             }
-        })
+            self.iter.next();
+        }
+        match self.iter.peek() {
+            Some((_, tok, _)) => *tok == Tok::Unprocessed(TokenType::Dot),
+            None => true,
+        }
+    }
+}
+
+impl<'tok, I> Iterator for WsFilter<'tok, I>
+where
+    I: Iterator<Item = (usize, Tok<'tok>, usize)>,
+{
+    type Item = (usize, Tok<'tok>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.filter_ws {
+            return self.iter.next();
+        }
+
+        loop {
+            let tok = self.iter.next()?;
+            match tok.1 {
+                tok if is_trivia(tok) => continue,
+                Tok::Unprocessed(TokenType::Newline) => {
+                    if self.depth > 0 || self.continues || self.skip_to_continuation() {
+                        continue;
+                    }
+                    self.continues = true;
+                }
+                Tok::Unprocessed(ty) => {
+                    match ty {
+                        TokenType::LParen
+                        | TokenType::LBracket
+                        | TokenType::LBrace
+                        | TokenType::InterpStart => self.depth += 1,
+                        TokenType::RParen
+                        | TokenType::RBracket
+                        | TokenType::RBrace
+                        | TokenType::InterpEnd => self.depth = self.depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    // A newline after `->` is kept, so that a block on
+                    // the following lines can be told apart from a single
+                    // statement.
+                    self.continues = ty != TokenType::Arrow && continues_expr(ty);
+                }
+                // A block (e.g. a function body after `-> do`) may start on
+                // the next line.
+                Tok::Keyword(Keyword::Do) | Tok::Keyword(Keyword::Else) => self.continues = true,
+                _ => self.continues = false,
+            }
+            return Some(tok);
+        }
+    }
+}
+
+fn is_trivia(tok: Tok) -> bool {
+    matches!(
+        tok,
+        Tok::Unprocessed(TokenType::Ws)
+            | Tok::Unprocessed(TokenType::LineComment { .. })
+            | Tok::Unprocessed(TokenType::BlockComment { .. })
+            | Tok::Unprocessed(TokenType::Shebang)
+    )
+}
+
+/// Does an expression continue onto the next line after this token (i.e. is
+/// this a token that can't end an expression)?
+///
+/// Only tokens that aren't processed into a different [`Tok`] are handled
+/// here, as the only processed tokens that continue an expression are `do`
+/// and `else`.
+pub(crate) fn continues_expr(ty: TokenType) -> bool {
+    !matches!(
+        ty,
+        TokenType::RParen
+            | TokenType::RBracket
+            | TokenType::RBrace
+            | TokenType::Under
+            | TokenType::StringEnd
+            | TokenType::Unexpected
+    )
 }
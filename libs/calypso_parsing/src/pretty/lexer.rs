@@ -1,9 +1,15 @@
-use super::Printer;
-use crate::lexer::{Token, TokenType};
-
+use calypso_ast::expr::Suffix;
+use calypso_diagnostic::diagnostic::{EnsembleDiagnostic, LabelStyle, Severity};
 use calypso_diagnostic::prelude::*;
 use calypso_diagnostic::reporting::files::Files;
 
+use super::record::{
+    DiagnosticRecord, LabelRecord, Location, Payload, Record, SpanRecord, TokenRecord,
+};
+use super::Printer;
+use crate::cst::SyntaxKind;
+use crate::lexer::{Token, TokenType};
+
 impl Printer {
     /// Print a token
     ///
@@ -41,4 +47,123 @@ impl Printer {
             value.0,
         ))
     }
+
+    /// Get a machine-readable record of a token.
+    ///
+    /// # Errors
+    ///
+    /// This function errors when it can't get the line/col location of a token from the byte span.
+    pub fn token_record(&self, tok: &Token<'_>) -> CalResult<Record> {
+        let (ty, lexeme) = tok.value_owned();
+        let payload = match ty {
+            TokenType::Ident(symbol) | TokenType::Keyword(symbol) | TokenType::Atom(symbol) => {
+                Some(Payload::Symbol {
+                    symbol: symbol.to_string(),
+                })
+            }
+            TokenType::Int { suffix, radix } => Some(Payload::Int {
+                suffix: suffix.map(|suffix| match suffix {
+                    Suffix::Invalid => "invalid".to_string(),
+                    suffix => suffix.to_string(),
+                }),
+                radix: radix.radix(),
+            }),
+            TokenType::RawString { hashes } => Some(Payload::RawString { hashes }),
+            TokenType::LineComment { doc, inner } => Some(Payload::Comment {
+                doc,
+                inner,
+                nest_level: None,
+            }),
+            TokenType::BlockComment {
+                doc,
+                inner,
+                nest_level,
+            } => Some(Payload::Comment {
+                doc,
+                inner,
+                nest_level: Some(nest_level),
+            }),
+            _ => None,
+        };
+        let ty = match ty {
+            // `SyntaxKind` has no kind for EOF, as it never ends up in a CST.
+            TokenType::Eof => "Eof".to_string(),
+            ty => format!("{:?}", SyntaxKind::from(ty)),
+        };
+
+        Ok(Record::Token(TokenRecord {
+            ty,
+            payload,
+            lexeme: lexeme.to_string(),
+            span: self.span_record(self.file_id, tok.span().lo(), tok.span().hi())?,
+        }))
+    }
+
+    /// Get machine-readable records of each diagnostic in an ensemble.
+    ///
+    /// # Errors
+    ///
+    /// This function errors when it can't get the line/col location of a label from the byte span.
+    pub fn diagnostic_records(&self, diag: &EnsembleDiagnostic) -> CalResult<Vec<Record>> {
+        diag.diagnostics()
+            .iter()
+            .map(|diag| {
+                let labels = diag
+                    .labels()
+                    .iter()
+                    .map(|label| {
+                        Ok(LabelRecord {
+                            style: match label.style {
+                                LabelStyle::Primary => "primary",
+                                LabelStyle::Secondary => "secondary",
+                            },
+                            message: label.message.clone(),
+                            span: self.span_record(
+                                label.file_id,
+                                label.range.start,
+                                label.range.end,
+                            )?,
+                        })
+                    })
+                    .collect::<CalResult<Vec<_>>>()?;
+
+                Ok(Record::Diagnostic(DiagnosticRecord {
+                    severity: match diag.severity() {
+                        Severity::Bug => "bug",
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                        Severity::Note => "note",
+                        Severity::Help => "help",
+                    },
+                    code: diag.code().map(ToString::to_string),
+                    message: diag.message().to_string(),
+                    labels,
+                    notes: diag.notes().to_vec(),
+                }))
+            })
+            .collect()
+    }
+
+    fn span_record(&self, file_id: usize, lo: usize, hi: usize) -> CalResult<SpanRecord> {
+        let sourcemgr = self.gcx.sourcemgr.read();
+        let lo_loc = sourcemgr
+            .location(file_id, lo)
+            .map_err(DiagnosticError::from)?;
+        let hi_loc = sourcemgr
+            .location(file_id, hi)
+            .map_err(DiagnosticError::from)?;
+
+        Ok(SpanRecord {
+            lo,
+            hi,
+            start: Location {
+                line: lo_loc.line_number,
+                column: lo_loc.column_number,
+            },
+            end: Location {
+                line: hi_loc.line_number,
+                column: hi_loc.column_number,
+            },
+        })
+    }
 }
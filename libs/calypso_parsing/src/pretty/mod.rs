@@ -3,6 +3,7 @@ use std::sync::Arc;
 use calypso_common::gcx::GlobalCtxt;
 
 pub mod lexer;
+pub mod record;
 
 // note(@ThePuzzlemaker: frame): This may be changed into a visitor API once I get an AST working.
 pub struct Printer {
@@ -16,3 +17,6 @@ impl Printer {
         Self { file_id, gcx }
     }
 }
+
+#[cfg(test)]
+mod tests;
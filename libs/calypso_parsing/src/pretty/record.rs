@@ -0,0 +1,233 @@
+//! Machine-readable records of tokens and diagnostics, for tools that need to
+//! consume the output of the lexer.
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use calypso_diagnostic::prelude::*;
+
+/// Output formats for tokens and diagnostics.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// A human-readable format, with diagnostics rendered separately.
+    Human,
+    /// A single JSON array of records.
+    Json,
+    /// One JSON record per line.
+    JsonLines,
+    /// One S-expression record per line.
+    Sexpr,
+}
+
+impl Format {
+    /// The names of the formats, as accepted by [`Format::from_str`].
+    pub const NAMES: &'static [&'static str] = &["human", "json", "jsonl", "sexpr"];
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::JsonLines),
+            "sexpr" => Ok(Self::Sexpr),
+            _ => Err(format!("unknown format `{}`", s)),
+        }
+    }
+}
+
+/// A record in the output stream.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    Token(TokenRecord),
+    Diagnostic(DiagnosticRecord),
+}
+
+/// A token, with its payload, lexeme and span.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TokenRecord {
+    /// The type of the token, without its payload.
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// The payload of the token, if its type has one.
+    pub payload: Option<Payload>,
+    pub lexeme: String,
+    pub span: SpanRecord,
+}
+
+/// The payload of a token.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Payload {
+    /// The symbol of an identifier, keyword or atom.
+    Symbol { symbol: String },
+    /// The suffix and radix of an integer literal. The suffix is `"invalid"`
+    /// if it was not a valid suffix.
+    Int { suffix: Option<String>, radix: u32 },
+    /// The number of `#`s around the quotes of a raw string literal.
+    RawString { hashes: usize },
+    /// The kind of a comment. `nest_level` is only present for block
+    /// comments.
+    Comment {
+        doc: bool,
+        inner: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        nest_level: Option<usize>,
+    },
+}
+
+/// A span, in bytes and in 1-indexed lines and columns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SpanRecord {
+    pub lo: usize,
+    pub hi: usize,
+    pub start: Location,
+    pub end: Location,
+}
+
+/// A 1-indexed line and column.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A diagnostic, with its labels and notes.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DiagnosticRecord {
+    /// One of `bug`, `error`, `warning`, `note` or `help`.
+    pub severity: &'static str,
+    pub code: Option<String>,
+    pub message: String,
+    pub labels: Vec<LabelRecord>,
+    pub notes: Vec<String>,
+}
+
+/// A label of a diagnostic.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LabelRecord {
+    /// Either `primary` or `secondary`.
+    pub style: &'static str,
+    pub message: String,
+    pub span: SpanRecord,
+}
+
+/// Print records in the given format, one record per line (or a single JSON
+/// array for [`Format::Json`]). [`Format::Human`] is not supported, as it
+/// does not have a record format.
+///
+/// # Errors
+///
+/// This function errors if the records could not be serialized to JSON.
+///
+/// # Panics
+///
+/// This function panics if `format` is [`Format::Human`].
+pub fn print_records(records: &[Record], format: Format) -> CalResult<String> {
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(records).map_err(anyhow::Error::from)?),
+        Format::JsonLines => Ok(records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(anyhow::Error::from)?
+            .join("\n")),
+        Format::Sexpr => Ok(records
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")),
+        Format::Human => panic!("the human-readable format has no records"),
+    }
+}
+
+/// Write a string as a quoted, escaped literal.
+fn quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    // Strings can always be serialized, and JSON escapes are easy to read
+    // back in.
+    write!(f, "{}", serde_json::to_string(s).map_err(|_| fmt::Error)?)
+}
+
+/// Print the record as an S-expression.
+impl Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token(token) => token.fmt(f),
+            Self::Diagnostic(diag) => diag.fmt(f),
+        }
+    }
+}
+
+impl Display for TokenRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(token (type {})", self.ty)?;
+        match &self.payload {
+            Some(Payload::Symbol { symbol }) => {
+                write!(f, " (symbol ")?;
+                quoted(f, symbol)?;
+                write!(f, ")")?;
+            }
+            Some(Payload::Int { suffix, radix }) => {
+                match suffix {
+                    Some(suffix) => write!(f, " (suffix {})", suffix)?,
+                    None => write!(f, " (suffix nil)")?,
+                }
+                write!(f, " (radix {})", radix)?;
+            }
+            Some(Payload::RawString { hashes }) => write!(f, " (hashes {})", hashes)?,
+            Some(Payload::Comment {
+                doc,
+                inner,
+                nest_level,
+            }) => {
+                write!(f, " (doc {}) (inner {})", doc, inner)?;
+                if let Some(nest_level) = nest_level {
+                    write!(f, " (nest_level {})", nest_level)?;
+                }
+            }
+            None => {}
+        }
+        write!(f, " (lexeme ")?;
+        quoted(f, &self.lexeme)?;
+        write!(f, ") {})", self.span)
+    }
+}
+
+impl Display for SpanRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(span {} {} (start {} {}) (end {} {}))",
+            self.lo, self.hi, self.start.line, self.start.column, self.end.line, self.end.column
+        )
+    }
+}
+
+impl Display for DiagnosticRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(diagnostic (severity {})", self.severity)?;
+        match &self.code {
+            Some(code) => write!(f, " (code {})", code)?,
+            None => write!(f, " (code nil)")?,
+        }
+        write!(f, " (message ")?;
+        quoted(f, &self.message)?;
+        write!(f, ") (labels")?;
+        for label in &self.labels {
+            write!(f, " (label (style {}) (message ", label.style)?;
+            quoted(f, &label.message)?;
+            write!(f, ") {})", label.span)?;
+        }
+        write!(f, ") (notes")?;
+        for note in &self.notes {
+            write!(f, " ")?;
+            quoted(f, note)?;
+        }
+        write!(f, "))")
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use calypso_base::ui::{termcolor::ColorChoice, Emitters};
+use calypso_common::{gcx::GlobalCtxt, parking_lot::RwLock};
+use calypso_diagnostic::{diagnostic::SourceMgr, report::GlobalReportingCtxt};
+
+use super::record::{print_records, Format, Payload, Record};
+use super::Printer;
+use crate::lexer::Lexer;
+
+fn new_gcx() -> Arc<GlobalCtxt> {
+    Arc::new(GlobalCtxt {
+        emit: RwLock::new(Emitters::new(ColorChoice::Never, ColorChoice::Never)),
+        grcx: RwLock::new(GlobalReportingCtxt::new()),
+        sourcemgr: RwLock::new(SourceMgr::new()),
+    })
+}
+
+/// Lex the source, returning the records of all tokens (except EOF) followed
+/// by the records of all synchronizable errors.
+fn records(source: &str) -> Vec<Record> {
+    let gcx = new_gcx();
+    let file_id = gcx
+        .sourcemgr
+        .write()
+        .add("<test>".to_string(), source.to_string());
+    let printer = Printer::new(file_id, Arc::clone(&gcx));
+    let mut records = Lexer::new(file_id, source, Arc::clone(&gcx))
+        .into_iter()
+        .map(|tok| printer.token_record(&tok).unwrap())
+        .collect::<Vec<_>>();
+    for diag in gcx.grcx.read().errors() {
+        records.extend(printer.diagnostic_records(diag).unwrap());
+    }
+    records
+}
+
+#[test]
+fn token_payloads() {
+    let records = records("foo 0x1fu /** a /* b */ */\n");
+    let payloads = records
+        .iter()
+        .filter_map(|record| match record {
+            Record::Token(token) if token.ty != "Ws" => Some((token.ty.as_str(), &token.payload)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        payloads,
+        vec![
+            (
+                "Ident",
+                &Some(Payload::Symbol {
+                    symbol: "foo".to_string()
+                })
+            ),
+            (
+                "Int",
+                &Some(Payload::Int {
+                    suffix: Some("u".to_string()),
+                    radix: 16
+                })
+            ),
+            (
+                "BlockComment",
+                &Some(Payload::Comment {
+                    doc: true,
+                    inner: false,
+                    nest_level: Some(1)
+                })
+            ),
+            ("Newline", &None),
+        ]
+    );
+}
+
+#[test]
+fn json_lines() {
+    let records = records("a\n  :b");
+    assert_eq!(
+        print_records(&records[2..3], Format::JsonLines).unwrap(),
+        r#"{"kind":"token","type":"Ws","payload":null,"lexeme":"  ","span":{"lo":2,"hi":4,"start":{"line":2,"column":1},"end":{"line":2,"column":3}}}"#
+    );
+}
+
+#[test]
+fn sexpr() {
+    let records = records(r#""\q""#);
+    assert_eq!(
+        print_records(&records, Format::Sexpr).unwrap(),
+        [
+            r#"(token (type String) (lexeme "\"\\q\"") (span 0 4 (start 1 1) (end 1 5)))"#,
+            r#"(diagnostic (severity error) (code E0006) (message "Expected a valid escape sequence, found `\\q`.") (labels (label (style primary) (message "this escape sequence is invalid") (span 1 3 (start 1 2) (end 1 4)))) (notes))"#,
+        ]
+        .join("\n")
+    );
+}
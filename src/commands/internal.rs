@@ -1,3 +1,4 @@
+use std::fmt;
 use std::fs;
 use std::io::{self, prelude::*};
 use std::path::Path;
@@ -6,11 +7,14 @@ use std::sync::Arc;
 use calypso_repl::Repl;
 use clap::ArgMatches;
 
+use calypso_ast::{pretty::PrettyPrinter, traverse::Visitor};
 use calypso_base::ui::termcolor::{Color, ColorSpec, WriteColor};
 use calypso_common::gcx::GlobalCtxt;
 use calypso_diagnostic::prelude::*;
 use calypso_diagnostic::reporting::files::Files;
 use calypso_parsing::lexer::{Lexer, TokenType};
+use calypso_parsing::parser::{parse_program, pratt};
+use calypso_parsing::pretty::record::{self, Format};
 use calypso_parsing::pretty::Printer;
 // use calypso_repl::Repl;
 
@@ -20,6 +24,7 @@ use crate::buildinfo::BUILD_INFO;
 pub fn internal(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
     match matches.subcommand() {
         ("lexer", Some(matches)) => lexer(gcx, matches),
+        ("parser", Some(matches)) => parser(gcx, matches),
         ("buildinfo", _) => buildinfo(gcx),
         ("panic", _) => panic!("Intentional panic to test ICE handling, please ignore."),
         _ => Ok(()),
@@ -28,13 +33,45 @@ pub fn internal(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
 
 pub fn lexer(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
     let ignore_ws = matches.is_present("ignore_ws");
+    // clap has already checked that this is one of the possible values.
+    let format = matches
+        .value_of("format")
+        .unwrap()
+        .parse::<Format>()
+        .unwrap();
     let path = matches.value_of("INPUT").unwrap();
 
-    let (file_name, contents) = if path == "-" {
-        if matches.is_present("repl") {
-            return lexer_repl(gcx, ignore_ws);
-        }
+    if path == "-" && matches.is_present("repl") {
+        return lexer_repl(gcx, ignore_ws, format);
+    }
+
+    match read_input(gcx, path)? {
+        Some((file_name, contents)) => run_lexer(gcx, ignore_ws, format, file_name, contents),
+        None => Ok(()),
+    }
+}
+
+pub fn parser(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
+    let raw = matches.is_present("raw");
+    let pratt = matches.is_present("pratt");
+    let path = matches.value_of("INPUT").unwrap();
 
+    if path == "-" && matches.is_present("repl") {
+        parser_repl(gcx, raw, pratt);
+        return Ok(());
+    }
+
+    match read_input(gcx, path)? {
+        Some((file_name, contents)) => run_parser(gcx, raw, pratt, file_name, contents),
+        None => Ok(()),
+    }
+}
+
+/// Read the input file, or standard input if the path is `-`, returning its
+/// name and contents. If it could not be read, an error is printed and
+/// `None` is returned.
+fn read_input(gcx: &Arc<GlobalCtxt>, path: &str) -> CalResult<Option<(String, String)>> {
+    Ok(Some(if path == "-" {
         let stdin = io::stdin();
         let mut contents = String::new();
         if let Err(err) = stdin.lock().read_to_string(&mut contents) {
@@ -44,7 +81,7 @@ pub fn lexer(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
                 .error(None, "while reading from stdin:", None)?
                 .error(None, &format!("{}", err), None)?
                 .flush()?;
-            return Ok(());
+            return Ok(None);
         }
 
         ("<stdin>".to_string(), contents)
@@ -60,7 +97,7 @@ pub fn lexer(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
                     Some(&format!("`{}`", path.display())),
                 )?
                 .flush()?;
-            return Ok(());
+            return Ok(None);
         }
 
         (
@@ -78,22 +115,24 @@ pub fn lexer(gcx: &Arc<GlobalCtxt>, matches: &ArgMatches) -> CalResult<()> {
                         )?
                         .error(None, &format!("{}", err), None)?
                         .flush()?;
-                    return Ok(());
+                    return Ok(None);
                 }
             },
         )
-    };
-
-    run_lexer(gcx, ignore_ws, file_name, contents)
+    }))
 }
 
 pub fn run_lexer(
     gcx: &Arc<GlobalCtxt>,
     ignore_ws: bool,
+    format: Format,
     file_name: String,
     contents: String,
 ) -> CalResult<()> {
     let file_id = gcx.sourcemgr.write().add(file_name, contents);
+    if format != Format::Human {
+        return run_lexer_records(gcx, ignore_ws, format, file_id);
+    }
 
     let sourcemgr = gcx.sourcemgr.read();
     let source = sourcemgr.source(file_id).unwrap();
@@ -103,37 +142,20 @@ pub fn run_lexer(
     let mut printer = Printer::new(file_id, Arc::clone(gcx));
     loop {
         let token = lexer.scan();
-        if let Err(err) = token {
-            let mut emit = gcx.emit.write();
-
-            emit.err.error(None, "while lexing input:", None)?;
-            if let Some(DiagnosticError::Diagnostic) = err.try_downcast_ref::<DiagnosticError>() {
-                let mut buf = emit.err.buffer();
-                gcx.grcx
-                    .read()
-                    .fatal()
-                    .unwrap()
-                    .render(&mut buf, &sourcemgr, None)?;
-                emit.err.emit(&buf)?;
-            } else {
-                emit.err.error(None, &format!("{}", err), None)?;
-            }
+        let token_ty = token.value().0;
+        if !ignore_ws || token_ty != TokenType::Ws {
+            tokens.push(token);
+        }
+        if token_ty == TokenType::Eof {
             break;
-        } else if let Ok(token) = token {
-            let token_ty = token.value().0;
-            if !ignore_ws || token_ty != TokenType::Ws {
-                tokens.push(token);
-            }
-            if token_ty == TokenType::Eof {
-                break;
-            }
         }
     }
 
-    gcx.grcx
-        .read()
+    let reports = gcx.grcx.read();
+    reports
         .errors()
         .iter()
+        .chain(reports.nonfatals().iter())
         .try_for_each(|e| -> CalResult<()> {
             let mut emit = gcx.emit.write();
             let mut buf = emit.err.buffer();
@@ -141,6 +163,7 @@ pub fn run_lexer(
             emit.err.emit(&buf)?;
             Ok(())
         })?;
+    drop(reports);
 
     let tokens = tokens
         .iter()
@@ -160,13 +183,60 @@ pub fn run_lexer(
     Ok(())
 }
 
-pub fn lexer_repl(gcx: &Arc<GlobalCtxt>, ignore_ws: bool) -> CalResult<()> {
+/// Run the lexer, printing tokens and diagnostics as records in a
+/// machine-readable format. Diagnostics are reported while lexing the token
+/// they are about, so they are placed just before that token.
+pub fn run_lexer_records(
+    gcx: &Arc<GlobalCtxt>,
+    ignore_ws: bool,
+    format: Format,
+    file_id: usize,
+) -> CalResult<()> {
+    let sourcemgr = gcx.sourcemgr.read();
+    let source = sourcemgr.source(file_id).unwrap();
+
+    let mut lexer = Lexer::new(file_id, source, Arc::clone(gcx));
+    let printer = Printer::new(file_id, Arc::clone(gcx));
+    let mut records = Vec::new();
+    // Diagnostics from earlier inputs in the REPL have already been printed.
+    let (mut n_errors, mut n_nonfatals) = {
+        let reports = gcx.grcx.read();
+        (reports.errors().len(), reports.nonfatals().len())
+    };
+    loop {
+        let token = lexer.scan();
+
+        let reports = gcx.grcx.read();
+        for diag in reports.errors()[n_errors..]
+            .iter()
+            .chain(reports.nonfatals()[n_nonfatals..].iter())
+        {
+            records.extend(printer.diagnostic_records(diag)?);
+        }
+        n_errors = reports.errors().len();
+        n_nonfatals = reports.nonfatals().len();
+
+        let token_ty = token.value().0;
+        if !ignore_ws || token_ty != TokenType::Ws {
+            records.push(printer.token_record(&token)?);
+        }
+        if token_ty == TokenType::Eof {
+            break;
+        }
+    }
+
+    println!("{}", record::print_records(&records, format)?);
+
+    Ok(())
+}
+
+pub fn lexer_repl(gcx: &Arc<GlobalCtxt>, ignore_ws: bool, format: Format) -> CalResult<()> {
     struct ReplCtx {}
 
     let repl_gcx = Arc::clone(gcx);
     let mut repl = Repl::new(
         Box::new(move |_ctx, contents| {
-            run_lexer(&repl_gcx, ignore_ws, "<repl>".to_string(), contents)
+            run_lexer(&repl_gcx, ignore_ws, format, "<repl>".to_string(), contents)
                 .ok()
                 .map(|_| String::new())
         }),
@@ -184,6 +254,104 @@ pub fn lexer_repl(gcx: &Arc<GlobalCtxt>, ignore_ws: bool) -> CalResult<()> {
     Ok(())
 }
 
+pub fn run_parser(
+    gcx: &Arc<GlobalCtxt>,
+    raw: bool,
+    pratt: bool,
+    file_name: String,
+    contents: String,
+) -> CalResult<()> {
+    let file_id = gcx.sourcemgr.write().add(file_name, contents);
+    let sourcemgr = gcx.sourcemgr.read();
+    let source = sourcemgr.source(file_id).unwrap();
+
+    // Diagnostics from earlier inputs in the REPL have already been printed.
+    let (n_errors, n_nonfatals) = {
+        let reports = gcx.grcx.read();
+        (reports.errors().len(), reports.nonfatals().len())
+    };
+    let block = if pratt {
+        Ok(pratt::parse_program(file_id, source, gcx))
+    } else {
+        parse_program(file_id, source, gcx)
+    };
+    let printed = block.map(|block| print_ast(&block, raw, |printer| printer.visit_block(&block)));
+
+    let reports = gcx.grcx.read();
+    reports.errors()[n_errors..]
+        .iter()
+        .chain(reports.nonfatals()[n_nonfatals..].iter())
+        .try_for_each(|e| -> CalResult<()> {
+            let mut emit = gcx.emit.write();
+            let mut buf = emit.err.buffer();
+            e.render(&mut buf, &sourcemgr, None)?;
+            emit.err.emit(&buf)?;
+            Ok(())
+        })?;
+    drop(reports);
+
+    match printed {
+        Ok(printed) => println!("{}", printed?),
+        Err(err) => {
+            let fatal = gcx.grcx.write().take_fatal();
+            let mut emit = gcx.emit.write();
+            emit.err
+                .error(None, "while parsing input:", None)?
+                .flush()?;
+            match fatal {
+                Some(fatal) if err.try_downcast_ref::<DiagnosticError>().is_some() => {
+                    let mut buf = emit.err.buffer();
+                    fatal.render(&mut buf, &sourcemgr, None)?;
+                    emit.err.emit(&buf)?;
+                }
+                _ => {
+                    emit.err.error(None, &format!("{}", err), None)?;
+                }
+            }
+            emit.err.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print an AST in its raw representation, or as an S-expression.
+fn print_ast<T: fmt::Debug>(
+    ast: &T,
+    raw: bool,
+    visit: impl FnOnce(&mut PrettyPrinter) -> CalResult<()>,
+) -> CalResult<String> {
+    if raw {
+        return Ok(format!("{:#?}", ast));
+    }
+    let mut printer = PrettyPrinter::default();
+    visit(&mut printer)?;
+    Ok(printer.to_string())
+}
+
+pub fn parser_repl(gcx: &Arc<GlobalCtxt>, raw: bool, pratt: bool) {
+    struct ReplCtx {}
+
+    let repl_gcx = Arc::clone(gcx);
+    let mut repl = Repl::new(
+        Box::new(move |_ctx, contents| {
+            run_parser(&repl_gcx, raw, pratt, "<repl>".to_string(), contents)
+                .ok()
+                .map(|()| String::new())
+        }),
+        ReplCtx {},
+    )
+    .prefix("\\".to_string());
+    repl.run(
+        &format!(
+            "Calypso CLI v{} - internal debugging command: parser",
+            BUILD_INFO.version
+        ),
+        |_| String::from(">>> "),
+    )
+    .expect("REPL failure");
+}
+
 pub fn buildinfo(gcx: &Arc<GlobalCtxt>) -> CalResult<()> {
     let mut bi = BUILD_INFO;
 